@@ -4,7 +4,9 @@ import type { CurrentUserPrompt } from "./user_prompt/CurrentUserPrompt";
 import type { DevMode } from "./dev_mode/DevMode";
 import type { DisplayCredential } from "./credentials/DisplayCredential";
 import type { HistoryEvent } from "./history/HistoryEvent";
+import type { HolderKeys } from "./did/HolderKeys";
+import type { PendingCredential } from "./credentials/PendingCredential";
 import type { ProfileSettings } from "./profile_settings/ProfileSettings";
 import type { SearchResults } from "./search/SearchResults";
 
-export interface AppState { dids: Record<string, string>, connections: Connections, credentials: Array<DisplayCredential>, search_results: SearchResults, profile_settings: ProfileSettings, current_user_prompt: CurrentUserPrompt | null, user_journey: any | null, debug_messages: Array<string>, history: Array<HistoryEvent>, dev_mode: DevMode, }
\ No newline at end of file
+export interface AppState { dids: Record<string, string>, holder_keys: HolderKeys, connections: Connections, credentials: Array<DisplayCredential>, pending_credentials: Array<PendingCredential>, search_results: SearchResults, profile_settings: ProfileSettings, current_user_prompt: CurrentUserPrompt | null, user_journey: any | null, debug_messages: Array<string>, history: Array<HistoryEvent>, dev_mode: DevMode, }
\ No newline at end of file
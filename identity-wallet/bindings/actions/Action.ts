@@ -1,20 +1,27 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 import type { AddRecentSearch } from "./AddRecentSearch";
+import type { AuthorizationCodeReceived } from "./AuthorizationCodeReceived";
 import type { CancelUserFlow } from "./CancelUserFlow";
+import type { ChangePassword } from "./ChangePassword";
 import type { CreateNew } from "./CreateNew";
 import type { CredentialOffersSelected } from "./CredentialOffersSelected";
 import type { CredentialsSelected } from "./CredentialsSelected";
 import type { DeleteCredential } from "./DeleteCredential";
 import type { DeleteRecentSearch } from "./DeleteRecentSearch";
 import type { DevProfile } from "./DevProfile";
+import type { ExportBackup } from "./ExportBackup";
+import type { ImportBackup } from "./ImportBackup";
 import type { QrCodeScanned } from "./QrCodeScanned";
+import type { RefreshCredential } from "./RefreshCredential";
+import type { RefreshTrustLists } from "./RefreshTrustLists";
 import type { SearchQuery } from "./SearchQuery";
 import type { SetLocale } from "./SetLocale";
 import type { SetPreferredDidMethod } from "./SetPreferredDidMethod";
 import type { SetPreferredKeyType } from "./SetPreferredKeyType";
+import type { SetUpDidWeb } from "./SetUpDidWeb";
 import type { UnlockStorage } from "./UnlockStorage";
 import type { UpdateCredentialMetadata } from "./UpdateCredentialMetadata";
 import type { UpdateProfileSettings } from "./UpdateProfileSettings";
 import type { UpdateSortingPreference } from "./UpdateSortingPreference";
 
-export type Action = { "type": "[App] Get state" } | { "type": "[Storage] Unlock", payload: UnlockStorage, } | { "type": "[App] Reset" } | { "type": "[DID] Create new", payload: CreateNew, } | { "type": "[Settings] Set locale", payload: SetLocale, } | { "type": "[Settings] Update profile", payload: UpdateProfileSettings, } | { "type": "[QR Code] Scanned", payload: QrCodeScanned, } | { "type": "[Authenticate] Connection accepted" } | { "type": "[User Flow] Cancel", payload?: CancelUserFlow, } | { "type": "[DEV] Load DEV profile", payload: DevProfile, } | { "type": "[DEV] Toggle DEV mode" } | { "type": "[Authenticate] Credentials selected", payload: CredentialsSelected, } | { "type": "[Credential Offer] Selected", payload: CredentialOffersSelected, } | { "type": "[Credential Metadata] Update", payload: UpdateCredentialMetadata, } | { "type": "[Credential] Delete", payload: DeleteCredential, } | { "type": "[User Journey] Cancel" } | { "type": "[Settings] Update sorting preference", payload: UpdateSortingPreference, } | { "type": "[Search] Query", payload: SearchQuery, } | { "type": "[Search] Add recent", payload: AddRecentSearch, } | { "type": "[Search] Delete recent", payload: DeleteRecentSearch, } | { "type": "[DID] Set preferred method", payload: SetPreferredDidMethod, } | { "type": "[Keys] Set preferred key type", payload: SetPreferredKeyType, };
\ No newline at end of file
+export type Action = { "type": "[App] Get state" } | { "type": "[Storage] Unlock", payload: UnlockStorage, } | { "type": "[Storage] Change password", payload: ChangePassword, } | { "type": "[Backup] Export", payload: ExportBackup, } | { "type": "[Backup] Import", payload: ImportBackup, } | { "type": "[App] Reset" } | { "type": "[DID] Create new", payload: CreateNew, } | { "type": "[Settings] Set locale", payload: SetLocale, } | { "type": "[Settings] Update profile", payload: UpdateProfileSettings, } | { "type": "[QR Code] Scanned", payload: QrCodeScanned, } | { "type": "[Authenticate] Connection accepted" } | { "type": "[User Flow] Cancel", payload?: CancelUserFlow, } | { "type": "[DEV] Load DEV profile", payload: DevProfile, } | { "type": "[DEV] Toggle DEV mode" } | { "type": "[Authenticate] Credentials selected", payload: CredentialsSelected, } | { "type": "[Authenticate] Presentation declined" } | { "type": "[Credential Offer] Selected", payload: CredentialOffersSelected, } | { "type": "[Credential Offer] Authorization code received", payload: AuthorizationCodeReceived, } | { "type": "[Credential Metadata] Update", payload: UpdateCredentialMetadata, } | { "type": "[Credential] Delete", payload: DeleteCredential, } | { "type": "[Credential] Poll deferred" } | { "type": "[Credential] Refresh", payload: RefreshCredential, } | { "type": "[Credential] Refresh statuses" } | { "type": "[User Journey] Cancel" } | { "type": "[Settings] Update sorting preference", payload: UpdateSortingPreference, } | { "type": "[Search] Query", payload: SearchQuery, } | { "type": "[Search] Add recent", payload: AddRecentSearch, } | { "type": "[Search] Delete recent", payload: DeleteRecentSearch, } | { "type": "[DID] Set preferred method", payload: SetPreferredDidMethod, } | { "type": "[DID] Set up did:web", payload: SetUpDidWeb, } | { "type": "[Keys] Set preferred key type", payload: SetPreferredKeyType, } | { "type": "[Keys] Rotate" } | { "type": "[Connections] Revalidate domains" } | { "type": "[Trust] Refresh trust lists", payload?: RefreshTrustLists, };
\ No newline at end of file
@@ -1,3 +1,3 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 
-export interface CredentialOffersSelected { credential_configuration_ids: Array<string>, }
\ No newline at end of file
+export interface CredentialOffersSelected { credential_configuration_ids: Array<string>, tx_code?: string, }
\ No newline at end of file
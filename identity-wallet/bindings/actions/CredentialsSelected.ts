@@ -1,3 +1,3 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 
-export interface CredentialsSelected { credential_uuids: Array<string>, }
\ No newline at end of file
+export interface CredentialsSelected { credential_uuids: Array<string>, input_descriptor_selection?: Record<string, string>, }
\ No newline at end of file
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { TrustListSource } from "../connections/TrustListSource";
+
+export interface RefreshTrustLists { trust_lists?: Array<TrustListSource>, }
\ No newline at end of file
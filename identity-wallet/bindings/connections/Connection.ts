@@ -1,3 +1,5 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { DomainValidation } from "./DomainValidation";
+import type { TrustedRole } from "./TrustedRole";
 
-export interface Connection { id: string, name: string, url: string, did?: string, verified: boolean, first_interacted: string, last_interacted: string, }
\ No newline at end of file
+export interface Connection { id: string, name: string, url: string, did?: string, verified: boolean, first_interacted: string, last_interacted: string, domain_validation: DomainValidation, key_index: number, roles: Array<TrustedRole>, }
\ No newline at end of file
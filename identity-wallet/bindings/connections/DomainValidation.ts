@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ValidationResult } from "../user_prompt/ValidationResult";
+
+export interface DomainValidation { result: ValidationResult, did?: string, origin?: string, validated_at?: string, did_configuration?: string, }
\ No newline at end of file
@@ -1,3 +1,5 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { CredentialStatus } from "./CredentialStatus";
+import type { CredentialVerification } from "./CredentialVerification";
 
-export interface CredentialMetadata { is_favorite: boolean, date_added: string, date_issued: string, }
\ No newline at end of file
+export interface CredentialMetadata { is_favorite: boolean, date_added: string, date_issued: string, date_expires?: string, is_expired: boolean, expires_soon: boolean, verification: CredentialVerification, status: CredentialStatus, key_index: number, key_retired: boolean, }
\ No newline at end of file
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { RevocationStatus } from "./RevocationStatus";
+
+export interface CredentialStatus { status: RevocationStatus, last_checked?: string, }
\ No newline at end of file
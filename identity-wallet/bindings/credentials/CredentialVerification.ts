@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ValidationResult } from "../user_prompt/ValidationResult";
+
+export interface CredentialVerification { signature: ValidationResult, validity_period: ValidationResult, subject: ValidationResult, }
\ No newline at end of file
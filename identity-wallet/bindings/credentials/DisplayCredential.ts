@@ -1,4 +1,5 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { CredentialFormat } from "./CredentialFormat";
 import type { CredentialMetadata } from "./CredentialMetadata";
 
-export interface DisplayCredential { id: string, issuer_name: string, data: any, metadata: CredentialMetadata, connection_id?: string, display_name: string, }
\ No newline at end of file
+export interface DisplayCredential { id: string, issuer_name: string, data: any, metadata: CredentialMetadata, connection_id?: string, display_name: string, format: CredentialFormat, }
\ No newline at end of file
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export interface PendingCredential { id: string, issuer_name: string, display_name: string, connection_id?: string, date_requested: string, }
\ No newline at end of file
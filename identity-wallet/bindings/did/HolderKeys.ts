@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { RetiredKeys } from "./RetiredKeys";
+
+export interface HolderKeys { active_index: number, retired: Array<RetiredKeys>, }
\ No newline at end of file
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export interface RetiredKeys { index: number, rotated_at: string, usable_until: string, pairwise: boolean, }
\ No newline at end of file
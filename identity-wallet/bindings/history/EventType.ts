@@ -1,3 +1,3 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 
-export type EventType = "ConnectionAdded" | "CredentialsAdded" | "CredentialsShared";
\ No newline at end of file
+export type EventType = "ConnectionAdded" | "CredentialsAdded" | "CredentialsShared" | "CredentialsRefreshed" | "RequestDeclined" | "KeysRotated";
\ No newline at end of file
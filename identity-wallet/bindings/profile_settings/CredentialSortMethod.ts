@@ -1,3 +1,3 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 
-export type CredentialSortMethod = "name_az" | "issue_date_new_old" | "added_date_new_old";
\ No newline at end of file
+export type CredentialSortMethod = "name_az" | "issue_date_new_old" | "added_date_new_old" | "expiry_date_soonest";
\ No newline at end of file
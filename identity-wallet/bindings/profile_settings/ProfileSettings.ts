@@ -2,5 +2,6 @@
 import type { Locale } from "./Locale";
 import type { Profile } from "./Profile";
 import type { SortingPreferences } from "./SortingPreferences";
+import type { TrustListSource } from "../connections/TrustListSource";
 
-export interface ProfileSettings { locale: Locale, profile: Profile | null, preferred_did_methods: Array<string>, preferred_key_types: Array<string>, sorting_preferences: SortingPreferences, }
\ No newline at end of file
+export interface ProfileSettings { locale: Locale, profile: Profile | null, preferred_did_methods: Array<string>, preferred_key_types: Array<string>, sorting_preferences: SortingPreferences, trust_lists: Array<TrustListSource>, domain_validation_ttl_hours: number, key_rotation_grace_period_days: number, }
\ No newline at end of file
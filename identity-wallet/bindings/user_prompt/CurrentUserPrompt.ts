@@ -1,4 +1,7 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { InputDescriptorOptions } from "./InputDescriptorOptions";
+import type { RequestedInputDescriptor } from "./RequestedInputDescriptor";
+import type { TransactionCode } from "./TransactionCode";
 import type { ValidationResult } from "./ValidationResult";
 
-export type CurrentUserPrompt = { "type": "redirect", target: string, } | { "type": "password-required" } | { "type": "accept-connection", client_name: string, logo_uri?: string, redirect_uri: string, previously_connected: boolean, domain_validation: ValidationResult, } | { "type": "credential-offer", issuer_name: string, logo_uri?: string, credential_configurations: Record<string, any>, } | { "type": "share-credentials", client_name: string, logo_uri?: string, options: Array<string>, };
\ No newline at end of file
+export type CurrentUserPrompt = { "type": "redirect", target: string, } | { "type": "password-required" } | { "type": "accept-connection", client_name: string, logo_uri?: string, redirect_uri: string, previously_connected: boolean, domain_validation: ValidationResult, client_id_validation: ValidationResult, client_metadata_error?: string, } | { "type": "credential-offer", issuer_name: string, logo_uri?: string, credential_configurations: Record<string, any>, tx_code?: TransactionCode, domain_validation: ValidationResult, trust: ValidationResult, } | { "type": "authorize", issuer_name: string, logo_uri?: string, authorization_url: string, } | { "type": "share-credentials", client_name: string, logo_uri?: string, options: Array<string>, revoked: Array<string>, input_descriptors: Array<InputDescriptorOptions>, domain_validation: ValidationResult, client_id_validation: ValidationResult, trust: ValidationResult, client_metadata_error?: string, } | { "type": "no-matching-credentials", client_name: string, logo_uri?: string, requested: Array<RequestedInputDescriptor>, client_metadata_error?: string, } | { "type": "recovery-phrase", words: Array<string>, };
\ No newline at end of file
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { TransactionCodeInputMode } from "./TransactionCodeInputMode";
+
+export interface TransactionCode { input_mode: TransactionCodeInputMode, length?: number, description?: string, }
\ No newline at end of file
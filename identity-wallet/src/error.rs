@@ -42,6 +42,24 @@ pub enum AppError {
     InvalidCredentialOffer(#[source] serde_json::Error),
     #[error("Could not find a matching credential for input descriptor")]
     NoMatchingCredentialError,
+    #[error("No active authorization request")]
+    MissingAuthorizationRequestError,
+    #[error("Failed to fetch client metadata from {0}")]
+    GetClientMetadataError(String, #[source] anyhow::Error),
+    #[error("The client could not be authenticated: {0}")]
+    ClientIdSchemeValidationError(String),
+    #[error("Failed to load the trust list from {0}")]
+    LoadTrustListError(String, #[source] anyhow::Error),
+    #[error("Unsupported response encryption algorithm: {0}")]
+    UnsupportedResponseEncryptionError(String),
+    #[error("Failed to encrypt the authorization response: {0}")]
+    ResponseEncryptionError(&'static str),
+    #[error("Invalid submission requirements in presentation definition")]
+    InvalidSubmissionRequirementsError,
+    #[error("The selected credential does not match input descriptor: {0}")]
+    InvalidCredentialSelectionError(String),
+    #[error("The selected credentials do not satisfy the submission requirements")]
+    SubmissionRequirementsNotSatisfiedError,
     #[error("Failed to generate authorization response")]
     GenerateAuthorizationResponseError(#[source] anyhow::Error),
     #[error("Failed to send authorization response")]
@@ -52,8 +70,56 @@ pub enum AppError {
     PresentationSubmissionError(#[source] anyhow::Error),
     #[error("Failed to parse DID")]
     DidParseError,
+    #[error("Unsupported DID method: {0}")]
+    UnsupportedDidMethodError(String),
+    #[error("Unsupported key type: {0}")]
+    UnsupportedKeyTypeError(String),
+    #[error("Invalid domain for a `did:web` DID: {0}")]
+    InvalidDidWebDomainError(String),
+    #[error("No `did:web` DID has been set up")]
+    MissingDidWebError,
+    #[error("Failed to produce the `did:web` DID document")]
+    DidWebDocumentError(#[source] anyhow::Error),
+    #[error("Failed to activate the holder keys")]
+    KeyActivationError(#[source] anyhow::Error),
+    #[error("The selected credentials are bound to different holder keys and cannot be presented together")]
+    MixedHolderKeysError,
+    #[error("The holder keys at index {0} have been replaced by a rotation and their grace period has ended")]
+    RetiredHolderKeysError(u32),
+    #[error("Failed to rotate the holder keys")]
+    KeyRotationError(#[source] anyhow::Error),
     #[error("Invalid credential format")]
     InvalidCredentialFormatError,
+    #[error("Invalid SD-JWT: {0}")]
+    InvalidSdJwtError(&'static str),
+    #[error("Failed to create the SD-JWT Key Binding JWT")]
+    KeyBindingJwtError(#[source] anyhow::Error),
+    #[error("Invalid mdoc: {0}")]
+    InvalidMdocError(&'static str),
+    #[error("The digest of mdoc element `{0}` does not match the Mobile Security Object")]
+    MdocDigestMismatchError(String),
+    #[error("Failed to create the mdoc device signature")]
+    DeviceSignatureError(#[source] anyhow::Error),
+    #[error("The credential could not be verified: {0}")]
+    CredentialVerificationError(String),
+    #[error("Unsupported Data Integrity proof: `{0}`")]
+    UnsupportedProofError(String),
+    #[error("The Data Integrity proof of the credential is invalid")]
+    InvalidProofError,
+    #[error("Failed to create Data Integrity proof")]
+    DataIntegrityProofError(#[source] anyhow::Error),
+    #[error("Failed to canonicalize JSON-LD document")]
+    JsonLdCanonicalizationError(#[source] anyhow::Error),
+    #[error("Failed to retrieve the status list credential")]
+    GetStatusListError(#[source] anyhow::Error),
+    #[error("Invalid status list: {0}")]
+    InvalidStatusListError(&'static str),
+    #[error("Credentials of different formats cannot be shared in a single presentation")]
+    MixedCredentialFormatsError,
+    #[error("The verifier does not support the `{0}` credential format")]
+    UnsupportedVpFormatError(&'static str),
+    #[error("An mdoc presentation can only be sent using the `direct_post.jwt` response mode")]
+    UnsupportedMdocResponseModeError,
     #[error("Failed to build verifiable presentation")]
     PresentationBuilderError(#[source] identity_credential::error::Error),
     #[error("Failed to retrieve credential offer from the credential issuer")]
@@ -62,12 +128,32 @@ pub enum AppError {
     GetAuthorizationServerMetadataError(#[source] anyhow::Error),
     #[error("Failed to retrieve the credential issuer's metadata")]
     GetCredentialIssuerMetadataError(#[source] anyhow::Error),
+    #[error("Failed to find the `authorization_endpoint` in the authorization server's metadata")]
+    MissingAuthorizationEndpointError,
+    #[error("The `state` parameter of the authorization response does not match the authorization request")]
+    AuthorizationStateMismatchError,
+    #[error("A transaction code is required to accept this credential offer")]
+    MissingTransactionCodeError,
+    #[error("The transaction code is invalid")]
+    InvalidTransactionCodeError(#[source] anyhow::Error),
     #[error("Failed to retrieve an access token from the credential issuer")]
     GetAccessTokenError(#[source] anyhow::Error),
     #[error("Failed to retrieve credential from the credential issuer")]
     GetCredentialError(#[source] anyhow::Error),
     #[error("Failed to retrieve batch credentials from the credential issuer")]
     GetBatchCredentialError(#[source] anyhow::Error),
+    #[error("Failed to find the `deferred_credential_endpoint` in the credential issuer's metadata")]
+    MissingDeferredCredentialEndpointError,
+    #[error("Failed to retrieve deferred credential from the credential issuer")]
+    GetDeferredCredentialError(#[source] anyhow::Error),
+    #[error("The credential can neither be refreshed with a refresh token nor through a refresh service")]
+    CredentialRefreshUnavailableError,
+    #[error("Unsupported refresh service")]
+    UnsupportedRefreshServiceError,
+    #[error("The credential issuer deferred the issuance of the refreshed credential")]
+    DeferredCredentialRefreshError,
+    #[error("The refreshed credential differs in issuer or type from the credential it would replace")]
+    RefreshedCredentialMismatchError,
     #[error("Failed to find credential offer `{0}` in the credential issuer's metadata")]
     MissingCredentialOfferError(String),
     #[error("No credential configuration available with ID {0}` in the credential issuer's metadata")]
@@ -80,6 +166,18 @@ pub enum AppError {
     StrongholdCreationError(#[source] anyhow::Error),
     #[error("Failed to load stronghold")]
     StrongholdLoadingError(#[source] anyhow::Error),
+    #[error("The password is incorrect")]
+    IncorrectPasswordError,
+    #[error("Failed to change the password of the stronghold")]
+    StrongholdPasswordChangeError(#[source] anyhow::Error),
+    #[error("Failed to export or import the backup")]
+    BackupError(#[source] anyhow::Error),
+    #[error("The passphrase or recovery phrase of the backup is incorrect")]
+    IncorrectBackupPassphraseError,
+    #[error("The backup has been created by a newer version (schema version {0}) and cannot be imported")]
+    UnsupportedBackupVersionError(u64),
+    #[error("A backup can only be imported when there is no profile yet")]
+    ExistingProfileError,
     #[error("Failed to delete credential from stronghold")]
     StrongholdDeletionError(#[source] anyhow::Error),
     #[error("Failed to insert credential into stronghold")]
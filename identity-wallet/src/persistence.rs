@@ -1,8 +1,15 @@
-use crate::{error::AppError, state::AppState};
+use crate::{
+    error::AppError,
+    state::{user_prompt::CurrentUserPrompt, AppState},
+};
 use lazy_static::lazy_static;
 use log::info;
 use log::{debug, warn};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::ffi::OsStr;
 use std::io::{copy, Cursor};
+use std::path::Path;
 use std::{fs, sync::Mutex};
 use tauri::Manager;
 use tokio::{
@@ -18,6 +25,7 @@ lazy_static! {
     pub static ref STATE_FILE: Mutex<std::path::PathBuf> = Mutex::new(std::path::PathBuf::new());
     pub static ref STRONGHOLD: Mutex<std::path::PathBuf> = Mutex::new(std::path::PathBuf::new());
     pub static ref ASSETS_DIR: Mutex<std::path::PathBuf> = Mutex::new(std::path::PathBuf::new());
+    pub static ref TRUST_STORE_DIR: Mutex<std::path::PathBuf> = Mutex::new(std::path::PathBuf::new());
 }
 
 pub const SUPPORTED_IMAGE_ASSET_EXTENSIONS: [&str; 2] = ["svg", "png"];
@@ -29,6 +37,7 @@ pub fn initialize_storage(app_handle: &tauri::AppHandle) -> anyhow::Result<()> {
         *STATE_FILE.lock().unwrap() = app_handle.path().data_dir()?.join("state.json");
         *STRONGHOLD.lock().unwrap() = app_handle.path().data_dir()?.join("stronghold.bin");
         *ASSETS_DIR.lock().unwrap() = app_handle.path().data_dir()?.join("assets");
+        *TRUST_STORE_DIR.lock().unwrap() = app_handle.path().data_dir()?.join("trust_store");
     } else {
         *STATE_FILE.lock().unwrap() = app_handle
             .path()
@@ -41,6 +50,11 @@ pub fn initialize_storage(app_handle: &tauri::AppHandle) -> anyhow::Result<()> {
             .join("com.impierce.unime")
             .join("stronghold.bin");
         *ASSETS_DIR.lock().unwrap() = app_handle.path().data_dir()?.join("com.impierce.unime").join("assets");
+        *TRUST_STORE_DIR.lock().unwrap() = app_handle
+            .path()
+            .data_dir()?
+            .join("com.impierce.unime")
+            .join("trust_store");
     }
     info!("STATE_FILE: {}", STATE_FILE.lock().unwrap().display());
     info!("STRONGHOLD: {}", STRONGHOLD.lock().unwrap().display());
@@ -50,6 +64,12 @@ pub fn initialize_storage(app_handle: &tauri::AppHandle) -> anyhow::Result<()> {
         Err(e) => info!("ASSETS_DIR: {}", e),
     };
 
+    // Certificates of trusted verifiers (and verifier attestation issuers) can be placed in this directory.
+    match fs::create_dir_all(TRUST_STORE_DIR.lock().unwrap().as_path()) {
+        Ok(_) => info!("TRUST_STORE_DIR: created"),
+        Err(e) => info!("TRUST_STORE_DIR: {}", e),
+    };
+
     Ok(())
 }
 
@@ -71,12 +91,21 @@ pub async fn save_state(app_state: &AppState) -> anyhow::Result<()> {
     let state_file = STATE_FILE.lock().unwrap().clone();
     let mut file = File::create(state_file).await?;
 
+    // The recovery phrase of a backup is only shown to the user, it is never written to disk.
+    let app_state = match app_state.current_user_prompt {
+        Some(CurrentUserPrompt::RecoveryPhrase { .. }) => Cow::Owned(AppState {
+            current_user_prompt: None,
+            ..app_state.clone()
+        }),
+        _ => Cow::Borrowed(app_state),
+    };
+
     // Here we take out the credentials field before saving the state,
     // being sensitive data they should only be stored in the stronghold, nowhere else.
     let mut json_app_state = serde_json::to_value(app_state)?;
     json_app_state["credentials"] = serde_json::Value::Array(Vec::new());
 
-    file.write_all(serde_json::to_string(app_state)?.as_bytes()).await?;
+    file.write_all(serde_json::to_string(&app_state)?.as_bytes()).await?;
     debug!("state saved to disk");
     Ok(())
 }
@@ -194,8 +223,105 @@ pub fn persist_asset(file_name: &str, id: &str) -> Result<(), AppError> {
     Ok(())
 }
 
+/// Reads the files in the `/assets` folder (not the `/assets/tmp` folder) by their file name, e.g. to include them in
+/// a backup.
+pub fn read_assets() -> anyhow::Result<BTreeMap<String, Vec<u8>>> {
+    let assets_dir = ASSETS_DIR.lock().unwrap().as_path().to_owned();
+
+    let mut assets = BTreeMap::new();
+    if !assets_dir.exists() {
+        return Ok(assets);
+    }
+    for entry in fs::read_dir(assets_dir)? {
+        let entry = entry?;
+        if entry.file_type()?.is_file() {
+            let file_name = entry
+                .file_name()
+                .into_string()
+                .map_err(|file_name| anyhow::anyhow!("invalid asset file name: {file_name:?}"))?;
+            assets.insert(file_name, fs::read(entry.path())?);
+        }
+    }
+
+    Ok(assets)
+}
+
+/// Replaces the contents of the `/assets` folder by the given files, e.g. from a backup.
+pub fn restore_assets(assets: &BTreeMap<String, Vec<u8>>) -> anyhow::Result<()> {
+    // Only plain file names are accepted, so no files can be written outside of the `/assets` folder.
+    if let Some(file_name) = assets
+        .keys()
+        .find(|file_name| Path::new(file_name).file_name() != Some(OsStr::new(file_name)))
+    {
+        return Err(anyhow::anyhow!("invalid asset file name: {file_name}"));
+    }
+
+    clear_all_assets()?;
+    let assets_dir = ASSETS_DIR.lock().unwrap().as_path().to_owned();
+    fs::create_dir_all(&assets_dir)?;
+    for (file_name, bytes) in assets {
+        fs::write(assets_dir.join(file_name), bytes)?;
+    }
+    debug!("Successfully restored {} assets.", assets.len());
+
+    Ok(())
+}
+
+/// Writes the DID document of the holder's `did:web` DID to `/assets/did.json`, from where it can be exported and
+/// hosted by the holder.
+pub async fn save_did_web_document(document: &serde_json::Value) -> anyhow::Result<std::path::PathBuf> {
+    let path = ASSETS_DIR.lock().unwrap().join("did.json");
+    let mut file = File::create(&path).await?;
+
+    file.write_all(serde_json::to_string_pretty(document)?.as_bytes())
+        .await?;
+    debug!("did:web document saved to disk");
+    Ok(path)
+}
+
 /// Hashes a given URL string to a unique SHA-256 string.
 /// Used for temporary asset file names in `/assets/tmp` to prevent unintended frontend image caching.
 pub fn hash(url: &str) -> String {
     sha256::digest(url).to_string()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    #[serial_test::serial]
+    fn restore_assets_replaces_the_assets() {
+        let assets_dir = TempDir::new().unwrap();
+        *ASSETS_DIR.lock().unwrap() = assets_dir.path().to_path_buf();
+        fs::write(assets_dir.path().join("old.png"), b"old").unwrap();
+
+        let assets = BTreeMap::from([("new.png".to_string(), b"new".to_vec())]);
+        restore_assets(&assets).unwrap();
+
+        assert_eq!(read_assets().unwrap(), assets);
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn restore_assets_only_accepts_plain_file_names() {
+        let parent_dir = TempDir::new().unwrap();
+        let assets_dir = parent_dir.path().join("assets");
+        fs::create_dir(&assets_dir).unwrap();
+        *ASSETS_DIR.lock().unwrap() = assets_dir.clone();
+        fs::write(assets_dir.join("old.png"), b"old").unwrap();
+
+        for file_name in ["../evil.png", "tmp/evil.png", "/evil.png", "..", ""] {
+            let assets = BTreeMap::from([(file_name.to_string(), b"evil".to_vec())]);
+            assert!(restore_assets(&assets).is_err(), "{file_name:?} is accepted");
+        }
+
+        // Nothing has been written or removed.
+        assert!(!parent_dir.path().join("evil.png").exists());
+        assert_eq!(
+            read_assets().unwrap(),
+            BTreeMap::from([("old.png".to_string(), b"old".to_vec())])
+        );
+    }
+}
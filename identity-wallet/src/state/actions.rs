@@ -65,13 +65,22 @@ mod bindings {
     use ts_rs::TS;
 
     use crate::state::{
-        common::actions::{cancel_user_flow::CancelUserFlow, unlock_storage::UnlockStorage},
+        backup::actions::{export_backup::ExportBackup, import_backup::ImportBackup},
+        common::actions::{
+            cancel_user_flow::CancelUserFlow, change_password::ChangePassword, unlock_storage::UnlockStorage,
+        },
+        connections::actions::refresh_trust_lists::RefreshTrustLists,
         credentials::actions::{
+            authorization_code_received::AuthorizationCodeReceived,
             credential_offers_selected::CredentialOffersSelected, credentials_selected::CredentialsSelected,
-            delete_credential::DeleteCredential, update_credential_metadata::UpdateCredentialMetadata,
+            delete_credential::DeleteCredential, refresh_credential::RefreshCredential,
+            update_credential_metadata::UpdateCredentialMetadata,
         },
         dev_mode::actions::dev_profile::DevProfile,
-        did::actions::{set_preferred_keytype::SetPreferredKeyType, set_preferred_method::SetPreferredDidMethod},
+        did::actions::{
+            rotate_keys::RotateKeys, set_preferred_keytype::SetPreferredKeyType,
+            set_preferred_method::SetPreferredDidMethod, set_up_did_web::SetUpDidWeb,
+        },
         profile_settings::actions::{
             create_new::CreateNew, set_locale::SetLocale, update_profile_settings::UpdateProfileSettings,
             update_sorting_preference::UpdateSortingPreference,
@@ -90,6 +99,12 @@ mod bindings {
         GetState,
         #[serde(rename = "[Storage] Unlock")]
         UnlockStorage { payload: UnlockStorage },
+        #[serde(rename = "[Storage] Change password")]
+        ChangePassword { payload: ChangePassword },
+        #[serde(rename = "[Backup] Export")]
+        ExportBackup { payload: ExportBackup },
+        #[serde(rename = "[Backup] Import")]
+        ImportBackup { payload: ImportBackup },
         #[serde(rename = "[App] Reset")]
         Reset,
         #[serde(rename = "[DID] Create new")]
@@ -113,12 +128,22 @@ mod bindings {
         ToggleDevMode,
         #[serde(rename = "[Authenticate] Credentials selected")]
         CredentialsSelected { payload: CredentialsSelected },
+        #[serde(rename = "[Authenticate] Presentation declined")]
+        PresentationDeclined,
         #[serde(rename = "[Credential Offer] Selected")]
         CredentialOffersSelected { payload: CredentialOffersSelected },
+        #[serde(rename = "[Credential Offer] Authorization code received")]
+        AuthorizationCodeReceived { payload: AuthorizationCodeReceived },
         #[serde(rename = "[Credential Metadata] Update")]
         UpdateCredentialMetadata { payload: UpdateCredentialMetadata },
         #[serde(rename = "[Credential] Delete")]
         DeleteCredential { payload: DeleteCredential },
+        #[serde(rename = "[Credential] Poll deferred")]
+        PollDeferredCredentials,
+        #[serde(rename = "[Credential] Refresh")]
+        RefreshCredential { payload: RefreshCredential },
+        #[serde(rename = "[Credential] Refresh statuses")]
+        RefreshCredentialStatuses,
         #[serde(rename = "[User Journey] Cancel")]
         CancelUserJourney,
         #[serde(rename = "[Settings] Update sorting preference")]
@@ -131,7 +156,18 @@ mod bindings {
         DeleteRecentSearch { payload: DeleteRecentSearch },
         #[serde(rename = "[DID] Set preferred method")]
         SetPreferredDidMethod { payload: SetPreferredDidMethod },
+        #[serde(rename = "[DID] Set up did:web")]
+        SetUpDidWeb { payload: SetUpDidWeb },
         #[serde(rename = "[Keys] Set preferred key type")]
         SetPreferredKeyType { payload: SetPreferredKeyType },
+        #[serde(rename = "[Keys] Rotate")]
+        RotateKeys,
+        #[serde(rename = "[Connections] Revalidate domains")]
+        RevalidateDomains,
+        #[serde(rename = "[Trust] Refresh trust lists")]
+        RefreshTrustLists {
+            #[ts(optional)]
+            payload: Option<RefreshTrustLists>,
+        },
     }
 }
@@ -0,0 +1,34 @@
+use crate::{
+    reducer,
+    state::{actions::ActionTrait, backup::reducers::export_backup::export_backup, Reducer},
+};
+
+use serde::{Deserialize, Serialize};
+use std::fmt::Formatter;
+use ts_rs::TS;
+
+/// Action to write an encrypted backup of the wallet to the given path. Without a passphrase, the backup is protected
+/// by a recovery phrase that is shown to the user.
+#[derive(Serialize, Deserialize, TS, Clone)]
+#[ts(export, export_to = "bindings/actions/ExportBackup.ts")]
+pub struct ExportBackup {
+    pub path: String,
+    #[ts(optional)]
+    pub passphrase: Option<String>,
+}
+
+impl std::fmt::Debug for ExportBackup {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ExportBackup")
+            .field("path", &self.path)
+            .field("passphrase", &self.passphrase.as_ref().map(|_| "*****"))
+            .finish()
+    }
+}
+
+#[typetag::serde(name = "[Backup] Export")]
+impl ActionTrait for ExportBackup {
+    fn reducers<'a>(&self) -> Vec<Reducer<'a>> {
+        vec![reducer!(export_backup)]
+    }
+}
@@ -0,0 +1,38 @@
+use crate::{
+    reducer,
+    state::{
+        actions::ActionTrait, backup::reducers::import_backup::import_backup,
+        profile_settings::reducers::update_sorting_preference::sort_credentials, Reducer,
+    },
+};
+
+use serde::{Deserialize, Serialize};
+use std::fmt::Formatter;
+use ts_rs::TS;
+
+/// Action to restore the wallet from an encrypted backup, using the passphrase or recovery phrase of the backup and the
+/// password of the wallet at the time of the backup.
+#[derive(Serialize, Deserialize, TS, Clone)]
+#[ts(export, export_to = "bindings/actions/ImportBackup.ts")]
+pub struct ImportBackup {
+    pub path: String,
+    pub passphrase: String,
+    pub password: String,
+}
+
+impl std::fmt::Debug for ImportBackup {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ImportBackup")
+            .field("path", &self.path)
+            .field("passphrase", &"*****")
+            .field("password", &"*****")
+            .finish()
+    }
+}
+
+#[typetag::serde(name = "[Backup] Import")]
+impl ActionTrait for ImportBackup {
+    fn reducers<'a>(&self) -> Vec<Reducer<'a>> {
+        vec![reducer!(import_backup), reducer!(sort_credentials)]
+    }
+}
@@ -0,0 +1,2 @@
+pub mod export_backup;
+pub mod import_backup;
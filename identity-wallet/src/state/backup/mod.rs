@@ -0,0 +1,227 @@
+pub mod actions;
+pub mod reducers;
+
+use crate::{
+    error::AppError::{self, *},
+    state::{
+        connections::Connections, core_utils::history_event::HistoryEvent, did::holder_keys::HolderKeys,
+        profile_settings::ProfileSettings, AppState,
+    },
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use crypto::keys::{age, bip39};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use rand::{rngs::OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::io::Read;
+
+/// The version of the backup format. Backups of a newer version are refused, since they may contain data that this
+/// version does not know how to restore.
+pub const BACKUP_SCHEMA_VERSION: u32 = 1;
+
+// The work factor of the scrypt key derivation with which a backup is encrypted (64 MiB of memory, which is feasible
+// on phones). Backups with a higher work factor than `MAX_BACKUP_WORK_FACTOR` are not decrypted, so that a crafted
+// backup cannot exhaust the device.
+const BACKUP_WORK_FACTOR: u8 = 16;
+const MAX_BACKUP_WORK_FACTOR: u8 = 18;
+
+// The minimum number of characters of a backup passphrase, which is the minimum length of the wallet password as well
+// (see the password policy of the onboarding).
+const MIN_BACKUP_PASSPHRASE_LENGTH: usize = 8;
+
+/// The non-secret parts of the state that are included in a backup. The credentials are part of the Stronghold
+/// snapshot.
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct BackupState {
+    pub dids: HashMap<String, String>,
+    pub holder_keys: HolderKeys,
+    pub connections: Connections,
+    pub profile_settings: ProfileSettings,
+    pub history: Vec<HistoryEvent>,
+}
+
+impl From<&AppState> for BackupState {
+    fn from(state: &AppState) -> Self {
+        Self {
+            dids: state.dids.clone(),
+            holder_keys: state.holder_keys.clone(),
+            connections: state.connections.clone(),
+            profile_settings: state.profile_settings.clone(),
+            history: state.history.clone(),
+        }
+    }
+}
+
+/// The contents of a backup, before it is compressed and encrypted.
+#[derive(Serialize, Deserialize)]
+pub struct Backup {
+    pub schema_version: u32,
+    pub created_at: String,
+    /// The Stronghold snapshot, which remains encrypted with the password of the wallet (Base64).
+    stronghold: String,
+    pub state: BackupState,
+    /// The files in the `/assets` folder by their file name (Base64).
+    assets: BTreeMap<String, String>,
+}
+
+impl Backup {
+    pub fn new(stronghold: &[u8], state: BackupState, assets: &BTreeMap<String, Vec<u8>>) -> Self {
+        Self {
+            schema_version: BACKUP_SCHEMA_VERSION,
+            created_at: chrono::Utc::now().to_rfc3339(),
+            stronghold: STANDARD.encode(stronghold),
+            state,
+            assets: assets
+                .iter()
+                .map(|(file_name, bytes)| (file_name.clone(), STANDARD.encode(bytes)))
+                .collect(),
+        }
+    }
+
+    pub fn stronghold(&self) -> Result<Vec<u8>, AppError> {
+        STANDARD
+            .decode(&self.stronghold)
+            .map_err(|e| BackupError(anyhow::anyhow!("invalid Stronghold snapshot: {e}")))
+    }
+
+    pub fn assets(&self) -> Result<BTreeMap<String, Vec<u8>>, AppError> {
+        self.assets
+            .iter()
+            .map(|(file_name, bytes)| {
+                STANDARD
+                    .decode(bytes)
+                    .map(|bytes| (file_name.clone(), bytes))
+                    .map_err(|e| BackupError(anyhow::anyhow!("invalid asset `{file_name}`: {e}")))
+            })
+            .collect()
+    }
+}
+
+/// Compresses the backup and encrypts it with the passphrase or recovery phrase, using age with an scrypt recipient
+/// (like the Stronghold snapshot itself).
+pub fn seal(backup: &Backup, passphrase: &str) -> Result<Vec<u8>, AppError> {
+    if passphrase.trim().chars().count() < MIN_BACKUP_PASSPHRASE_LENGTH {
+        return Err(BackupError(anyhow::anyhow!(
+            "the backup passphrase needs to be at least {MIN_BACKUP_PASSPHRASE_LENGTH} characters long"
+        )));
+    }
+
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    serde_json::to_writer(&mut encoder, backup).map_err(|e| BackupError(e.into()))?;
+    let compressed = encoder.finish().map_err(|e| BackupError(e.into()))?;
+
+    age::encrypt_vec(
+        backup_key(passphrase).as_bytes(),
+        age::WorkFactor::new(BACKUP_WORK_FACTOR),
+        &compressed,
+    )
+    .map_err(|e| BackupError(anyhow::anyhow!("failed to encrypt the backup: {e:?}")))
+}
+
+/// Decrypts and decompresses a backup. Backups of a newer schema version than `BACKUP_SCHEMA_VERSION` are refused.
+pub fn open(archive: &[u8], passphrase: &str) -> Result<Backup, AppError> {
+    let compressed =
+        age::decrypt_vec(backup_key(passphrase).as_bytes(), MAX_BACKUP_WORK_FACTOR, archive).map_err(|e| match e {
+            age::DecError::BadFileKey | age::DecError::BadHeaderMac => IncorrectBackupPassphraseError,
+            e => BackupError(anyhow::anyhow!("failed to decrypt the backup: {e:?}")),
+        })?;
+
+    let mut json = Vec::new();
+    GzDecoder::new(compressed.as_slice())
+        .read_to_end(&mut json)
+        .map_err(|e| BackupError(e.into()))?;
+    let backup: serde_json::Value = serde_json::from_slice(&json).map_err(|e| BackupError(e.into()))?;
+
+    // The schema version is checked first, since the rest of a newer backup may not be understood.
+    let schema_version = backup["schema_version"]
+        .as_u64()
+        .ok_or(BackupError(anyhow::anyhow!("the backup has no schema version")))?;
+    if schema_version > BACKUP_SCHEMA_VERSION.into() {
+        return Err(UnsupportedBackupVersionError(schema_version));
+    }
+
+    serde_json::from_value(backup).map_err(|e| BackupError(e.into()))
+}
+
+/// Generates a recovery phrase of 24 words from the BIP39 English word list, which can be used instead of a passphrase
+/// to protect a backup.
+pub fn generate_recovery_phrase() -> Result<String, AppError> {
+    let mut entropy = [0u8; 32];
+    OsRng.fill_bytes(&mut entropy);
+
+    bip39::wordlist::encode(&entropy, &bip39::wordlist::ENGLISH)
+        .map(|mnemonic| mnemonic.as_ref().to_string())
+        .map_err(|e| BackupError(anyhow::anyhow!("failed to generate a recovery phrase: {e:?}")))
+}
+
+// Returns the key with which a backup is encrypted. A valid recovery phrase is normalized, so that it can be entered
+// with different whitespace or capitalization. Any other passphrase is used as it is.
+fn backup_key(passphrase: &str) -> String {
+    let words: Vec<String> = passphrase.split_whitespace().map(str::to_lowercase).collect();
+    let mnemonic = bip39::Mnemonic::from(words);
+
+    match bip39::wordlist::verify(&mnemonic, &bip39::wordlist::ENGLISH) {
+        Ok(()) => mnemonic.as_ref().to_string(),
+        Err(_) => passphrase.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn backup() -> Backup {
+        let assets = BTreeMap::from([("logo.png".to_string(), vec![0x89, 0x50, 0x4e, 0x47])]);
+        Backup::new(b"snapshot", BackupState::default(), &assets)
+    }
+
+    #[test]
+    fn backups_can_be_opened_with_their_passphrase() {
+        let archive = seal(&backup(), "sup3rSecr3t").unwrap();
+
+        let backup = open(&archive, "sup3rSecr3t").unwrap();
+        assert_eq!(backup.schema_version, BACKUP_SCHEMA_VERSION);
+        assert_eq!(backup.stronghold().unwrap(), b"snapshot");
+        assert_eq!(backup.assets().unwrap()["logo.png"], vec![0x89, 0x50, 0x4e, 0x47]);
+
+        assert!(matches!(
+            open(&archive, "wr0ngSecr3t"),
+            Err(IncorrectBackupPassphraseError)
+        ));
+    }
+
+    #[test]
+    fn backups_need_a_passphrase_of_minimum_length() {
+        assert!(matches!(seal(&backup(), ""), Err(BackupError(_))));
+        assert!(matches!(seal(&backup(), " s3cr3t  "), Err(BackupError(_))));
+    }
+
+    #[test]
+    fn backups_can_be_opened_with_their_recovery_phrase() {
+        let recovery_phrase = generate_recovery_phrase().unwrap();
+        assert_eq!(recovery_phrase.split(' ').count(), 24);
+
+        let archive = seal(&backup(), &recovery_phrase).unwrap();
+
+        // The recovery phrase may be entered with different whitespace and capitalization.
+        let entered = format!(" {} ", recovery_phrase.to_uppercase().replace(' ', "\n"));
+        assert!(open(&archive, &entered).is_ok());
+    }
+
+    #[test]
+    fn backups_of_newer_schema_versions_are_refused() {
+        let backup = Backup {
+            schema_version: BACKUP_SCHEMA_VERSION + 1,
+            ..backup()
+        };
+        let archive = seal(&backup, "sup3rSecr3t").unwrap();
+
+        assert!(matches!(
+            open(&archive, "sup3rSecr3t"),
+            Err(UnsupportedBackupVersionError(version)) if version == u64::from(BACKUP_SCHEMA_VERSION + 1)
+        ));
+    }
+}
@@ -0,0 +1,110 @@
+use crate::{
+    error::AppError::{self, *},
+    persistence::{read_assets, STRONGHOLD},
+    state::{
+        actions::{listen, Action},
+        backup::{actions::export_backup::ExportBackup, generate_recovery_phrase, seal, Backup, BackupState},
+        user_prompt::CurrentUserPrompt,
+        AppState,
+    },
+};
+
+use log::info;
+
+/// Writes an encrypted backup containing the Stronghold snapshot, the non-secret parts of the state and the assets.
+/// Without a passphrase, a recovery phrase is generated and shown to the user, who needs to write it down.
+pub async fn export_backup(state: AppState, action: Action) -> Result<AppState, AppError> {
+    if let Some(ExportBackup { path, passphrase }) = listen::<ExportBackup>(action) {
+        let state_guard = state.core_utils.managers.lock().await;
+        let stronghold_manager = state_guard
+            .stronghold_manager
+            .as_ref()
+            .ok_or(MissingManagerError("stronghold"))?;
+
+        // The snapshot on disk is brought up to date before it is read.
+        stronghold_manager.commit().map_err(BackupError)?;
+        let stronghold_file = STRONGHOLD.lock().unwrap().clone();
+        let stronghold = tokio::fs::read(stronghold_file)
+            .await
+            .map_err(|e| BackupError(e.into()))?;
+
+        let assets = read_assets().map_err(BackupError)?;
+        let backup = Backup::new(&stronghold, BackupState::from(&state), &assets);
+
+        let (passphrase, current_user_prompt) = match passphrase {
+            Some(passphrase) => (passphrase, None),
+            None => {
+                let recovery_phrase = generate_recovery_phrase()?;
+                let words = recovery_phrase.split(' ').map(ToString::to_string).collect();
+                (recovery_phrase, Some(CurrentUserPrompt::RecoveryPhrase { words }))
+            }
+        };
+
+        let archive = seal(&backup, &passphrase)?;
+        tokio::fs::write(&path, archive)
+            .await
+            .map_err(|e| BackupError(e.into()))?;
+
+        info!("backup exported to {}", path);
+
+        drop(state_guard);
+        return Ok(AppState {
+            current_user_prompt,
+            ..state
+        });
+    }
+
+    Ok(state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        persistence::ASSETS_DIR,
+        state::{backup::open, core_utils::Managers},
+        stronghold::StrongholdManager,
+    };
+
+    use std::sync::Arc;
+    use tempfile::{NamedTempFile, TempDir};
+
+    #[tokio::test]
+    #[serial_test::serial]
+    async fn backup_without_passphrase_is_protected_by_a_recovery_phrase() {
+        let path = NamedTempFile::new().unwrap().into_temp_path();
+        *STRONGHOLD.lock().unwrap() = path.as_os_str().into();
+        let assets_dir = TempDir::new().unwrap();
+        *ASSETS_DIR.lock().unwrap() = assets_dir.path().to_path_buf();
+        std::fs::write(assets_dir.path().join("logo.png"), b"logo").unwrap();
+
+        let mut state = AppState::default();
+        state.dids.insert("did:key".to_string(), "did:key:z6Mk".to_string());
+        state.core_utils.managers = Arc::new(tauri::async_runtime::Mutex::new(Managers {
+            stronghold_manager: Some(Arc::new(StrongholdManager::create("sup3rSecr3t").unwrap())),
+            identity_manager: None,
+        }));
+
+        let backup_file = assets_dir.path().join("backup.age");
+        let state = export_backup(
+            state,
+            Arc::new(ExportBackup {
+                path: backup_file.to_string_lossy().to_string(),
+                passphrase: None,
+            }),
+        )
+        .await
+        .unwrap();
+
+        let words = match state.current_user_prompt {
+            Some(CurrentUserPrompt::RecoveryPhrase { words }) => words,
+            current_user_prompt => panic!("unexpected user prompt: {current_user_prompt:?}"),
+        };
+        assert_eq!(words.len(), 24);
+
+        let backup = open(&std::fs::read(&backup_file).unwrap(), &words.join(" ")).unwrap();
+        assert_eq!(backup.stronghold().unwrap(), std::fs::read(&path).unwrap());
+        assert_eq!(backup.assets().unwrap()["logo.png"], b"logo".to_vec());
+        assert_eq!(backup.state.dids["did:key"], "did:key:z6Mk");
+    }
+}
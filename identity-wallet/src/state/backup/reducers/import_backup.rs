@@ -0,0 +1,214 @@
+use crate::{
+    error::AppError::{self, *},
+    persistence::{read_assets, restore_assets, STRONGHOLD},
+    state::{
+        actions::{listen, Action},
+        backup::{actions::import_backup::ImportBackup, open, Backup, BackupState},
+        common::{actions::unlock_storage::UnlockStorage, reducers::unlock_storage::unlock_storage},
+        AppState,
+    },
+    stronghold::StrongholdManager,
+};
+
+use log::{info, warn};
+use std::sync::Arc;
+
+/// Restores the wallet from an encrypted backup and unlocks it, like `unlock_storage` does. A backup can only be
+/// imported when there is no profile yet (e.g. from the welcome page), so that no wallet is overwritten by accident.
+pub async fn import_backup(state: AppState, action: Action) -> Result<AppState, AppError> {
+    if let Some(ImportBackup {
+        path,
+        passphrase,
+        password,
+    }) = listen::<ImportBackup>(action)
+    {
+        if state.profile_settings.profile.is_some() {
+            return Err(ExistingProfileError);
+        }
+
+        let archive = tokio::fs::read(&path).await.map_err(|e| BackupError(e.into()))?;
+        let backup = open(&archive, &passphrase)?;
+
+        // The current snapshot and assets are put back if the backup cannot be restored, so that a failed import leaves
+        // the storage as it was.
+        let stronghold_file = STRONGHOLD.lock().unwrap().clone();
+        let previous_snapshot = match tokio::fs::read(&stronghold_file).await {
+            Ok(previous_snapshot) => Some(previous_snapshot),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => return Err(BackupError(e.into())),
+        };
+        let previous_assets = read_assets().map_err(BackupError)?;
+
+        return match restore(backup, password, state).await {
+            Ok(state) => Ok(state),
+            Err(e) => {
+                warn!("failed to restore the backup, reverting the storage: {:?}", e);
+                StrongholdManager::revert_snapshot(previous_snapshot.as_deref()).map_err(StrongholdLoadingError)?;
+                restore_assets(&previous_assets).map_err(BackupError)?;
+                Err(e)
+            }
+        };
+    }
+
+    Ok(state)
+}
+
+// Replaces the snapshot, the assets and the state by those of the backup and unlocks the storage.
+async fn restore(backup: Backup, password: String, state: AppState) -> Result<AppState, AppError> {
+    // The current snapshot is only replaced once the snapshot of the backup is known to load with the password.
+    StrongholdManager::restore_snapshot(&backup.stronghold()?, &password).map_err(StrongholdLoadingError)?;
+    restore_assets(&backup.assets()?).map_err(BackupError)?;
+
+    info!("backup created at {} has been restored", backup.created_at);
+
+    let BackupState {
+        dids,
+        holder_keys,
+        connections,
+        profile_settings,
+        history,
+    } = backup.state;
+
+    unlock_storage(
+        AppState {
+            dids,
+            holder_keys,
+            connections,
+            profile_settings,
+            history,
+            ..state
+        },
+        Arc::new(UnlockStorage { password }),
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        persistence::ASSETS_DIR,
+        state::{
+            backup::{actions::export_backup::ExportBackup, reducers::export_backup::export_backup, seal},
+            connections::trust_registry::TrustedRole,
+            core_utils::Managers,
+            profile_settings::{Profile, ProfileSettings},
+        },
+    };
+
+    use std::collections::BTreeMap;
+    use std::path::Path;
+    use tempfile::{NamedTempFile, TempDir};
+
+    fn use_storage(stronghold_file: &Path, assets_dir: &Path) {
+        *STRONGHOLD.lock().unwrap() = stronghold_file.as_os_str().into();
+        *ASSETS_DIR.lock().unwrap() = assets_dir.to_path_buf();
+    }
+
+    fn import(path: &Path, password: &str) -> Action {
+        Arc::new(ImportBackup {
+            path: path.to_string_lossy().to_string(),
+            passphrase: "backupPassphrase".to_string(),
+            password: password.to_string(),
+        })
+    }
+
+    #[tokio::test]
+    #[serial_test::serial]
+    async fn backup_is_restored_or_leaves_the_storage_as_it_was() {
+        let backup_dir = TempDir::new().unwrap();
+        let backup_file = backup_dir.path().join("backup.age");
+
+        // Export a backup of a wallet with a profile, a connection and an asset.
+        let stronghold_file = NamedTempFile::new().unwrap().into_temp_path();
+        let assets_dir = TempDir::new().unwrap();
+        use_storage(&stronghold_file, assets_dir.path());
+        std::fs::write(assets_dir.path().join("logo.png"), b"logo").unwrap();
+
+        let mut state = AppState::default();
+        state.profile_settings.profile = Some(Profile {
+            name: "Ferris".to_string(),
+            ..Default::default()
+        });
+        state
+            .connections
+            .update_or_insert("example.com", "Example", None, TrustedRole::Issuer);
+        state.core_utils.managers = Arc::new(tauri::async_runtime::Mutex::new(Managers {
+            stronghold_manager: Some(Arc::new(StrongholdManager::create("sup3rSecr3t").unwrap())),
+            identity_manager: None,
+        }));
+        export_backup(
+            state,
+            Arc::new(ExportBackup {
+                path: backup_file.to_string_lossy().to_string(),
+                passphrase: Some("backupPassphrase".to_string()),
+            }),
+        )
+        .await
+        .unwrap();
+
+        // A backup cannot be imported into a wallet with a profile.
+        let existing = AppState {
+            profile_settings: ProfileSettings {
+                profile: Some(Profile::default()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert!(matches!(
+            import_backup(existing, import(&backup_file, "sup3rSecr3t")).await,
+            Err(ExistingProfileError)
+        ));
+
+        // Another storage, into which the backup is imported.
+        let stronghold_file = NamedTempFile::new().unwrap().into_temp_path();
+        let assets_dir = TempDir::new().unwrap();
+        use_storage(&stronghold_file, assets_dir.path());
+        StrongholdManager::create("0therSecr3t").unwrap();
+        std::fs::write(assets_dir.path().join("other.png"), b"other").unwrap();
+        let other_assets = read_assets().unwrap();
+
+        // The snapshot of the backup does not load with a wrong password.
+        assert!(matches!(
+            import_backup(AppState::default(), import(&backup_file, "wr0ngSecr3t")).await,
+            Err(StrongholdLoadingError(_))
+        ));
+        assert!(StrongholdManager::load("0therSecr3t").is_ok());
+        assert_eq!(read_assets().unwrap(), other_assets);
+
+        // A backup with an invalid asset is reverted after its snapshot has been restored.
+        let backup = open(&std::fs::read(&backup_file).unwrap(), "backupPassphrase").unwrap();
+        let invalid_backup = Backup::new(
+            &backup.stronghold().unwrap(),
+            BackupState::default(),
+            &BTreeMap::from([("../evil.png".to_string(), b"evil".to_vec())]),
+        );
+        let invalid_backup_file = backup_dir.path().join("invalid.age");
+        std::fs::write(&invalid_backup_file, seal(&invalid_backup, "backupPassphrase").unwrap()).unwrap();
+
+        assert!(matches!(
+            import_backup(AppState::default(), import(&invalid_backup_file, "sup3rSecr3t")).await,
+            Err(BackupError(_))
+        ));
+        assert!(StrongholdManager::load("0therSecr3t").is_ok());
+        assert!(StrongholdManager::load("sup3rSecr3t").is_err());
+        assert_eq!(read_assets().unwrap(), other_assets);
+
+        // The backup is restored and the storage is unlocked.
+        let state = import_backup(AppState::default(), import(&backup_file, "sup3rSecr3t"))
+            .await
+            .unwrap();
+
+        assert_eq!(state.profile_settings.profile.unwrap().name, "Ferris");
+        assert!(state.connections.contains("example.com", "Example"));
+        assert_eq!(
+            read_assets().unwrap(),
+            BTreeMap::from([("logo.png".to_string(), b"logo".to_vec())])
+        );
+        assert!(StrongholdManager::load("sup3rSecr3t").is_ok());
+
+        let managers = state.core_utils.managers.lock().await;
+        assert!(managers.stronghold_manager.is_some());
+        assert!(managers.identity_manager.is_some());
+    }
+}
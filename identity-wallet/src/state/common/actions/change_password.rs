@@ -0,0 +1,32 @@
+use crate::{
+    reducer,
+    state::{actions::ActionTrait, common::reducers::change_password::change_password, Reducer},
+};
+
+use serde::{Deserialize, Serialize};
+use std::fmt::Formatter;
+use ts_rs::TS;
+
+/// Action to change the password of the storage.
+#[derive(Serialize, Deserialize, TS, Clone)]
+#[ts(export, export_to = "bindings/actions/ChangePassword.ts")]
+pub struct ChangePassword {
+    pub old_password: String,
+    pub new_password: String,
+}
+
+impl std::fmt::Debug for ChangePassword {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ChangePassword")
+            .field("old_password", &"*****")
+            .field("new_password", &"*****")
+            .finish()
+    }
+}
+
+#[typetag::serde(name = "[Storage] Change password")]
+impl ActionTrait for ChangePassword {
+    fn reducers<'a>(&self) -> Vec<Reducer<'a>> {
+        vec![reducer!(change_password)]
+    }
+}
@@ -1,4 +1,5 @@
 pub mod cancel_user_flow;
+pub mod change_password;
 pub mod get_state;
 pub mod reset;
 pub mod unlock_storage;
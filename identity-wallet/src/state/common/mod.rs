@@ -22,6 +22,12 @@ mod tests {
             client_name: "Impierce Technologies".to_string(),
             logo_uri: Some("logo.png".to_string()),
             options: vec![],
+            revoked: vec![],
+            input_descriptors: vec![],
+            domain_validation: Default::default(),
+            client_id_validation: Default::default(),
+            trust: Default::default(),
+            client_metadata_error: None,
         });
 
         let mut app_state = AppState {
@@ -1,14 +1,38 @@
-use crate::error::AppError::{self};
+use crate::error::AppError::{self, *};
 use crate::persistence::clear_assets_tmp_folder;
 use crate::state::actions::{listen, Action};
 use crate::state::common::actions::cancel_user_flow::CancelUserFlow;
+use crate::state::connections::{trust_registry::TrustedRole, Connection};
+use crate::state::core_utils::helpers::HTTP_TIMEOUT;
+use crate::state::core_utils::history_event::{EventType, HistoryEvent};
+use crate::state::core_utils::{CoreUtils, DateUtils};
 use crate::state::user_prompt::CurrentUserPrompt;
 use crate::state::AppState;
 
+use log::warn;
+
 pub async fn cancel_user_flow(state: AppState, action: Action) -> Result<AppState, AppError> {
     if let Some(cancel_user_flow) = listen::<CancelUserFlow>(action) {
         clear_assets_tmp_folder().ok();
+
+        // Only these prompts belong to the `active_connection_request`, which is otherwise left over from an earlier
+        // flow.
+        let state = match state.current_user_prompt {
+            Some(CurrentUserPrompt::AcceptConnection { .. })
+            | Some(CurrentUserPrompt::ShareCredentials { .. })
+            | Some(CurrentUserPrompt::NoMatchingCredentials { .. }) => {
+                decline_connection_request(state, "The user declined the request").await
+            }
+            _ => state,
+        };
+
+        // A cancelled credential offer cannot be continued, e.g. by a late authorization code.
         return Ok(AppState {
+            core_utils: CoreUtils {
+                active_credential_offer: None,
+                active_authorization_code_flow: None,
+                ..state.core_utils
+            },
             current_user_prompt: cancel_user_flow
                 .redirect
                 .map(|target| CurrentUserPrompt::Redirect { target }),
@@ -18,3 +42,51 @@ pub async fn cancel_user_flow(state: AppState, action: Action) -> Result<AppStat
 
     Ok(state)
 }
+
+/// Notifies the relying party of the `active_connection_request` that the user declined it and records this in the
+/// history. Failing to reach the relying party within `HTTP_TIMEOUT` does not prevent the request from being
+/// declined locally.
+pub async fn decline_connection_request(state: AppState, error_description: &str) -> AppState {
+    let Some(connection_request) = state.core_utils.active_connection_request.clone() else {
+        return state;
+    };
+
+    let state_guard = state.core_utils.managers.lock().await;
+    let sent = match state_guard.identity_manager.as_ref() {
+        Some(identity_manager) => tokio::time::timeout(
+            HTTP_TIMEOUT,
+            connection_request.send_access_denied(&identity_manager.provider_manager, error_description),
+        )
+        .await
+        .unwrap_or(Err(SendAuthorizationResponseError)),
+        None => Err(MissingManagerError("identity")),
+    };
+    if let Err(e) = sent {
+        warn!("failed to send error response: {:?}", e);
+    }
+    drop(state_guard);
+
+    // The connection itself is not stored, but declined requests of a known connection are listed with it.
+    let (client_name, connection_url) = connection_request.client_name_and_connection_url();
+    let connection = Connection::new(client_name, connection_url, None, TrustedRole::Verifier);
+
+    let mut history = state.history;
+    history.push(HistoryEvent {
+        connection_name: connection.name,
+        event_type: EventType::RequestDeclined,
+        connection_id: connection.id,
+        date: DateUtils::new_date_string(),
+        credentials: vec![],
+    });
+
+    AppState {
+        core_utils: CoreUtils {
+            active_connection_request: None,
+            active_domain_validation: None,
+            active_client_id_validation: None,
+            ..state.core_utils
+        },
+        history,
+        ..state
+    }
+}
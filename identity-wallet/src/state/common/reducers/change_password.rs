@@ -0,0 +1,168 @@
+use crate::error::AppError::{self, *};
+use crate::state::actions::{listen, Action};
+use crate::state::common::actions::change_password::ChangePassword;
+use crate::state::core_utils::IdentityManager;
+use crate::state::did::available_did_methods;
+use crate::state::AppState;
+use crate::stronghold::StrongholdManager;
+use crate::subject::subject;
+
+use log::info;
+use oid4vc::oid4vc_manager::ProviderManager;
+use oid4vc::oid4vci::Wallet;
+use std::sync::Arc;
+
+/// Re-encrypts the Stronghold with the new password and reloads the managers that depend on it, like
+/// `unlock_storage` does. The old password has to be the password with which the Stronghold can be loaded. The new
+/// managers are only put in place once all of them have been created.
+pub async fn change_password(state: AppState, action: Action) -> Result<AppState, AppError> {
+    if let Some(ChangePassword {
+        old_password,
+        new_password,
+    }) = listen::<ChangePassword>(action)
+    {
+        let mut state_guard = state.core_utils.managers.lock().await;
+        let current_stronghold_manager = state_guard
+            .stronghold_manager
+            .as_ref()
+            .ok_or(MissingManagerError("stronghold"))?;
+        let current_identity_manager = state_guard
+            .identity_manager
+            .as_ref()
+            .ok_or(MissingManagerError("identity"))?;
+
+        StrongholdManager::load(&old_password).map_err(|_| IncorrectPasswordError)?;
+
+        let key_index = current_identity_manager.subject.default_key_index;
+        let did_web = current_identity_manager.subject.did_web.read().unwrap().clone();
+        let supported_signing_algorithms = current_identity_manager
+            .wallet
+            .proof_signing_alg_values_supported
+            .clone();
+
+        let stronghold_manager = Arc::new(
+            current_stronghold_manager
+                .change_password(&new_password)
+                .map_err(StrongholdPasswordChangeError)?,
+        );
+
+        let subject = subject(stronghold_manager.clone(), new_password, key_index).await;
+        *subject.did_web.write().unwrap() = did_web;
+
+        let did_methods = available_did_methods(&state.profile_settings.preferred_did_methods, &state.dids);
+        let managers = ProviderManager::new(
+            subject.clone(),
+            did_methods.clone(),
+            supported_signing_algorithms.clone(),
+        )
+        .map_err(OID4VCProviderManagerError)
+        .and_then(|provider_manager| {
+            Wallet::new(subject.clone(), did_methods, supported_signing_algorithms)
+                .map(|wallet| (provider_manager, wallet))
+                .map_err(OID4VCWalletError)
+        });
+        let (provider_manager, wallet) = match managers {
+            Ok(managers) => managers,
+            Err(e) => {
+                // The snapshot is encrypted with the old password again, so it keeps matching the current managers.
+                stronghold_manager
+                    .change_password(&old_password)
+                    .map_err(StrongholdPasswordChangeError)?;
+                return Err(e);
+            }
+        };
+
+        state_guard.stronghold_manager.replace(stronghold_manager);
+        state_guard.identity_manager.replace(IdentityManager {
+            subject,
+            provider_manager,
+            wallet,
+        });
+
+        info!("password changed");
+
+        drop(state_guard);
+        return Ok(AppState {
+            current_user_prompt: None,
+            ..state
+        });
+    }
+
+    Ok(state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        persistence::STRONGHOLD,
+        state::{core_utils::Managers, SUPPORTED_SIGNING_ALGORITHMS},
+    };
+
+    use tempfile::NamedTempFile;
+
+    async fn unlocked_state(password: &str) -> AppState {
+        let stronghold_manager = Arc::new(StrongholdManager::create(password).unwrap());
+
+        let mut state = AppState::default();
+        let subject = subject(stronghold_manager.clone(), password.to_string(), 0).await;
+        let did_methods = available_did_methods(&state.profile_settings.preferred_did_methods, &state.dids);
+        state.core_utils.managers = Arc::new(tauri::async_runtime::Mutex::new(Managers {
+            stronghold_manager: Some(stronghold_manager),
+            identity_manager: Some(IdentityManager {
+                subject: subject.clone(),
+                provider_manager: ProviderManager::new(
+                    subject.clone(),
+                    did_methods.clone(),
+                    Vec::from(SUPPORTED_SIGNING_ALGORITHMS),
+                )
+                .unwrap(),
+                wallet: Wallet::new(subject, did_methods, Vec::from(SUPPORTED_SIGNING_ALGORITHMS)).unwrap(),
+            }),
+        }));
+
+        state
+    }
+
+    #[tokio::test]
+    #[serial_test::serial]
+    async fn password_is_only_changed_with_the_current_password() {
+        let path = NamedTempFile::new().unwrap().into_temp_path();
+        *STRONGHOLD.lock().unwrap() = path.as_os_str().into();
+        let state = unlocked_state("sup3rSecr3t").await;
+
+        let result = change_password(
+            state.clone(),
+            Arc::new(ChangePassword {
+                old_password: "wr0ngPassw0rd".to_string(),
+                new_password: "n3wSecr3t".to_string(),
+            }),
+        )
+        .await;
+
+        assert!(matches!(result, Err(IncorrectPasswordError)));
+        assert!(StrongholdManager::load("sup3rSecr3t").is_ok());
+
+        let state = change_password(
+            state,
+            Arc::new(ChangePassword {
+                old_password: "sup3rSecr3t".to_string(),
+                new_password: "n3wSecr3t".to_string(),
+            }),
+        )
+        .await
+        .unwrap();
+
+        assert!(StrongholdManager::load("n3wSecr3t").is_ok());
+        assert!(StrongholdManager::load("sup3rSecr3t").is_err());
+
+        // The managers use the new password, also to load the pairwise keys, which are committed to the snapshot.
+        let managers = state.core_utils.managers.lock().await;
+        let identity_manager = managers.identity_manager.as_ref().unwrap();
+        assert_eq!(identity_manager.subject.password.as_str(), "n3wSecr3t");
+        identity_manager.subject.with_keys(1).await.unwrap();
+        drop(managers);
+
+        assert!(StrongholdManager::load("n3wSecr3t").is_ok());
+    }
+}
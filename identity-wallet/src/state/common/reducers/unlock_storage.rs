@@ -1,47 +1,110 @@
 use crate::error::AppError::{self, *};
 use crate::state::actions::{listen, Action};
 use crate::state::common::actions::unlock_storage::UnlockStorage;
+use crate::state::connections::trust_registry::load_trust_lists;
 use crate::state::core_utils::IdentityManager;
+use crate::state::did::{available_did_methods, did_web::DID_WEB};
 use crate::state::user_prompt::CurrentUserPrompt;
-use crate::state::{AppState, SUPPORTED_DID_METHODS, SUPPORTED_SIGNING_ALGORITHMS};
+use crate::state::{AppState, SUPPORTED_SIGNING_ALGORITHMS};
 use crate::stronghold::StrongholdManager;
 use crate::subject::subject;
 
-use log::info;
+use log::{info, warn};
 use oid4vc::oid4vc_manager::ProviderManager;
 use oid4vc::oid4vci::Wallet;
+use serde_json::json;
 use std::sync::Arc;
+use std::time::Duration;
+
+/// The time the trust lists may take to load when the storage is unlocked. After that, the trust lists that have been
+/// loaded before (if any) are used, so that an unreachable trust list cannot keep the wallet from unlocking.
+const TRUST_LISTS_UNLOCK_TIMEOUT: Duration = Duration::from_secs(3);
 
 pub async fn unlock_storage(state: AppState, action: Action) -> Result<AppState, AppError> {
     if let Some(password) = listen::<UnlockStorage>(action).map(|payload| payload.password) {
         let mut state_guard = state.core_utils.managers.lock().await;
 
         let stronghold_manager = Arc::new(StrongholdManager::load(&password).map_err(StrongholdLoadingError)?);
+        // Keys of types that were added after the Stronghold was created are generated now.
+        stronghold_manager.generate_keys(0).map_err(StrongholdLoadingError)?;
 
-        let subject = subject(stronghold_manager.clone(), password).await;
+        let subject = subject(stronghold_manager.clone(), password, state.holder_keys.active_index).await;
+        if let Some(did_web) = state.dids.get(DID_WEB) {
+            *subject.did_web.write().unwrap() = Some(did_web.clone());
+        }
 
         let provider_manager = ProviderManager::new(
             subject.clone(),
-            Vec::from(SUPPORTED_DID_METHODS),
+            available_did_methods(&state.profile_settings.preferred_did_methods, &state.dids),
             Vec::from(SUPPORTED_SIGNING_ALGORITHMS),
         )
         .map_err(OID4VCProviderManagerError)?;
         let wallet: Wallet = Wallet::new(
             subject.clone(),
-            Vec::from(SUPPORTED_DID_METHODS),
+            available_did_methods(&state.profile_settings.preferred_did_methods, &state.dids),
             Vec::from(SUPPORTED_SIGNING_ALGORITHMS),
         )
         .map_err(OID4VCWalletError)?;
 
         info!("loading credentials from stronghold");
-        let credentials = stronghold_manager
-            .values()
+        let mut verifiable_credential_records = stronghold_manager.values().map_err(StrongholdValuesError)?.unwrap();
+
+        // Credentials that were stored before their expiry date was tracked get one now.
+        let backfilled = verifiable_credential_records
+            .iter_mut()
+            .filter_map(|verifiable_credential_record| {
+                verifiable_credential_record
+                    .backfill_expiry_date()
+                    .then_some(verifiable_credential_record)
+            })
+            .map(|verifiable_credential_record| {
+                Ok((
+                    verifiable_credential_record
+                        .display_credential
+                        .id
+                        .parse()
+                        .map_err(InvalidUuidError)?,
+                    json!(verifiable_credential_record).to_string().as_bytes().to_vec(),
+                ))
+            })
+            .collect::<Result<Vec<_>, AppError>>()?;
+        if !backfilled.is_empty() {
+            info!("backfilled the expiry date of {} credentials", backfilled.len());
+            stronghold_manager
+                .insert_all(backfilled)
+                .map_err(StrongholdInsertionError)?;
+        }
+
+        let credentials = verifiable_credential_records
+            .into_iter()
+            .map(|verifiable_credential_record| {
+                let mut display_credential = verifiable_credential_record.display_credential;
+                display_credential.metadata.update_expiry_state();
+                display_credential.metadata.update_key_state(&state.holder_keys);
+                display_credential
+            })
+            .collect();
+
+        let pending_credentials = stronghold_manager
+            .deferred_values()
             .map_err(StrongholdValuesError)?
-            .unwrap()
             .into_iter()
-            .map(|verifiable_credential_record| verifiable_credential_record.display_credential)
+            .map(|deferred_credential_record| deferred_credential_record.pending_credential)
             .collect();
 
+        // The trust lists are needed to determine which connections are verified.
+        if tokio::time::timeout(
+            TRUST_LISTS_UNLOCK_TIMEOUT,
+            load_trust_lists(&state.profile_settings.trust_lists, subject.as_ref(), false),
+        )
+        .await
+        .is_err()
+        {
+            warn!("loading the trust lists timed out, using the trust lists that have been loaded before");
+        }
+        let mut connections = state.connections;
+        connections.update_verified();
+
         state_guard.stronghold_manager.replace(stronghold_manager);
 
         state_guard.identity_manager.replace(IdentityManager {
@@ -54,7 +117,9 @@ pub async fn unlock_storage(state: AppState, action: Action) -> Result<AppState,
 
         drop(state_guard);
         return Ok(AppState {
+            connections,
             credentials,
+            pending_credentials,
             current_user_prompt: Some(CurrentUserPrompt::Redirect {
                 target: "me".to_string(),
             }),
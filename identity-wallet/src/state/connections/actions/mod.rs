@@ -1 +1,3 @@
 pub mod connection_accepted;
+pub mod refresh_trust_lists;
+pub mod revalidate_domains;
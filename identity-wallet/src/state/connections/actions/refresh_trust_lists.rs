@@ -0,0 +1,27 @@
+use crate::{
+    reducer,
+    state::{
+        actions::ActionTrait,
+        connections::{reducers::refresh_trust_lists::refresh_trust_lists, trust_registry::TrustListSource},
+        Reducer,
+    },
+};
+
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+/// Action to reload the trust lists and update which connections are verified. Optionally replaces the configured
+/// sources (URLs or file paths) of the trust lists and their operators.
+#[derive(Serialize, Deserialize, Debug, TS, Clone)]
+#[ts(export, export_to = "bindings/actions/RefreshTrustLists.ts")]
+pub struct RefreshTrustLists {
+    #[ts(optional)]
+    pub trust_lists: Option<Vec<TrustListSource>>,
+}
+
+#[typetag::serde(name = "[Trust] Refresh trust lists")]
+impl ActionTrait for RefreshTrustLists {
+    fn reducers<'a>(&self) -> Vec<Reducer<'a>> {
+        vec![reducer!(refresh_trust_lists)]
+    }
+}
@@ -0,0 +1,17 @@
+use crate::{
+    reducer,
+    state::{actions::ActionTrait, connections::reducers::revalidate_domains::revalidate_domains, Reducer},
+};
+
+use serde::{Deserialize, Serialize};
+
+/// Action to validate the domain linkage of all connections again.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RevalidateDomains;
+
+#[typetag::serde(name = "[Connections] Revalidate domains")]
+impl ActionTrait for RevalidateDomains {
+    fn reducers<'a>(&self) -> Vec<Reducer<'a>> {
+        vec![reducer!(revalidate_domains)]
+    }
+}
@@ -1,7 +1,10 @@
 pub mod actions;
+pub mod pairwise;
 pub mod reducers;
+pub mod trust_registry;
 
-use super::{core_utils::DateUtils, FeatTrait};
+use super::{core_utils::DateUtils, did::validate_domain_linkage::DomainValidation, FeatTrait};
+use trust_registry::{is_trusted, TrustedRole};
 
 use identity_iota::did::CoreDID;
 use log::info;
@@ -26,12 +29,12 @@ impl Connections {
 
     /// Inserts a new connection into the list of connections.
     /// Modelled after the `std::collections::HashMap::insert` method.
-    fn insert(&mut self, connection: Connection) -> Option<&Connection> {
+    fn insert(&mut self, connection: Connection) -> Option<&mut Connection> {
         self.contains(&connection.url, &connection.name)
             .not()
             .then(|| {
                 self.0.push(connection);
-                self.0.last()
+                self.0.last_mut()
             })
             .flatten()
     }
@@ -45,16 +48,27 @@ impl Connections {
     }
 
     /// Inserts a new connection into the list of connections if it does not already exist. If it does exist, updates
-    /// the last interaction time and returns a reference to the connection.
-    pub fn update_or_insert(&mut self, url: &str, name: &str, did: Option<CoreDID>) -> &Connection {
+    /// the last interaction time and returns a reference to the connection. The connection is (also) known to act in
+    /// the given `role`.
+    pub fn update_or_insert(
+        &mut self,
+        url: &str,
+        name: &str,
+        did: Option<CoreDID>,
+        role: TrustedRole,
+    ) -> &mut Connection {
         if self.contains(url, name) {
             info!("Updating existing connection: {} {}", name, url);
             self.get_mut(url, name).map(|connection| {
                 if let Some(core_did) = did {
                     connection.did = Some(core_did.to_string());
                 }
+                if !connection.roles.contains(&role) {
+                    connection.roles.push(role);
+                }
+                connection.update_verified();
                 connection.update_last_interaction_time();
-                &*connection
+                connection
             })
         } else {
             info!("Inserting new connection: {} {}", name, url);
@@ -62,10 +76,44 @@ impl Connections {
                 name.to_string(),
                 url.to_string(),
                 did.map(|d| d.to_string()),
+                role,
             ))
         }
         .expect("Failed to update or insert connection")
     }
+
+    /// Returns the index of the holder keys that are used for the connection with the given `url` and `name`.
+    pub fn key_index(&self, url: &str, name: &str) -> Option<u32> {
+        self.0
+            .iter()
+            .find(|connection| connection.url == url && connection.name == name)
+            .map(|connection| connection.key_index)
+    }
+
+    /// Returns the index of the holder keys that are used for the connection with the given `id`.
+    pub fn key_index_by_id(&self, id: &str) -> Option<u32> {
+        self.0
+            .iter()
+            .find(|connection| connection.id == id)
+            .map(|connection| connection.key_index)
+    }
+
+    /// Returns the most recent domain validation stored on any of the connections for the DID and the origin of the
+    /// URL.
+    pub fn domain_validation(&self, did: &str, url: &url::Url) -> Option<&DomainValidation> {
+        self.0
+            .iter()
+            .map(|connection| &connection.domain_validation)
+            .filter(|domain_validation| domain_validation.concerns(url, did))
+            .max_by(|a, b| a.validated_at.cmp(&b.validated_at))
+    }
+
+    /// Marks the connections that are listed in one of the loaded trust lists as verified.
+    pub fn update_verified(&mut self) {
+        for connection in self.0.iter_mut() {
+            connection.update_verified();
+        }
+    }
 }
 
 #[typetag::serde(name = "connections")]
@@ -89,24 +137,44 @@ pub struct Connection {
     pub verified: bool,
     pub first_interacted: String,
     pub last_interacted: String,
+    /// The last validation of the linkage between the DID and the domain of the connection.
+    pub domain_validation: DomainValidation,
+    /// The index of the holder keys, and thereby of the pairwise DID, that are used for this connection. Connections
+    /// that were made before pairwise DIDs were introduced use the keys at index 0.
+    pub key_index: u32,
+    /// The roles (issuer and/or verifier) in which the connection has been interacted with, which are the roles in
+    /// which it needs to be listed in a trust list to be verified.
+    pub roles: Vec<TrustedRole>,
 }
 
 impl Connection {
-    pub fn new(name: String, url: String, did: Option<String>) -> Self {
+    pub fn new(name: String, url: String, did: Option<String>, role: TrustedRole) -> Self {
         // TODO(ngdil): Temporary solution to support NGDIL demo, replace with different unique identifier to distinguish connection
         let id = sha256::digest([name.as_bytes(), url.as_bytes()].concat()).to_string();
         let current_datetime = DateUtils::new_date_string();
+        let verified = is_trusted(did.as_deref(), &url, role);
         Self {
             id,
             name,
             url,
             did,
-            verified: false,
+            verified,
             first_interacted: current_datetime.clone(),
             last_interacted: current_datetime,
+            domain_validation: DomainValidation::default(),
+            key_index: 0,
+            roles: vec![role],
         }
     }
 
+    // A connection is verified if it is listed in one of the loaded trust lists in any of its roles.
+    fn update_verified(&mut self) {
+        self.verified = self
+            .roles
+            .iter()
+            .any(|role| is_trusted(self.did.as_deref(), &self.url, *role));
+    }
+
     pub fn update_last_interaction_time(&mut self) {
         self.last_interacted = DateUtils::new_date_string();
     }
@@ -125,18 +193,19 @@ mod tests {
     use super::*;
 
     #[test]
+    #[serial_test::serial]
     fn test_update_or_insert() {
         let mut connections = Connections::new();
         let url = "https://example.com";
         let name = "Example";
-        let connection = connections.update_or_insert(url, name, None);
+        let connection = connections.update_or_insert(url, name, None, TrustedRole::Issuer);
         assert_eq!(connection.url, url);
         assert_eq!(connection.name, name);
         assert_eq!(connection.first_interacted, connection.last_interacted);
         assert_eq!(connections.0.len(), 1);
         assert!(connections.contains(url, name));
 
-        let connection = connections.update_or_insert(url, name, None);
+        let connection = connections.update_or_insert(url, name, None, TrustedRole::Issuer);
         assert_eq!(connection.url, url);
         assert_eq!(connection.name, name);
         // The last interaction time should have been updated.
@@ -145,11 +214,12 @@ mod tests {
     }
 
     #[test]
+    #[serial_test::serial]
     fn test_update_or_insert_with_duplicate_names() {
         let mut connections = Connections::new();
         let url = "https://example.com";
         let name = "Example";
-        let connection = connections.update_or_insert(url, name, None);
+        let connection = connections.update_or_insert(url, name, None, TrustedRole::Issuer);
         assert_eq!(connection.url, url);
         assert_eq!(connection.name, name);
         assert_eq!(connection.first_interacted, connection.last_interacted);
@@ -158,7 +228,7 @@ mod tests {
 
         // A different server with the same name is treated as a different connection.
         let url = "https://example2.com";
-        let connection = connections.update_or_insert(url, name, None);
+        let connection = connections.update_or_insert(url, name, None, TrustedRole::Issuer);
         assert_eq!(connection.url, url);
         assert_eq!(connection.name, name);
         assert_eq!(connection.first_interacted, connection.last_interacted);
@@ -167,11 +237,12 @@ mod tests {
     }
 
     #[test]
+    #[serial_test::serial]
     fn test_update_or_insert_with_duplicate_urls() {
         let mut connections = Connections::new();
         let url = "https://example.com";
         let name = "Example";
-        let connection = connections.update_or_insert(url, name, None);
+        let connection = connections.update_or_insert(url, name, None, TrustedRole::Issuer);
         assert_eq!(connection.url, url);
         assert_eq!(connection.name, name);
         assert_eq!(connection.first_interacted, connection.last_interacted);
@@ -180,7 +251,7 @@ mod tests {
 
         // The same server is used with a different name.
         let name = "Example2";
-        let connection = connections.update_or_insert(url, name, None);
+        let connection = connections.update_or_insert(url, name, None, TrustedRole::Issuer);
         assert_eq!(connection.url, url);
         assert_eq!(connection.name, name);
         assert_eq!(connection.first_interacted, connection.last_interacted);
@@ -0,0 +1,138 @@
+use crate::{
+    error::AppError::{self, *},
+    state::{connections::Connections, core_utils::IdentityManager, did::holder_keys::HolderKeys},
+};
+
+use log::info;
+
+/// Selects the holder keys of the connection with the given `url` and `name`. A new connection gets keys at a new
+/// index, so that every relying party sees a different (pairwise) DID and cannot correlate the holder with others.
+/// A connection whose keys have been replaced by a rotation gets keys at a new index as well.
+/// Returns the index of the keys, which needs to be stored on the connection, and the `IdentityManager` that signs with
+/// them.
+pub async fn activate_connection_keys(
+    connections: &Connections,
+    url: &str,
+    name: &str,
+    identity_manager: &IdentityManager,
+    holder_keys: &HolderKeys,
+    did_methods: Vec<&'static str>,
+) -> Result<(u32, IdentityManager), AppError> {
+    let key_index = match connections.key_index(url, name) {
+        Some(key_index) if !holder_keys.is_retired(key_index) => key_index,
+        _ => identity_manager
+            .subject
+            .stronghold_manager
+            .next_key_index()
+            .map_err(KeyActivationError)?,
+    };
+    info!(
+        "using the holder keys at index {} for connection: {} {}",
+        key_index, name, url
+    );
+
+    let identity_manager = identity_manager.with_keys(key_index, did_methods).await?;
+
+    Ok((key_index, identity_manager))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        persistence::STRONGHOLD,
+        state::{connections::trust_registry::TrustedRole, did::available_did_methods, AppState},
+        stronghold::StrongholdManager,
+        subject::subject,
+    };
+
+    use jsonwebtoken::Algorithm;
+    use oid4vc::{
+        oid4vc_core::{Sign, Subject as _},
+        oid4vc_manager::ProviderManager,
+        oid4vci::Wallet,
+    };
+    use std::sync::Arc;
+    use tempfile::NamedTempFile;
+
+    #[tokio::test]
+    #[serial_test::serial]
+    async fn every_connection_signs_with_its_own_keys() {
+        let path = NamedTempFile::new().unwrap().into_temp_path();
+        *STRONGHOLD.lock().unwrap() = path.as_os_str().into();
+        let stronghold_manager = Arc::new(StrongholdManager::create("sup3rSecr3t").unwrap());
+
+        let state = AppState::default();
+        let did_methods = || available_did_methods(&state.profile_settings.preferred_did_methods, &state.dids);
+        let subject = subject(stronghold_manager, "sup3rSecr3t".to_string(), 0).await;
+        let identity_manager = IdentityManager {
+            subject: subject.clone(),
+            provider_manager: ProviderManager::new(subject.clone(), did_methods(), vec![Algorithm::EdDSA]).unwrap(),
+            wallet: Wallet::new(subject.clone(), did_methods(), vec![Algorithm::EdDSA]).unwrap(),
+        };
+        let holder_did = subject.identifier("did:key", Algorithm::EdDSA).await.unwrap();
+
+        let mut connections = state.connections.clone();
+        let (first_key_index, first) = activate_connection_keys(
+            &connections,
+            "first.example.com",
+            "First",
+            &identity_manager,
+            &state.holder_keys,
+            did_methods(),
+        )
+        .await
+        .unwrap();
+        connections
+            .update_or_insert("first.example.com", "First", None, TrustedRole::Verifier)
+            .key_index = first_key_index;
+
+        let (second_key_index, second) = activate_connection_keys(
+            &connections,
+            "second.example.com",
+            "Second",
+            &identity_manager,
+            &state.holder_keys,
+            did_methods(),
+        )
+        .await
+        .unwrap();
+
+        assert_ne!(first_key_index, second_key_index);
+
+        let first_did = first.subject.identifier("did:key", Algorithm::EdDSA).await.unwrap();
+        let second_did = second.subject.identifier("did:key", Algorithm::EdDSA).await.unwrap();
+        assert_ne!(first_did, second_did);
+        assert_ne!(first_did, holder_did);
+        assert_ne!(second_did, holder_did);
+
+        // The key id of each connection refers to its own DID.
+        let first_key_id = first.subject.key_id("did:key", Algorithm::EdDSA).await.unwrap();
+        let second_key_id = second.subject.key_id("did:key", Algorithm::EdDSA).await.unwrap();
+        assert!(first_key_id.starts_with(&format!("{first_did}#")));
+        assert!(second_key_id.starts_with(&format!("{second_did}#")));
+
+        // The holder keys are not affected by the keys of the connections.
+        assert_eq!(
+            subject.identifier("did:key", Algorithm::EdDSA).await.unwrap(),
+            holder_did
+        );
+
+        // A known connection signs with the same keys again.
+        let (key_index, again) = activate_connection_keys(
+            &connections,
+            "first.example.com",
+            "First",
+            &identity_manager,
+            &state.holder_keys,
+            did_methods(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(key_index, first_key_index);
+        assert_eq!(
+            again.subject.identifier("did:key", Algorithm::EdDSA).await.unwrap(),
+            first_did
+        );
+    }
+}
@@ -3,32 +3,35 @@ use crate::{
     persistence::{hash, persist_asset},
     state::{
         actions::Action,
+        connections::{pairwise::activate_connection_keys, trust_registry::TrustedRole},
         core_utils::{
+            client_metadata::cached_client_metadata,
             history_event::{EventType, HistoryEvent},
+            jarm::{is_direct_post_jwt, send_encrypted_response},
             ConnectionRequest,
         },
+        did::available_did_methods,
         user_prompt::CurrentUserPrompt,
         AppState,
     },
 };
 
-use identity_iota::did::CoreDID;
 use log::{info, warn};
 use oid4vc::oid4vc_core::{
     authorization_request::{AuthorizationRequest, Object},
     client_metadata::ClientMetadataResource,
 };
 use oid4vc::siopv2::siopv2::SIOPv2;
+use serde_json::{json, Value};
 
 // Sends the authorization response.
 pub async fn handle_siopv2_authorization_request(state: AppState, _action: Action) -> Result<AppState, AppError> {
     let state_guard = state.core_utils.managers.lock().await;
 
-    let provider_manager = &state_guard
+    let identity_manager = state_guard
         .identity_manager
         .as_ref()
-        .ok_or(MissingManagerError("identity"))?
-        .provider_manager;
+        .ok_or(MissingManagerError("identity"))?;
 
     let siopv2_authorization_request =
         match serde_json::from_value(serde_json::json!(state.core_utils.active_connection_request)).unwrap() {
@@ -36,6 +39,21 @@ pub async fn handle_siopv2_authorization_request(state: AppState, _action: Actio
             _ => unreachable!(),
         };
 
+    let (client_name, logo_uri, connection_url, client_id) =
+        get_siopv2_client_name_and_logo_uri(&siopv2_authorization_request);
+
+    // The relying party receives the pairwise DID of the connection.
+    let (key_index, identity_manager) = activate_connection_keys(
+        &state.connections,
+        &connection_url,
+        &client_name,
+        identity_manager,
+        &state.holder_keys,
+        available_did_methods(&state.profile_settings.preferred_did_methods, &state.dids),
+    )
+    .await?;
+    let provider_manager = &identity_manager.provider_manager;
+
     info!("generating response");
 
     let response = provider_manager
@@ -44,23 +62,40 @@ pub async fn handle_siopv2_authorization_request(state: AppState, _action: Actio
         .map_err(GenerateAuthorizationResponseError)?;
     info!("response generated: {:?}", response);
 
-    if provider_manager.send_response(&response).await.is_err() {
+    if is_direct_post_jwt(&json!(siopv2_authorization_request.body)) {
+        let mut response_parameters = json!(response);
+        if let Some(response_parameters) = response_parameters.as_object_mut() {
+            response_parameters.remove("redirect_uri");
+        }
+
+        send_encrypted_response(
+            provider_manager,
+            &siopv2_authorization_request.body.redirect_uri,
+            &siopv2_client_metadata(&siopv2_authorization_request),
+            &response_parameters,
+            None,
+            Some(siopv2_authorization_request.body.extension.nonce.as_str()),
+        )
+        .await?;
+    } else if provider_manager.send_response(&response).await.is_err() {
         info!("failed to send response");
         return Err(SendAuthorizationResponseError);
     }
     info!("response successfully sent");
 
-    let (client_name, logo_uri, connection_url, client_id) =
-        get_siopv2_client_name_and_logo_uri(&siopv2_authorization_request);
-
     if logo_uri.is_some() {
         warn!("Skipping download of client logo as it should have already been downloaded in `read_authorization_request()` and be present in /assets/tmp folder");
     }
 
-    let did = CoreDID::parse(client_id).ok();
+    let did = state.core_utils.validated_client_did(client_id);
+
+    // The domain validation that has been shown to the user is stored on the connection.
+    let domain_validation = state.core_utils.active_domain_validation.clone().unwrap_or_default();
 
     let mut connections = state.connections;
-    let connection = connections.update_or_insert(&connection_url, &client_name, did);
+    let connection = connections.update_or_insert(&connection_url, &client_name, did, TrustedRole::Verifier);
+    connection.domain_validation = domain_validation;
+    connection.key_index = key_index;
 
     let file_name = match logo_uri {
         Some(logo_uri) => hash(logo_uri.as_str()),
@@ -91,6 +126,17 @@ pub async fn handle_siopv2_authorization_request(state: AppState, _action: Actio
 
 // Helper
 
+/// Returns the client metadata of the relying party as JSON, including the parameters that are not modelled
+/// explicitly, such as `jwks`.
+fn siopv2_client_metadata(siopv2_authorization_request: &AuthorizationRequest<Object<SIOPv2>>) -> Value {
+    match &siopv2_authorization_request.body.extension.client_metadata {
+        ClientMetadataResource::ClientMetadata { other, .. } => json!(other),
+        ClientMetadataResource::ClientMetadataUri(client_metadata_uri) => {
+            cached_client_metadata(&client_metadata_uri.to_string()).unwrap_or_default()
+        }
+    }
+}
+
 // TODO: move this functionality to the oid4vc-manager crate.
 /// Returns (client_name, logo_uri, connection_url, client_id)
 pub fn get_siopv2_client_name_and_logo_uri(
@@ -112,8 +158,20 @@ pub fn get_siopv2_client_name_and_logo_uri(
             let logo_uri = logo_uri.as_ref().map(|logo_uri| logo_uri.to_string());
             Some((client_name, logo_uri, connection_url.to_string(), client_id.clone()))
         }
-        // TODO: support `client_metadata_uri`
-        ClientMetadataResource::ClientMetadataUri(_) => None,
+        // The client metadata document is resolved when the authorization request is read.
+        ClientMetadataResource::ClientMetadataUri(client_metadata_uri) => {
+            cached_client_metadata(&client_metadata_uri.to_string()).map(|client_metadata| {
+                (
+                    client_metadata["client_name"]
+                        .as_str()
+                        .unwrap_or(connection_url)
+                        .to_string(),
+                    client_metadata["logo_uri"].as_str().map(ToString::to_string),
+                    connection_url.to_string(),
+                    client_id.clone(),
+                )
+            })
+        }
     }
     // Otherwise use the connection_url as the client_name.
     .unwrap_or((connection_url.to_string(), None, connection_url.to_string(), client_id))
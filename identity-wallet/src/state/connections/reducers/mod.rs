@@ -1 +1,3 @@
 pub mod handle_siopv2_authorization_request;
+pub mod refresh_trust_lists;
+pub mod revalidate_domains;
@@ -0,0 +1,44 @@
+use crate::{
+    error::AppError::{self, *},
+    state::{
+        actions::{listen, Action},
+        connections::{actions::refresh_trust_lists::RefreshTrustLists, trust_registry::load_trust_lists},
+        profile_settings::ProfileSettings,
+        AppState,
+    },
+};
+
+use log::info;
+
+/// Reloads the trust lists from their (optionally updated) sources and marks the connections that are listed in them as
+/// verified.
+pub async fn refresh_trust_lists(state: AppState, action: Action) -> Result<AppState, AppError> {
+    if let Some(RefreshTrustLists { trust_lists }) = listen::<RefreshTrustLists>(action) {
+        let state_guard = state.core_utils.managers.lock().await;
+        let identity_manager = state_guard
+            .identity_manager
+            .as_ref()
+            .ok_or(MissingManagerError("identity"))?;
+
+        let trust_lists = trust_lists.unwrap_or(state.profile_settings.trust_lists.clone());
+
+        load_trust_lists(&trust_lists, identity_manager.subject.as_ref(), true).await;
+
+        let mut connections = state.connections;
+        connections.update_verified();
+
+        info!("trust lists refreshed");
+
+        drop(state_guard);
+        return Ok(AppState {
+            connections,
+            profile_settings: ProfileSettings {
+                trust_lists,
+                ..state.profile_settings
+            },
+            ..state
+        });
+    }
+
+    Ok(state)
+}
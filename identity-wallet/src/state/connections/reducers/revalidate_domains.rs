@@ -0,0 +1,54 @@
+use crate::{
+    error::AppError,
+    state::{
+        actions::{listen, Action},
+        connections::actions::revalidate_domains::RevalidateDomains,
+        did::validate_domain_linkage::DomainValidation,
+        AppState,
+    },
+};
+
+use chrono::Duration;
+use log::info;
+
+/// Validates the linkage between the DID and the domain of every connection that has a DID. When a domain cannot be
+/// reached, the last known validation of the connection is kept.
+pub async fn revalidate_domains(state: AppState, action: Action) -> Result<AppState, AppError> {
+    if listen::<RevalidateDomains>(action).is_some() {
+        let mut connections = state.connections;
+
+        for connection in connections.0.iter_mut() {
+            // Credential issuers are connected without a DID, so their DID is only known from their domain validation.
+            let Some(did) = connection.domain_validation.did.clone().or(connection.did.clone()) else {
+                continue;
+            };
+
+            // The URL of a connection may only consist of its host.
+            let origin = connection
+                .domain_validation
+                .origin
+                .clone()
+                .unwrap_or(connection.url.clone());
+            let Some(url) = url::Url::parse(&origin)
+                .ok()
+                .filter(url::Url::has_host)
+                .or_else(|| url::Url::parse(&format!("https://{origin}")).ok())
+            else {
+                continue;
+            };
+
+            connection.domain_validation =
+                DomainValidation::validate_or_reuse(Some(&connection.domain_validation), url, &did, Duration::zero())
+                    .await;
+
+            info!(
+                "domain validation of {}: {:?}",
+                connection.name, connection.domain_validation.result.status
+            );
+        }
+
+        return Ok(AppState { connections, ..state });
+    }
+
+    Ok(state)
+}
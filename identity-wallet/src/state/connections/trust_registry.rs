@@ -0,0 +1,549 @@
+use crate::{
+    error::AppError::{self, *},
+    state::{
+        core_utils::{client_id_scheme::verify_jwt_signature, helpers::http_client},
+        did::validate_domain_linkage::{ValidationResult, ValidationStatus},
+    },
+};
+
+use base64::{
+    engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD},
+    Engine as _,
+};
+use chrono::{DateTime, Duration, Utc};
+use lazy_static::lazy_static;
+use log::{info, warn};
+use oid4vc::oid4vc_core::Verify;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{collections::HashMap, sync::Mutex};
+use ts_rs::TS;
+
+/// The time during which a loaded trust list is reused before it is loaded again.
+const TRUST_LIST_TTL_HOURS: i64 = 24;
+
+lazy_static! {
+    // Trust lists are cached by their source (a URL or a file path), so the trust status of issuers and verifiers can
+    // be looked up without loading the trust lists again.
+    static ref TRUST_LIST_CACHE: Mutex<HashMap<String, (DateTime<Utc>, TrustList)>> = Mutex::new(HashMap::new());
+}
+
+/// The source (a URL or a file path) of a trust list and its operator, which is the only party whose signature on the
+/// trust list is accepted.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, TS)]
+#[ts(export, export_to = "bindings/connections/TrustListSource.ts")]
+pub struct TrustListSource {
+    pub source: String,
+    /// The DID of the operator, or the SHA-256 fingerprint (hex) of the certificate the operator signs with. An
+    /// unsigned trust list in a local file does not need an operator.
+    #[ts(optional)]
+    pub operator: Option<String>,
+}
+
+/// A list of issuers and/or verifiers that are trusted by the operator of the list.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TrustList {
+    pub name: String,
+    pub entities: Vec<TrustedEntity>,
+}
+
+/// An issuer or verifier in a trust list, identified by its DIDs and/or URLs.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TrustedEntity {
+    pub identifiers: Vec<String>,
+    pub name: Option<String>,
+    pub roles: Vec<TrustedRole>,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, TS)]
+#[ts(export, export_to = "bindings/connections/TrustedRole.ts")]
+pub enum TrustedRole {
+    Issuer,
+    Verifier,
+}
+
+impl TrustedEntity {
+    // DIDs are compared without their fragment, whereas URLs are compared by their host.
+    fn matches(&self, did: Option<&str>, url: &str) -> bool {
+        let did = did.and_then(|did| did.split('#').next());
+        let url_host = host(url);
+
+        self.identifiers
+            .iter()
+            .any(|identifier| match identifier.starts_with("did:") {
+                true => did == identifier.split('#').next(),
+                false => url_host.is_some() && url_host == host(identifier),
+            })
+    }
+}
+
+// Returns the host of a URL, which may also be given without a scheme.
+fn host(url: &str) -> Option<String> {
+    url::Url::parse(url)
+        .ok()
+        .filter(|url| url.has_host())
+        .or_else(|| url::Url::parse(&format!("https://{url}")).ok())
+        .and_then(|url| url.host_str().map(str::to_lowercase))
+}
+
+/// Loads the trust lists from their sources, unless a recently loaded copy is cached (or `force` is set). Trust lists
+/// that are no longer among the `sources` are removed from the cache. A trust list that cannot be loaded is skipped.
+pub async fn load_trust_lists(sources: &[TrustListSource], verifier: &dyn Verify, force: bool) {
+    TRUST_LIST_CACHE
+        .lock()
+        .unwrap()
+        .retain(|cached_source, _| sources.iter().any(|source| &source.source == cached_source));
+
+    for source in sources {
+        if !force {
+            if let Some((loaded_at, _)) = TRUST_LIST_CACHE.lock().unwrap().get(&source.source) {
+                if Utc::now() - *loaded_at < Duration::hours(TRUST_LIST_TTL_HOURS) {
+                    continue;
+                }
+            }
+        }
+
+        match load_trust_list(source, verifier).await {
+            Ok(trust_list) => {
+                info!(
+                    "trust list loaded: {} ({} entities)",
+                    trust_list.name,
+                    trust_list.entities.len()
+                );
+                TRUST_LIST_CACHE
+                    .lock()
+                    .unwrap()
+                    .insert(source.source.clone(), (Utc::now(), trust_list));
+            }
+            Err(e) => warn!("failed to load trust list: {}", e),
+        }
+    }
+}
+
+/// Loads a trust list from a URL or a file. Trust lists are signed JWTs, which are verified against the trust store or
+/// the DID of their operator, and need to be signed by the operator that is pinned for the source. Only a local file
+/// may also contain an unsigned JSON trust list, since it has been placed on the device on purpose. Trust lists are
+/// retrieved with a timeout.
+pub async fn load_trust_list(
+    trust_list_source: &TrustListSource,
+    verifier: &dyn Verify,
+) -> Result<TrustList, AppError> {
+    let source = trust_list_source.source.as_str();
+    let error = |message: String| LoadTrustListError(source.to_string(), anyhow::anyhow!(message));
+
+    let (contents, is_local) = if source.starts_with("https://") || source.starts_with("http://") {
+        let contents = http_client()
+            .get(source)
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .map_err(|e| LoadTrustListError(source.to_string(), e.into()))?
+            .text()
+            .await
+            .map_err(|e| LoadTrustListError(source.to_string(), e.into()))?;
+        (contents, false)
+    } else {
+        let path = source.strip_prefix("file://").unwrap_or(source);
+        let contents = tokio::fs::read_to_string(path)
+            .await
+            .map_err(|e| LoadTrustListError(source.to_string(), e.into()))?;
+        (contents, true)
+    };
+    let contents = contents.trim();
+
+    let document: Value = match serde_json::from_str(contents) {
+        Ok(document) if is_local => document,
+        Ok(_) => {
+            return Err(error(
+                "a trust list that is loaded from a URL must be signed".to_string(),
+            ))
+        }
+        Err(_) => {
+            let signature_validation = verify_jwt_signature(contents, verifier).await;
+            if signature_validation.status != ValidationStatus::Success {
+                return Err(error(format!(
+                    "the signature of the trust list could not be verified: {}",
+                    signature_validation.message.unwrap_or_default()
+                )));
+            }
+            if let Err(message) = verify_operator(contents, trust_list_source.operator.as_deref()) {
+                return Err(error(message));
+            }
+
+            contents
+                .split('.')
+                .nth(1)
+                .and_then(|payload| URL_SAFE_NO_PAD.decode(payload).ok())
+                .and_then(|payload| serde_json::from_slice(&payload).ok())
+                .ok_or(error("the trust list is neither JSON nor a JWT".to_string()))?
+        }
+    };
+
+    parse_trust_list(source, &document).ok_or(error("unsupported trust list format".to_string()))
+}
+
+// The trust list needs to be signed by its pinned operator, either with a key of the operator's DID or with the
+// certificate with the pinned fingerprint.
+fn verify_operator(jwt: &str, operator: Option<&str>) -> Result<(), String> {
+    let operator = operator.ok_or("the operator of the trust list is not pinned".to_string())?;
+    let header = jsonwebtoken::decode_header(jwt).map_err(|e| e.to_string())?;
+
+    let signed_by_operator = match (&header.x5c, &header.kid) {
+        (Some(x5c), _) => x5c
+            .first()
+            .and_then(|certificate| STANDARD.decode(certificate).ok())
+            .is_some_and(|certificate| sha256::digest(certificate).eq_ignore_ascii_case(&operator.replace(':', ""))),
+        (None, Some(kid)) => kid.split('#').next() == operator.split('#').next(),
+        (None, None) => false,
+    };
+
+    match signed_by_operator {
+        true => Ok(()),
+        false => Err(format!("the trust list is not signed by its operator `{operator}`")),
+    }
+}
+
+// Supports the (JSON representation of) ETSI Trusted Lists and EBSI-style lists of trusted issuers.
+// See: https://www.etsi.org/deliver/etsi_ts/119600_119699/119612/02.03.01_60/ts_119612v020301p.pdf and
+// https://hub.ebsi.eu/apis/pilot/trusted-issuers-registry
+fn parse_trust_list(source: &str, document: &Value) -> Option<TrustList> {
+    if let Some(trust_service_status_list) = document.get("TrustServiceStatusList") {
+        let name = texts(&trust_service_status_list["SchemeInformation"]["SchemeName"]["Name"])
+            .into_iter()
+            .next()
+            .unwrap_or(source.to_string());
+
+        let entities = elements(&trust_service_status_list["TrustServiceProviderList"]["TrustServiceProvider"])
+            .into_iter()
+            .filter_map(|trust_service_provider| {
+                let information = &trust_service_provider["TSPInformation"];
+
+                // Services that have been withdrawn are no longer trusted.
+                let services: Vec<&Value> = elements(&trust_service_provider["TSPServices"]["TSPService"])
+                    .into_iter()
+                    .map(|service| &service["ServiceInformation"])
+                    .collect();
+                let active_services: Vec<&Value> = services
+                    .iter()
+                    .filter(|service| {
+                        !service["ServiceStatus"]
+                            .as_str()
+                            .is_some_and(|status| status.ends_with("withdrawn"))
+                    })
+                    .copied()
+                    .collect();
+                if !services.is_empty() && active_services.is_empty() {
+                    return None;
+                }
+
+                let mut roles = vec![];
+                for service_type in active_services
+                    .iter()
+                    .filter_map(|service| service["ServiceTypeIdentifier"].as_str())
+                {
+                    let service_type = service_type.to_lowercase();
+                    if service_type.contains("issu") && !roles.contains(&TrustedRole::Issuer) {
+                        roles.push(TrustedRole::Issuer);
+                    }
+                    if (service_type.contains("verifier") || service_type.contains("relyingparty"))
+                        && !roles.contains(&TrustedRole::Verifier)
+                    {
+                        roles.push(TrustedRole::Verifier);
+                    }
+                }
+                if roles.is_empty() {
+                    roles = vec![TrustedRole::Issuer, TrustedRole::Verifier];
+                }
+
+                let identifiers: Vec<String> = texts(&information["TSPInformationURI"]["URI"])
+                    .into_iter()
+                    .chain(
+                        active_services
+                            .iter()
+                            .flat_map(|service| elements(&service["ServiceDigitalIdentity"]["DigitalId"]))
+                            .filter_map(|digital_id| digital_id["DID"].as_str().map(ToString::to_string)),
+                    )
+                    .collect();
+
+                (!identifiers.is_empty()).then(|| TrustedEntity {
+                    identifiers,
+                    name: texts(&information["TSPName"]["Name"]).into_iter().next(),
+                    roles,
+                })
+            })
+            .collect();
+
+        return Some(TrustList { name, entities });
+    }
+
+    if let Some(items) = document["items"].as_array() {
+        let entities = items
+            .iter()
+            .filter_map(|item| item["did"].as_str())
+            .map(|did| TrustedEntity {
+                identifiers: vec![did.to_string()],
+                name: None,
+                roles: vec![TrustedRole::Issuer],
+            })
+            .collect();
+
+        return Some(TrustList {
+            name: source.to_string(),
+            entities,
+        });
+    }
+
+    None
+}
+
+// An element of an ETSI Trusted List can occur once (as an object) or multiple times (as an array).
+fn elements(value: &Value) -> Vec<&Value> {
+    match value {
+        Value::Array(values) => values.iter().collect(),
+        Value::Null => vec![],
+        value => vec![value],
+    }
+}
+
+// Multilingual names and URIs are either plain strings or objects with a `value`.
+fn texts(value: &Value) -> Vec<String> {
+    elements(value)
+        .into_iter()
+        .filter_map(|value| value.as_str().or(value["value"].as_str()))
+        .map(ToString::to_string)
+        .collect()
+}
+
+/// Returns whether the issuer or verifier identified by its DID and/or URL is listed in one of the loaded trust lists
+/// (in the given role, if any). The result is left empty when no trust lists have been loaded.
+pub fn trust_status(did: Option<&str>, url: &str, role: Option<TrustedRole>) -> ValidationResult {
+    let trust_list_cache = TRUST_LIST_CACHE.lock().unwrap();
+    if trust_list_cache.is_empty() {
+        return ValidationResult::default();
+    }
+
+    let listing = trust_list_cache.values().find_map(|(_, trust_list)| {
+        trust_list
+            .entities
+            .iter()
+            .filter(|entity| role.map_or(true, |role| entity.roles.contains(&role)))
+            .find(|entity| entity.matches(did, url))
+            .map(|entity| (trust_list, entity))
+    });
+
+    match listing {
+        Some((trust_list, entity)) => ValidationResult {
+            status: ValidationStatus::Success,
+            message: Some(match &entity.name {
+                Some(name) => format!("Listed as `{}` in the trust list `{}`", name, trust_list.name),
+                None => format!("Listed in the trust list `{}`", trust_list.name),
+            }),
+        },
+        None => ValidationResult {
+            status: ValidationStatus::Unknown,
+            message: Some("Not listed in any of the trust lists".to_string()),
+        },
+    }
+}
+
+/// Returns `true` if the issuer or verifier identified by its DID and/or URL is listed in one of the loaded trust lists
+/// in the given role.
+pub fn is_trusted(did: Option<&str>, url: &str, role: TrustedRole) -> bool {
+    trust_status(did, url, Some(role)).status == ValidationStatus::Success
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ring::signature::{Ed25519KeyPair, KeyPair as _};
+    use serde_json::json;
+    use serial_test::serial;
+    use std::io::Write;
+
+    const OPERATOR: &str = "did:example:operator";
+
+    struct NoVerifier;
+
+    // Resolves only the key `did:example:operator#key-1`.
+    struct OperatorKey(Vec<u8>);
+
+    #[async_trait::async_trait]
+    impl Verify for OperatorKey {
+        async fn public_key(&self, did_url: &str) -> anyhow::Result<Vec<u8>> {
+            match did_url {
+                "did:example:operator#key-1" => Ok(self.0.clone()),
+                did_url => anyhow::bail!("unknown key: {did_url}"),
+            }
+        }
+    }
+
+    fn local(source: String) -> TrustListSource {
+        TrustListSource { source, operator: None }
+    }
+
+    #[async_trait::async_trait]
+    impl Verify for NoVerifier {
+        async fn public_key(&self, _did_url: &str) -> anyhow::Result<Vec<u8>> {
+            anyhow::bail!("no keys")
+        }
+    }
+
+    fn trust_list_file(trust_list: &Value) -> tempfile::NamedTempFile {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(trust_list.to_string().as_bytes()).unwrap();
+        file
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn etsi_trust_list_is_loaded_from_file() {
+        let file = trust_list_file(&json!({
+            "TrustServiceStatusList": {
+                "SchemeInformation": { "SchemeName": { "Name": [{ "lang": "en", "value": "Example Trusted List" }] } },
+                "TrustServiceProviderList": {
+                    "TrustServiceProvider": [
+                        {
+                            "TSPInformation": {
+                                "TSPName": { "Name": [{ "lang": "en", "value": "Example University" }] },
+                                "TSPInformationURI": { "URI": ["https://university.example.com"] }
+                            },
+                            "TSPServices": {
+                                "TSPService": {
+                                    "ServiceInformation": {
+                                        "ServiceTypeIdentifier": "http://uri.etsi.org/TrstSvc/Svctype/EAA/Issuance",
+                                        "ServiceStatus": "http://uri.etsi.org/TrstSvc/TrustedList/Svcstatus/granted"
+                                    }
+                                }
+                            }
+                        },
+                        {
+                            "TSPInformation": {
+                                "TSPName": { "Name": "Former Issuer" },
+                                "TSPInformationURI": { "URI": "https://former.example.com" }
+                            },
+                            "TSPServices": {
+                                "TSPService": {
+                                    "ServiceInformation": {
+                                        "ServiceStatus": "http://uri.etsi.org/TrstSvc/TrustedList/Svcstatus/withdrawn"
+                                    }
+                                }
+                            }
+                        }
+                    ]
+                }
+            }
+        }));
+        let source = file.path().to_str().unwrap().to_string();
+
+        load_trust_lists(&[local(source)], &NoVerifier, true).await;
+
+        let status = trust_status(None, "https://university.example.com/issuer", Some(TrustedRole::Issuer));
+        assert_eq!(status.status, ValidationStatus::Success);
+        assert_eq!(
+            status.message.unwrap(),
+            "Listed as `Example University` in the trust list `Example Trusted List`"
+        );
+        assert_eq!(
+            trust_status(None, "https://university.example.com", Some(TrustedRole::Verifier)).status,
+            ValidationStatus::Unknown
+        );
+        assert!(!is_trusted(None, "https://former.example.com", TrustedRole::Issuer));
+
+        // Trust lists that are no longer configured are removed from the cache.
+        load_trust_lists(&[], &NoVerifier, true).await;
+        assert_eq!(
+            trust_status(None, "https://university.example.com", None),
+            ValidationResult::default()
+        );
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn ebsi_trust_list_matches_dids() {
+        let file = trust_list_file(&json!({
+            "self": "https://api.example.com/trusted-issuers-registry/v4/issuers",
+            "items": [{ "did": "did:ebsi:zZeKyEJfUTGwajhNyNX928z", "href": "https://api.example.com/issuers/1" }],
+            "total": 1
+        }));
+        let source = format!("file://{}", file.path().to_str().unwrap());
+
+        load_trust_lists(&[local(source)], &NoVerifier, true).await;
+
+        assert!(is_trusted(
+            Some("did:ebsi:zZeKyEJfUTGwajhNyNX928z#key-1"),
+            "https://issuer.example.com",
+            TrustedRole::Issuer
+        ));
+        assert!(!is_trusted(
+            Some("did:ebsi:zZeKyEJfUTGwajhNyNX928z#key-1"),
+            "https://issuer.example.com",
+            TrustedRole::Verifier
+        ));
+        assert!(!is_trusted(
+            Some("did:ebsi:other"),
+            "https://issuer.example.com",
+            TrustedRole::Issuer
+        ));
+
+        load_trust_lists(&[], &NoVerifier, true).await;
+    }
+
+    #[tokio::test]
+    async fn unsigned_remote_trust_list_is_rejected() {
+        let mock_server = wiremock::MockServer::start().await;
+
+        wiremock::Mock::given(wiremock::matchers::method("GET"))
+            .respond_with(wiremock::ResponseTemplate::new(200).set_body_json(json!({ "items": [] })))
+            .mount(&mock_server)
+            .await;
+
+        assert!(matches!(
+            load_trust_list(&local(mock_server.uri()), &NoVerifier).await,
+            Err(LoadTrustListError(..))
+        ));
+    }
+
+    #[tokio::test]
+    async fn signed_trust_list_is_only_accepted_from_its_operator() {
+        let key_pair = Ed25519KeyPair::from_seed_unchecked(&[7; 32]).unwrap();
+        let verifier = OperatorKey(key_pair.public_key().as_ref().to_vec());
+
+        let encode = |value: &Value| URL_SAFE_NO_PAD.encode(value.to_string());
+        let message = format!(
+            "{}.{}",
+            encode(&json!({ "alg": "EdDSA", "kid": format!("{OPERATOR}#key-1") })),
+            encode(&json!({ "items": [{ "did": "did:example:issuer" }] }))
+        );
+        let jwt = format!(
+            "{message}.{}",
+            URL_SAFE_NO_PAD.encode(key_pair.sign(message.as_bytes()).as_ref())
+        );
+
+        let mock_server = wiremock::MockServer::start().await;
+        wiremock::Mock::given(wiremock::matchers::method("GET"))
+            .respond_with(wiremock::ResponseTemplate::new(200).set_body_string(jwt))
+            .mount(&mock_server)
+            .await;
+
+        let trust_list_source = |operator: Option<&str>| TrustListSource {
+            source: mock_server.uri(),
+            operator: operator.map(ToString::to_string),
+        };
+
+        let trust_list = load_trust_list(&trust_list_source(Some(OPERATOR)), &verifier)
+            .await
+            .unwrap();
+        assert_eq!(trust_list.entities[0].identifiers, vec!["did:example:issuer"]);
+
+        // A trust list that is signed by anyone other than the pinned operator is rejected.
+        assert!(matches!(
+            load_trust_list(&trust_list_source(Some("did:example:other")), &verifier).await,
+            Err(LoadTrustListError(..))
+        ));
+        assert!(matches!(
+            load_trust_list(&trust_list_source(None), &verifier).await,
+            Err(LoadTrustListError(..))
+        ));
+    }
+}
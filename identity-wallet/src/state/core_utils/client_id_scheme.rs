@@ -0,0 +1,775 @@
+use crate::{
+    persistence::TRUST_STORE_DIR,
+    state::{
+        core_utils::helpers::http_client,
+        did::validate_domain_linkage::{ValidationResult, ValidationStatus},
+    },
+};
+
+use base64::{
+    engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD},
+    Engine as _,
+};
+use chrono::Utc;
+use jsonwebtoken::{crypto::verify, jwk::Jwk, Algorithm, DecodingKey, Header};
+use log::{info, warn};
+use oid4vc::oid4vc_core::Verify;
+use rustls_pki_types::{CertificateDer, ServerName, UnixTime};
+use serde_json::Value;
+use std::collections::HashMap;
+use webpki::{anchor_from_trusted_cert, EndEntityCert, KeyUsage, ALL_VERIFICATION_ALGS};
+
+// The extended key usage of mdoc reader authentication certificates (ISO/IEC 18013-5), `1.0.18013.5.1.6`.
+const MDL_READER_AUTH: &[u8] = &[0x28, 0x81, 0x8c, 0x5d, 0x05, 0x01, 0x06];
+
+/// Returns the authorization request with its request object passed by value (`request`) instead of by reference
+/// (`request_uri`). The request object is only fetched once, so the request object that is parsed is the same one whose
+/// `client_id_scheme` is validated.
+pub async fn request_object_by_value(form_urlencoded: &str) -> Result<String, String> {
+    let mut url = url::Url::parse(form_urlencoded).map_err(|e| e.to_string())?;
+    let mut parameters: Vec<(String, String)> = url.query_pairs().into_owned().collect();
+
+    if parameters.iter().any(|(name, _)| name == "request") {
+        return Ok(form_urlencoded.to_string());
+    }
+    let Some(index) = parameters.iter().position(|(name, _)| name == "request_uri") else {
+        return Ok(form_urlencoded.to_string());
+    };
+
+    let (_, request_uri) = parameters.remove(index);
+    let request = http_client()
+        .get(request_uri)
+        .send()
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .map_err(|e| e.to_string())?
+        .text()
+        .await
+        .map_err(|e| e.to_string())?
+        .trim()
+        .to_string();
+    parameters.push(("request".to_string(), request));
+
+    url.query_pairs_mut().clear().extend_pairs(parameters);
+
+    Ok(url.to_string())
+}
+
+/// Validates that the relying party is who it claims to be, according to the `client_id_scheme` of its authorization
+/// request. A request object passed by reference needs to be resolved with `request_object_by_value` first.
+/// See: https://openid.net/specs/openid-4-verifiable-presentations-1_0.html#name-verifier-metadata-managemen
+pub async fn validate_client_id_scheme(
+    form_urlencoded: &str,
+    client_id: &str,
+    redirect_uri: &url::Url,
+    verifier: &dyn Verify,
+) -> ValidationResult {
+    let parameters: HashMap<String, String> = match url::Url::parse(form_urlencoded) {
+        Ok(url) => url.query_pairs().into_owned().collect(),
+        Err(e) => return failure(e.to_string()),
+    };
+
+    let request_object = match request_object(&parameters) {
+        Ok(request_object) => request_object,
+        Err(message) => return failure(message),
+    };
+
+    let client_id_scheme = request_object
+        .as_ref()
+        .and_then(|(_, claims)| claims["client_id_scheme"].as_str().map(ToString::to_string))
+        .or(parameters.get("client_id_scheme").cloned());
+
+    info!("client_id_scheme: {:?}", client_id_scheme);
+
+    match (client_id_scheme.as_deref(), &request_object) {
+        // Without a `client_id_scheme`, the client is expected to be pre-registered, which this wallet does not
+        // support.
+        (None, _) => failure("The request does not specify a `client_id_scheme`".to_string()),
+        (Some("redirect_uri"), None) => {
+            if client_id == redirect_uri.as_str() {
+                success()
+            } else {
+                failure(format!(
+                    "The `client_id` `{client_id}` does not match the `redirect_uri` `{redirect_uri}`"
+                ))
+            }
+        }
+        (Some("redirect_uri"), Some(_)) => {
+            failure("A request with the `redirect_uri` client_id_scheme must not be signed".to_string())
+        }
+        (Some(client_id_scheme), None) => failure(format!(
+            "A request with the `{client_id_scheme}` client_id_scheme must be signed"
+        )),
+        (Some("did"), Some((jwt, _))) => validate_did(jwt, client_id, verifier).await,
+        (Some("x509_san_dns"), Some((jwt, _))) => validate_x509_san_dns(jwt, client_id, redirect_uri),
+        (Some("verifier_attestation"), Some((jwt, _))) => validate_verifier_attestation(jwt, client_id),
+        (Some(client_id_scheme), Some(_)) => failure(format!("Unsupported client_id_scheme: `{client_id_scheme}`")),
+    }
+}
+
+// Returns the request object (and its claims), which needs to be passed by value.
+fn request_object(parameters: &HashMap<String, String>) -> Result<Option<(String, Value)>, String> {
+    let jwt = match (parameters.get("request"), parameters.get("request_uri")) {
+        (Some(request), _) => request.clone(),
+        (None, Some(_)) => return Err("The request object has not been resolved".to_string()),
+        (None, None) => return Ok(None),
+    };
+
+    let claims = jwt
+        .split('.')
+        .nth(1)
+        .and_then(|payload| URL_SAFE_NO_PAD.decode(payload).ok())
+        .and_then(|payload| serde_json::from_slice(&payload).ok())
+        .ok_or("The request object is not a valid JWT".to_string())?;
+
+    Ok(Some((jwt, claims)))
+}
+
+// The request object needs to be signed with a key of the DID that is the `client_id`.
+async fn validate_did(jwt: &str, client_id: &str, verifier: &dyn Verify) -> ValidationResult {
+    let header = match jsonwebtoken::decode_header(jwt) {
+        Ok(header) => header,
+        Err(e) => return failure(e.to_string()),
+    };
+
+    let kid = match header.kid {
+        Some(kid) => kid,
+        None => return failure("The request object does not contain a key identifier (`kid`)".to_string()),
+    };
+
+    if kid.split('#').next() != Some(client_id) {
+        return failure(format!("The key `{kid}` does not belong to the client `{client_id}`"));
+    }
+
+    let public_key = match verifier.public_key(&kid).await {
+        Ok(public_key) => public_key,
+        Err(e) => return failure(format!("The key `{kid}` cannot be resolved: {e}")),
+    };
+
+    let decoding_key = match header.alg {
+        Algorithm::EdDSA => DecodingKey::from_ed_der(&public_key),
+        Algorithm::ES256 | Algorithm::ES384 => DecodingKey::from_ec_der(&public_key),
+        algorithm => return failure(format!("Unsupported signature algorithm: {algorithm:?}")),
+    };
+
+    verify_jws(jwt, &decoding_key, header.alg)
+}
+
+// The request object needs to be signed with the key of an X.509 certificate that chains up to the trust store and
+// contains the `client_id` as a DNS name. The `redirect_uri` needs to be on the same host.
+fn validate_x509_san_dns(jwt: &str, client_id: &str, redirect_uri: &url::Url) -> ValidationResult {
+    let header = match jsonwebtoken::decode_header(jwt) {
+        Ok(header) => header,
+        Err(e) => return failure(e.to_string()),
+    };
+
+    if redirect_uri.host_str() != Some(client_id) {
+        return failure(format!(
+            "The `redirect_uri` `{redirect_uri}` is not on the host of the client `{client_id}`"
+        ));
+    }
+
+    let certificate_chain = match certificate_chain(&header) {
+        Ok(certificate_chain) => certificate_chain,
+        Err(message) => return failure(message),
+    };
+
+    let end_entity_certificate = match validate_relying_party_certificate_chain(&certificate_chain) {
+        Ok(end_entity_certificate) => end_entity_certificate,
+        Err(result) => return result,
+    };
+
+    let server_name = match ServerName::try_from(client_id) {
+        Ok(server_name) => server_name,
+        Err(e) => return failure(e.to_string()),
+    };
+    if let Err(e) = end_entity_certificate.verify_is_valid_for_subject_name(&server_name) {
+        return failure(format!("The certificate is not valid for `{client_id}`: {e:?}"));
+    }
+
+    verify_jws_with_certificate(jwt, &end_entity_certificate, header.alg)
+}
+
+// The request object needs to be signed with the key in the `cnf` claim of a Verifier Attestation JWT, which is issued
+// for the `client_id` by an attestation issuer whose certificate chains up to the trust store.
+// See: https://openid.net/specs/openid-4-verifiable-presentations-1_0.html#name-verifier-attestation-jwt
+fn validate_verifier_attestation(jwt: &str, client_id: &str) -> ValidationResult {
+    let header = match jsonwebtoken::decode_header(jwt) {
+        Ok(header) => header,
+        Err(e) => return failure(e.to_string()),
+    };
+
+    // The Verifier Attestation JWT is passed in the `jwt` header parameter, which is not modelled by `Header`.
+    let attestation = match jwt
+        .split('.')
+        .next()
+        .and_then(|header| URL_SAFE_NO_PAD.decode(header).ok())
+        .and_then(|header| serde_json::from_slice::<Value>(&header).ok())
+        .and_then(|header| header["jwt"].as_str().map(ToString::to_string))
+    {
+        Some(attestation) => attestation,
+        None => return failure("The request object does not contain a Verifier Attestation JWT".to_string()),
+    };
+
+    let attestation_header = match jsonwebtoken::decode_header(&attestation) {
+        Ok(attestation_header) => attestation_header,
+        Err(e) => return failure(e.to_string()),
+    };
+
+    let attestation_claims: Value = match attestation
+        .split('.')
+        .nth(1)
+        .and_then(|payload| URL_SAFE_NO_PAD.decode(payload).ok())
+        .and_then(|payload| serde_json::from_slice(&payload).ok())
+    {
+        Some(attestation_claims) => attestation_claims,
+        None => return failure("The Verifier Attestation JWT is not a valid JWT".to_string()),
+    };
+
+    if attestation_claims["sub"] != client_id {
+        return failure(format!("The Verifier Attestation was not issued for `{client_id}`"));
+    }
+    if attestation_claims["exp"]
+        .as_i64()
+        .map_or(true, |exp| exp < Utc::now().timestamp())
+    {
+        return failure("The Verifier Attestation has expired".to_string());
+    }
+
+    // The attestation issuer is trusted through its certificate.
+    let certificate_chain = match certificate_chain(&attestation_header) {
+        Ok(certificate_chain) => certificate_chain,
+        Err(message) => return failure(message),
+    };
+    let end_entity_certificate = match validate_relying_party_certificate_chain(&certificate_chain) {
+        Ok(end_entity_certificate) => end_entity_certificate,
+        Err(result) => return result,
+    };
+    let attestation_result = verify_jws_with_certificate(&attestation, &end_entity_certificate, attestation_header.alg);
+    if attestation_result.status != success().status {
+        return attestation_result;
+    }
+
+    let decoding_key = match serde_json::from_value::<Jwk>(attestation_claims["cnf"]["jwk"].clone())
+        .ok()
+        .and_then(|jwk| DecodingKey::from_jwk(&jwk).ok())
+    {
+        Some(decoding_key) => decoding_key,
+        None => return failure("The Verifier Attestation does not contain a valid `cnf` key".to_string()),
+    };
+
+    verify_jws(jwt, &decoding_key, header.alg)
+}
+
+/// Verifies the signature of a JWT that is signed either with the key of an X.509 certificate (`x5c`) that chains up to
+/// the trust store, or with a key of a DID (`kid`).
+pub async fn verify_jwt_signature(jwt: &str, verifier: &dyn Verify) -> ValidationResult {
+    let header = match jsonwebtoken::decode_header(jwt) {
+        Ok(header) => header,
+        Err(e) => return failure(e.to_string()),
+    };
+
+    if header.x5c.is_some() {
+        let certificate_chain = match certificate_chain(&header) {
+            Ok(certificate_chain) => certificate_chain,
+            Err(message) => return failure(message),
+        };
+        let end_entity_certificate = match validate_certificate_chain(&certificate_chain, KeyUsage::server_auth()) {
+            Ok(end_entity_certificate) => end_entity_certificate,
+            Err(result) => return result,
+        };
+
+        return verify_jws_with_certificate(jwt, &end_entity_certificate, header.alg);
+    }
+
+    match header.kid.as_deref().and_then(|kid| kid.split('#').next()) {
+        Some(did) if did.starts_with("did:") => validate_did(jwt, did, verifier).await,
+        _ => failure("The JWT contains neither a certificate chain (`x5c`) nor a DID key (`kid`)".to_string()),
+    }
+}
+
+// Returns the DER-encoded certificates of the `x5c` header, starting with the end-entity certificate.
+fn certificate_chain(header: &Header) -> Result<Vec<CertificateDer<'static>>, String> {
+    header
+        .x5c
+        .as_ref()
+        .filter(|x5c| !x5c.is_empty())
+        .ok_or("The JWT does not contain a certificate chain (`x5c`)".to_string())?
+        .iter()
+        .map(|certificate| {
+            STANDARD
+                .decode(certificate)
+                .map(CertificateDer::from)
+                .map_err(|_| "The certificate chain (`x5c`) is not base64-encoded".to_string())
+        })
+        .collect()
+}
+
+/// Validates the certificate chain (starting with the end-entity certificate) against the trust anchors in the trust
+/// store. The end-entity certificate needs to be valid for the given `key_usage`.
+pub(crate) fn validate_certificate_chain<'a>(
+    certificate_chain: &'a [CertificateDer<'static>],
+    key_usage: KeyUsage,
+) -> Result<EndEntityCert<'a>, ValidationResult> {
+    let trust_store = trust_store();
+    let trust_anchors: Vec<_> = trust_store
+        .iter()
+        .filter_map(|certificate| anchor_from_trusted_cert(certificate).ok())
+        .collect();
+    if trust_anchors.is_empty() {
+        return Err(unknown("The trust store does not contain any certificates".to_string()));
+    }
+
+    let end_entity_certificate = certificate_chain
+        .first()
+        .ok_or(failure("The certificate chain is empty".to_string()))
+        .and_then(|certificate| {
+            EndEntityCert::try_from(certificate).map_err(|e| failure(format!("Invalid certificate: {e:?}")))
+        })?;
+
+    end_entity_certificate
+        .verify_for_usage(
+            ALL_VERIFICATION_ALGS,
+            &trust_anchors,
+            &certificate_chain[1..],
+            UnixTime::now(),
+            key_usage,
+            None,
+            None,
+        )
+        .map_err(|e| failure(format!("The certificate is not trusted: {e:?}")))?;
+
+    Ok(end_entity_certificate)
+}
+
+// Relying parties authenticate with either a TLS server certificate or an mdoc reader authentication certificate.
+fn validate_relying_party_certificate_chain(
+    certificate_chain: &[CertificateDer<'static>],
+) -> Result<EndEntityCert<'_>, ValidationResult> {
+    validate_certificate_chain(certificate_chain, KeyUsage::server_auth())
+        .or_else(|_| validate_certificate_chain(certificate_chain, KeyUsage::required(MDL_READER_AUTH)))
+}
+
+/// Returns the certificates (PEM or DER) in the trust store directory, which can be overridden with the
+/// `TRUST_STORE_DIR` environment variable.
+pub fn trust_store() -> Vec<CertificateDer<'static>> {
+    let trust_store_dir = std::env::var("TRUST_STORE_DIR")
+        .map(std::path::PathBuf::from)
+        .unwrap_or(TRUST_STORE_DIR.lock().unwrap().clone());
+
+    let Ok(entries) = std::fs::read_dir(&trust_store_dir) else {
+        warn!("trust store not found: {}", trust_store_dir.display());
+        return vec![];
+    };
+
+    entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| std::fs::read(entry.path()).ok())
+        .flat_map(|contents| match pem::parse_many(&contents) {
+            Ok(pems) if !pems.is_empty() => pems
+                .into_iter()
+                .filter(|pem| pem.tag() == "CERTIFICATE")
+                .map(|pem| CertificateDer::from(pem.into_contents()))
+                .collect(),
+            _ => vec![CertificateDer::from(contents)],
+        })
+        .collect()
+}
+
+fn verify_jws(jwt: &str, decoding_key: &DecodingKey, algorithm: Algorithm) -> ValidationResult {
+    let (message, signature) = match jwt.rsplit_once('.') {
+        Some(parts) => parts,
+        None => return failure("The request object is not a valid JWS".to_string()),
+    };
+
+    match verify(signature, message.as_bytes(), decoding_key, algorithm) {
+        Ok(true) => success(),
+        Ok(false) => failure("The signature of the request object is invalid".to_string()),
+        Err(e) => failure(e.to_string()),
+    }
+}
+
+fn verify_jws_with_certificate(
+    jwt: &str,
+    end_entity_certificate: &EndEntityCert,
+    algorithm: Algorithm,
+) -> ValidationResult {
+    let (message, signature) = match jwt
+        .rsplit_once('.')
+        .and_then(|(message, signature)| Some((message, URL_SAFE_NO_PAD.decode(signature).ok()?)))
+    {
+        Some(parts) => parts,
+        None => return failure("The JWT is not a valid JWS".to_string()),
+    };
+
+    // JWS uses the fixed-size `r || s` encoding of ECDSA signatures, whereas X.509 uses the ASN.1 encoding.
+    let (signature_algorithm, signature) = match algorithm {
+        Algorithm::ES256 => (webpki::ring::ECDSA_P256_SHA256, asn1_ecdsa_signature(&signature)),
+        Algorithm::ES384 => (webpki::ring::ECDSA_P384_SHA384, asn1_ecdsa_signature(&signature)),
+        Algorithm::EdDSA => (webpki::ring::ED25519, signature),
+        Algorithm::RS256 => (webpki::ring::RSA_PKCS1_2048_8192_SHA256, signature),
+        algorithm => return unknown(format!("Unsupported signature algorithm: {algorithm:?}")),
+    };
+
+    match end_entity_certificate.verify_signature(signature_algorithm, message.as_bytes(), &signature) {
+        Ok(()) => success(),
+        Err(e) => failure(format!("The signature of the JWT is invalid: {e:?}")),
+    }
+}
+
+/// Converts a fixed-size `r || s` ECDSA signature into an ASN.1 `Ecdsa-Sig-Value`.
+pub(crate) fn asn1_ecdsa_signature(signature: &[u8]) -> Vec<u8> {
+    let integer = |bytes: &[u8]| {
+        let bytes = match bytes.iter().position(|byte| *byte != 0) {
+            Some(start) => &bytes[start..],
+            None => &[0][..],
+        };
+        // A leading zero is needed to keep the integer positive.
+        let bytes = if bytes[0] & 0x80 != 0 {
+            [&[0][..], bytes].concat()
+        } else {
+            bytes.to_vec()
+        };
+        [&[0x02, bytes.len() as u8][..], &bytes[..]].concat()
+    };
+
+    let (r, s) = signature.split_at(signature.len() / 2);
+    let sequence = [integer(r), integer(s)].concat();
+
+    [&[0x30, sequence.len() as u8][..], &sequence[..]].concat()
+}
+
+fn success() -> ValidationResult {
+    ValidationResult {
+        status: ValidationStatus::Success,
+        message: None,
+    }
+}
+
+fn failure(message: String) -> ValidationResult {
+    ValidationResult {
+        status: ValidationStatus::Failure,
+        message: Some(message),
+    }
+}
+
+fn unknown(message: String) -> ValidationResult {
+    ValidationResult {
+        status: ValidationStatus::Unknown,
+        message: Some(message),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ring::{
+        rand::SystemRandom,
+        signature::{EcdsaKeyPair, KeyPair, ECDSA_P256_SHA256_FIXED_SIGNING},
+    };
+    use serde_json::json;
+    use wiremock::{
+        matchers::{method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    fn key_pair(pkcs8: &[u8]) -> EcdsaKeyPair {
+        EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, pkcs8, &SystemRandom::new()).unwrap()
+    }
+
+    fn es256_jwt(mut header: Value, claims: Value, key_pair: &EcdsaKeyPair) -> String {
+        header["alg"] = json!("ES256");
+        let message = format!(
+            "{}.{}",
+            URL_SAFE_NO_PAD.encode(header.to_string()),
+            URL_SAFE_NO_PAD.encode(claims.to_string())
+        );
+        let signature = key_pair.sign(&SystemRandom::new(), message.as_bytes()).unwrap();
+
+        format!("{message}.{}", URL_SAFE_NO_PAD.encode(signature.as_ref()))
+    }
+
+    fn by_value(client_id: &str, request: &str) -> String {
+        format!("openid4vp://?client_id={client_id}&request={request}")
+    }
+
+    #[test]
+    fn ecdsa_signature_is_asn1_encoded() {
+        let mut signature = vec![0u8; 64];
+        signature[0] = 0x80;
+        signature[63] = 0x01;
+
+        let asn1_signature = asn1_ecdsa_signature(&signature);
+
+        // `r` needs a leading zero, whereas the leading zeros of `s` are stripped.
+        assert_eq!(&asn1_signature[..5], &[0x30, 0x26, 0x02, 0x21, 0x00]);
+        assert_eq!(&asn1_signature[asn1_signature.len() - 3..], &[0x02, 0x01, 0x01]);
+    }
+
+    struct NoVerifier;
+
+    #[async_trait::async_trait]
+    impl Verify for NoVerifier {
+        async fn public_key(&self, _did_url: &str) -> anyhow::Result<Vec<u8>> {
+            anyhow::bail!("no keys")
+        }
+    }
+
+    #[tokio::test]
+    async fn redirect_uri_must_be_the_client_id() {
+        let verifier = NoVerifier;
+        let redirect_uri: url::Url = "https://example.com/callback".parse().unwrap();
+
+        let result = validate_client_id_scheme(
+            "openid4vp://?client_id=https%3A%2F%2Fexample.com%2Fcallback&client_id_scheme=redirect_uri",
+            "https://example.com/callback",
+            &redirect_uri,
+            &verifier,
+        )
+        .await;
+        assert_eq!(result.status, ValidationStatus::Success);
+
+        let result = validate_client_id_scheme(
+            "openid4vp://?client_id=https%3A%2F%2Fexample.org&client_id_scheme=redirect_uri",
+            "https://example.org",
+            &redirect_uri,
+            &verifier,
+        )
+        .await;
+        assert_eq!(result.status, ValidationStatus::Failure);
+
+        let result = validate_client_id_scheme(
+            "openid4vp://?client_id=did%3Aexample%3A123&client_id_scheme=did",
+            "did:example:123",
+            &redirect_uri,
+            &verifier,
+        )
+        .await;
+        assert_eq!(result.status, ValidationStatus::Failure);
+    }
+
+    #[tokio::test]
+    async fn request_without_client_id_scheme_is_rejected() {
+        let redirect_uri: url::Url = "https://example.com/callback".parse().unwrap();
+
+        let result = validate_client_id_scheme(
+            "openid4vp://?client_id=https%3A%2F%2Fexample.com%2Fcallback",
+            "https://example.com/callback",
+            &redirect_uri,
+            &NoVerifier,
+        )
+        .await;
+        assert_eq!(result.status, ValidationStatus::Failure);
+
+        // A request object passed by reference needs to be resolved first.
+        let result = validate_client_id_scheme(
+            concat!(
+                "openid4vp://?client_id=example.com&client_id_scheme=x509_san_dns",
+                "&request_uri=https%3A%2F%2Fexample.com%2Frequest"
+            ),
+            "example.com",
+            &redirect_uri,
+            &NoVerifier,
+        )
+        .await;
+        assert_eq!(result.status, ValidationStatus::Failure);
+    }
+
+    #[tokio::test]
+    async fn unsupported_client_id_scheme_and_unresolvable_did_are_rejected() {
+        let key_pair = key_pair(
+            EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, &SystemRandom::new())
+                .unwrap()
+                .as_ref(),
+        );
+        let redirect_uri: url::Url = "https://example.com/callback".parse().unwrap();
+
+        let request = es256_jwt(
+            json!({}),
+            json!({ "client_id": "example.com", "client_id_scheme": "entity_id" }),
+            &key_pair,
+        );
+        let result = validate_client_id_scheme(
+            &by_value("example.com", &request),
+            "example.com",
+            &redirect_uri,
+            &NoVerifier,
+        )
+        .await;
+        assert_eq!(result.status, ValidationStatus::Failure);
+
+        let request = es256_jwt(
+            json!({ "kid": "did:example:123#key-1" }),
+            json!({ "client_id": "did:example:123", "client_id_scheme": "did" }),
+            &key_pair,
+        );
+        let result = validate_client_id_scheme(
+            &by_value("did:example:123", &request),
+            "did:example:123",
+            &redirect_uri,
+            &NoVerifier,
+        )
+        .await;
+        assert_eq!(result.status, ValidationStatus::Failure);
+    }
+
+    #[tokio::test]
+    async fn request_object_is_resolved_once() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/request.jwt"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("header.payload.signature\n"))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let form_urlencoded = request_object_by_value(&format!(
+            "openid4vp://?client_id=example.com&request_uri={}/request.jwt",
+            mock_server.uri()
+        ))
+        .await
+        .unwrap();
+
+        let parameters: HashMap<String, String> = url::Url::parse(&form_urlencoded)
+            .unwrap()
+            .query_pairs()
+            .into_owned()
+            .collect();
+        assert_eq!(
+            parameters,
+            HashMap::from([
+                ("client_id".to_string(), "example.com".to_string()),
+                ("request".to_string(), "header.payload.signature".to_string()),
+            ])
+        );
+
+        // A request object that is passed by value is left as is.
+        assert_eq!(
+            request_object_by_value(&form_urlencoded).await.unwrap(),
+            form_urlencoded
+        );
+    }
+
+    #[tokio::test]
+    #[serial_test::serial]
+    async fn x509_san_dns_request_is_signed_with_a_trusted_reader_certificate() {
+        *TRUST_STORE_DIR.lock().unwrap() = concat!(env!("CARGO_MANIFEST_DIR"), "/resources/test/trust_store").into();
+
+        let reader = key_pair(include_bytes!("../../../resources/test/verifier/reader-key.der"));
+        let x5c = json!({ "x5c": [STANDARD.encode(include_bytes!("../../../resources/test/verifier/reader.der"))] });
+        let request = |client_id: &str, key_pair: &EcdsaKeyPair| {
+            es256_jwt(
+                x5c.clone(),
+                json!({ "client_id": client_id, "client_id_scheme": "x509_san_dns" }),
+                key_pair,
+            )
+        };
+
+        let result = validate_client_id_scheme(
+            &by_value("example.com", &request("example.com", &reader)),
+            "example.com",
+            &"https://example.com/callback".parse().unwrap(),
+            &NoVerifier,
+        )
+        .await;
+        assert_eq!(result.status, ValidationStatus::Success);
+
+        // The certificate is not issued for another client.
+        let result = validate_client_id_scheme(
+            &by_value("example.org", &request("example.org", &reader)),
+            "example.org",
+            &"https://example.org/callback".parse().unwrap(),
+            &NoVerifier,
+        )
+        .await;
+        assert_eq!(result.status, ValidationStatus::Failure);
+
+        // The request object needs to be signed with the key of the certificate.
+        let other_key = key_pair(include_bytes!(
+            "../../../resources/test/verifier/attestation-issuer-key.der"
+        ));
+        let result = validate_client_id_scheme(
+            &by_value("example.com", &request("example.com", &other_key)),
+            "example.com",
+            &"https://example.com/callback".parse().unwrap(),
+            &NoVerifier,
+        )
+        .await;
+        assert_eq!(result.status, ValidationStatus::Failure);
+    }
+
+    #[tokio::test]
+    #[serial_test::serial]
+    async fn verifier_attestation_request_is_signed_with_the_attested_key() {
+        *TRUST_STORE_DIR.lock().unwrap() = concat!(env!("CARGO_MANIFEST_DIR"), "/resources/test/trust_store").into();
+
+        let attestation_issuer = key_pair(include_bytes!(
+            "../../../resources/test/verifier/attestation-issuer-key.der"
+        ));
+        let verifier = key_pair(
+            EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, &SystemRandom::new())
+                .unwrap()
+                .as_ref(),
+        );
+        let public_key = verifier.public_key().as_ref();
+        let attestation = |client_id: &str| {
+            es256_jwt(
+                json!({
+                    "typ": "verifier-attestation+jwt",
+                    "x5c": [STANDARD.encode(include_bytes!("../../../resources/test/verifier/attestation-issuer.der"))]
+                }),
+                json!({
+                    "iss": "attestation.example.com",
+                    "sub": client_id,
+                    "exp": Utc::now().timestamp() + 3600,
+                    "cnf": {
+                        "jwk": {
+                            "kty": "EC",
+                            "crv": "P-256",
+                            "x": URL_SAFE_NO_PAD.encode(&public_key[1..33]),
+                            "y": URL_SAFE_NO_PAD.encode(&public_key[33..65])
+                        }
+                    }
+                }),
+                &attestation_issuer,
+            )
+        };
+        let request = |attestation: String, key_pair: &EcdsaKeyPair| {
+            es256_jwt(
+                json!({ "jwt": attestation }),
+                json!({ "client_id": "example.com", "client_id_scheme": "verifier_attestation" }),
+                key_pair,
+            )
+        };
+        let redirect_uri: url::Url = "https://example.com/callback".parse().unwrap();
+
+        let result = validate_client_id_scheme(
+            &by_value("example.com", &request(attestation("example.com"), &verifier)),
+            "example.com",
+            &redirect_uri,
+            &NoVerifier,
+        )
+        .await;
+        assert_eq!(result.status, ValidationStatus::Success);
+
+        // The attestation needs to be issued for the client.
+        let result = validate_client_id_scheme(
+            &by_value("example.com", &request(attestation("example.org"), &verifier)),
+            "example.com",
+            &redirect_uri,
+            &NoVerifier,
+        )
+        .await;
+        assert_eq!(result.status, ValidationStatus::Failure);
+
+        // The request object needs to be signed with the attested key.
+        let result = validate_client_id_scheme(
+            &by_value("example.com", &request(attestation("example.com"), &attestation_issuer)),
+            "example.com",
+            &redirect_uri,
+            &NoVerifier,
+        )
+        .await;
+        assert_eq!(result.status, ValidationStatus::Failure);
+    }
+}
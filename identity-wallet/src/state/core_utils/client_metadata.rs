@@ -0,0 +1,179 @@
+use crate::error::AppError::{self, *};
+use crate::state::SUPPORTED_SIGNING_ALGORITHMS;
+
+use chrono::{DateTime, Duration, Utc};
+use jsonwebtoken::Algorithm;
+use lazy_static::lazy_static;
+use log::info;
+use oid4vc::oid4vc_core::client_metadata::ClientMetadataResource;
+use serde_json::Value;
+use std::{collections::HashMap, sync::Mutex};
+
+/// The time during which a fetched client metadata document is reused before it is fetched again.
+const CLIENT_METADATA_TTL_MINUTES: i64 = 60;
+
+lazy_static! {
+    // Client metadata documents are cached by their URI, so the (synchronous) helpers that read the client metadata of
+    // an authorization request can use them once they have been resolved.
+    static ref CLIENT_METADATA_CACHE: Mutex<HashMap<String, (DateTime<Utc>, Value)>> = Mutex::new(HashMap::new());
+}
+
+/// Fetches the client metadata document referenced by the `client_metadata_uri` of an authorization request, unless a
+/// recently fetched copy is cached.
+/// See: https://openid.net/specs/openid-connect-self-issued-v2-1_0.html#name-relying-party-metadata
+pub async fn resolve_client_metadata(client_metadata_uri: &str) -> Result<Value, AppError> {
+    if let Some(client_metadata) = cached_client_metadata(client_metadata_uri) {
+        return Ok(client_metadata);
+    }
+
+    info!("fetching client metadata: {}", client_metadata_uri);
+
+    let client_metadata: Value = reqwest::get(client_metadata_uri)
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .map_err(|e| GetClientMetadataError(client_metadata_uri.to_string(), e.into()))?
+        .json()
+        .await
+        .map_err(|e| GetClientMetadataError(client_metadata_uri.to_string(), e.into()))?;
+
+    if !client_metadata.is_object() {
+        return Err(GetClientMetadataError(
+            client_metadata_uri.to_string(),
+            anyhow::anyhow!("the client metadata is not a JSON object"),
+        ));
+    }
+
+    let mut client_metadata_cache = CLIENT_METADATA_CACHE.lock().unwrap();
+    // Expired documents are evicted, so the cache only holds the documents of recent requests.
+    client_metadata_cache.retain(|_, (fetched_at, _)| !is_expired(fetched_at));
+    client_metadata_cache.insert(client_metadata_uri.to_string(), (Utc::now(), client_metadata.clone()));
+
+    Ok(client_metadata)
+}
+
+/// Resolves the client metadata of an authorization request if it is passed by reference. Returns why it could not be
+/// resolved, if so.
+pub async fn resolve_client_metadata_resource<T>(client_metadata: &ClientMetadataResource<T>) -> Option<String> {
+    match client_metadata {
+        ClientMetadataResource::ClientMetadataUri(client_metadata_uri) => {
+            resolve_client_metadata(&client_metadata_uri.to_string())
+                .await
+                .err()
+                .map(|e| e.to_string())
+        }
+        ClientMetadataResource::ClientMetadata { .. } => None,
+    }
+}
+
+/// Returns the client metadata document that has been resolved for the `client_metadata_uri`, unless it has expired.
+pub fn cached_client_metadata(client_metadata_uri: &str) -> Option<Value> {
+    CLIENT_METADATA_CACHE
+        .lock()
+        .unwrap()
+        .get(client_metadata_uri)
+        .filter(|(fetched_at, _)| !is_expired(fetched_at))
+        .map(|(_, client_metadata)| client_metadata.clone())
+}
+
+fn is_expired(fetched_at: &DateTime<Utc>) -> bool {
+    Utc::now() - *fetched_at >= Duration::minutes(CLIENT_METADATA_TTL_MINUTES)
+}
+
+/// Returns the first algorithm the verifier supports for `jwt_vc_json` credentials according to its `vp_formats` that
+/// the wallet supports as well.
+pub fn jwt_vc_json_algorithm(vp_formats: &Value) -> Option<Algorithm> {
+    vp_formats["jwt_vc_json"]["alg"]
+        .as_array()?
+        .iter()
+        .filter_map(|algorithm| serde_json::from_value(algorithm.clone()).ok())
+        .find(|algorithm| SUPPORTED_SIGNING_ALGORITHMS.contains(algorithm))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::json;
+    use wiremock::{
+        matchers::{method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    #[tokio::test]
+    async fn client_metadata_is_fetched_once() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/client_metadata"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "client_name": "Verifier",
+                "vp_formats": { "jwt_vc_json": { "alg": ["ES256"] } }
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let client_metadata_uri = format!("{}/client_metadata", mock_server.uri());
+
+        assert!(cached_client_metadata(&client_metadata_uri).is_none());
+
+        let client_metadata = resolve_client_metadata(&client_metadata_uri).await.unwrap();
+        assert_eq!(
+            resolve_client_metadata(&client_metadata_uri).await.unwrap(),
+            client_metadata
+        );
+        assert_eq!(
+            cached_client_metadata(&client_metadata_uri),
+            Some(client_metadata.clone())
+        );
+
+        assert_eq!(client_metadata["client_name"], "Verifier");
+        assert_eq!(
+            jwt_vc_json_algorithm(&client_metadata["vp_formats"]),
+            Some(Algorithm::ES256)
+        );
+    }
+
+    #[test]
+    fn first_supported_jwt_vc_json_algorithm_is_used() {
+        assert_eq!(
+            jwt_vc_json_algorithm(&json!({ "jwt_vc_json": { "alg": ["ES256K", "RS256", "ES256", "EdDSA"] } })),
+            Some(Algorithm::ES256)
+        );
+        assert_eq!(
+            jwt_vc_json_algorithm(&json!({ "jwt_vc_json": { "alg": ["ES256K"] } })),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn unavailable_client_metadata_fails() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/client_metadata"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&mock_server)
+            .await;
+
+        assert!(matches!(
+            resolve_client_metadata(&format!("{}/client_metadata", mock_server.uri())).await,
+            Err(GetClientMetadataError(..))
+        ));
+    }
+
+    #[test]
+    fn expired_client_metadata_is_not_used() {
+        let client_metadata_uri = "https://example.com/expired_client_metadata";
+
+        CLIENT_METADATA_CACHE.lock().unwrap().insert(
+            client_metadata_uri.to_string(),
+            (
+                Utc::now() - Duration::minutes(CLIENT_METADATA_TTL_MINUTES),
+                json!({ "client_name": "Verifier" }),
+            ),
+        );
+
+        assert!(cached_client_metadata(client_metadata_uri).is_none());
+    }
+}
@@ -1,5 +1,19 @@
 use crate::error::AppError;
 use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use serde_json::Value;
+use std::{collections::HashSet, time::Duration};
+
+/// The time after which HTTP requests that are made while the user is waiting (e.g. for the verification of a
+/// credential) are aborted.
+pub const HTTP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Returns an HTTP client whose requests time out after `HTTP_TIMEOUT`.
+pub fn http_client() -> reqwest::Client {
+    reqwest::Client::builder()
+        .timeout(HTTP_TIMEOUT)
+        .build()
+        .unwrap_or_default()
+}
 
 /// Get the claims from a JWT without performing validation.
 pub fn get_unverified_jwt_claims(jwt: &serde_json::Value) -> Result<serde_json::Value, AppError> {
@@ -14,6 +28,69 @@ pub fn get_unverified_jwt_claims(jwt: &serde_json::Value) -> Result<serde_json::
         .ok_or(AppError::Error("Failed to decode JWT claims".to_string()))
 }
 
+/// Returns the paths of all claims that are referenced by the `path` of any of the fields in the presentation
+/// definition's input descriptors, split into their segments, e.g. `$.address.street_address` results in `address` and
+/// `street_address`, and `$['org.iso.18013.5.1']['family_name']` in `org.iso.18013.5.1` and `family_name`.
+pub fn requested_claim_paths(presentation_definition: &Value) -> HashSet<Vec<String>> {
+    presentation_definition["input_descriptors"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .flat_map(|input_descriptor| {
+            input_descriptor["constraints"]["fields"]
+                .as_array()
+                .into_iter()
+                .flatten()
+        })
+        .flat_map(|field| field["path"].as_array().into_iter().flatten())
+        .filter_map(Value::as_str)
+        .map(json_path_segments)
+        .collect()
+}
+
+// Splits a JSONPath into the names of its segments, in dot notation (`.name`) or bracket notation (`['name']`). The
+// root (`$`), wildcards and array indices are left out.
+fn json_path_segments(path: &str) -> Vec<String> {
+    let mut segments = Vec::new();
+    let mut rest = path.strip_prefix('$').unwrap_or(path);
+
+    while !rest.is_empty() {
+        let segment;
+        if let Some(bracketed) = rest.strip_prefix('[') {
+            match bracketed.chars().next() {
+                // A quoted name may contain dots and brackets itself.
+                Some(quote @ ('\'' | '"')) => {
+                    let name = &bracketed[1..];
+                    let end = name.find(quote).unwrap_or(name.len());
+                    segment = &name[..end];
+                    rest = name[end..].strip_prefix(quote).unwrap_or_default();
+                    rest = rest.strip_prefix(']').unwrap_or(rest);
+                }
+                _ => {
+                    let end = bracketed.find(']').unwrap_or(bracketed.len());
+                    segment = if bracketed[..end].parse::<usize>().is_ok() {
+                        ""
+                    } else {
+                        &bracketed[..end]
+                    };
+                    rest = bracketed[end..].strip_prefix(']').unwrap_or(&bracketed[end..]);
+                }
+            }
+        } else {
+            let name = rest.strip_prefix('.').unwrap_or(rest);
+            let end = name.find(['.', '[']).unwrap_or(name.len());
+            segment = &name[..end];
+            rest = &name[end..];
+        }
+
+        if !segment.is_empty() && segment != "*" {
+            segments.push(segment.to_string());
+        }
+    }
+
+    segments
+}
+
 pub struct DateUtils;
 
 impl DateUtils {
@@ -26,6 +103,23 @@ impl DateUtils {
 mod tests {
     use super::*;
 
+    #[test]
+    fn json_paths_are_split_into_claim_names() {
+        assert_eq!(
+            json_path_segments("$.address.street_address"),
+            vec!["address", "street_address"]
+        );
+        assert_eq!(
+            json_path_segments("$['org.iso.18013.5.1']['family_name']"),
+            vec!["org.iso.18013.5.1", "family_name"]
+        );
+        assert_eq!(
+            json_path_segments(r#"$["vc"].credentialSubject[*]"#),
+            vec!["vc", "credentialSubject"]
+        );
+        assert_eq!(json_path_segments("$.nationalities[0]"), vec!["nationalities"]);
+    }
+
     #[test]
     fn get_unverified_jwt_claims_successfully_gets_claims() {
         let jwt = serde_json::json!("eyJ0eXAiOiJKV1QiLCJhbGciOiJFZERTQSIsImtpZCI6ImRpZDprZXk6ejZNa2toUDQzTENTWGFqM1NRQm92eTF1RTJuWHZTQm5SUFdaMndoUExxblo4UGdEI3o2TWtraFA0M0xDU1hhajNTUUJvdnkxdUUyblh2U0JuUlBXWjJ3aFBMcW5aOFBnRCJ9.eyJpc3MiOiJodHRwOi8vMTkyLjE2OC4xLjEyNzo5MDkwLyIsInN1YiI6ImRpZDprZXk6ejZNa2cxWFhHVXFma2hBS1Uxa1ZkMVBtdzZVRWoxdnhpTGoxeGM5MU1CejVvd05ZIiwiZXhwIjo5OTk5OTk5OTk5LCJpYXQiOjAsInZjIjp7IkBjb250ZXh0IjpbImh0dHBzOi8vd3d3LnczLm9yZy8yMDE4L2NyZWRlbnRpYWxzL3YxIiwiaHR0cHM6Ly93d3cudzMub3JnLzIwMTgvY3JlZGVudGlhbHMvZXhhbXBsZXMvdjEiXSwidHlwZSI6WyJWZXJpZmlhYmxlQ3JlZGVudGlhbCIsIlBlcnNvbmFsSW5mb3JtYXRpb24iXSwiaXNzdWFuY2VEYXRlIjoiMjAyMi0wMS0wMVQwMDowMDowMFoiLCJpc3N1ZXIiOiJodHRwOi8vMTkyLjE2OC4xLjEyNzo5MDkwLyIsImNyZWRlbnRpYWxTdWJqZWN0Ijp7ImlkIjoiZGlkOmtleTp6Nk1rZzFYWEdVcWZraEFLVTFrVmQxUG13NlVFajF2eGlMajF4YzkxTUJ6NW93TlkiLCJnaXZlbk5hbWUiOiJGZXJyaXMiLCJmYW1pbHlOYW1lIjoiQ3JhYm1hbiIsImVtYWlsIjoiZmVycmlzLmNyYWJtYW5AY3JhYm1haWwuY29tIiwiYmlydGhkYXRlIjoiMTk4NS0wNS0yMSJ9fX0.Yl841U5BwWgctX5vF5Zi8SYCEQpxFqEs8_J8KrX9D_mOwL-IRmP64BeQZvnKeAdcOoYGn6CyciV51_amdPNQBw");
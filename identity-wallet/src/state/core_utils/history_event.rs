@@ -9,6 +9,11 @@ pub enum EventType {
     ConnectionAdded,
     CredentialsAdded,
     CredentialsShared,
+    CredentialsRefreshed,
+    /// The user declined an authorization request of the connection.
+    RequestDeclined,
+    /// The holder keys have been rotated. The credentials are the ones bound to the replaced keys.
+    KeysRotated,
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug, TS)]
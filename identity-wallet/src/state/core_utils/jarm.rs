@@ -0,0 +1,271 @@
+use crate::error::AppError::{self, *};
+
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng, Payload},
+    Aes256Gcm,
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use log::info;
+use oid4vc::{
+    oid4vc_core::{authorization_response::AuthorizationResponse, openid4vc_extension::Generic},
+    oid4vc_manager::ProviderManager,
+};
+use p256::{ecdh::EphemeralSecret, PublicKey};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+
+/// The response mode in which the authorization response is sent as an encrypted JWT (JARM).
+/// See: https://openid.net/specs/openid-4-verifiable-presentations-1_0.html#name-response-mode-direct_postjw
+pub const DIRECT_POST_JWT: &str = "direct_post.jwt";
+
+const ALG: &str = "ECDH-ES";
+const ENC: &str = "A256GCM";
+
+/// Returns `true` if the authorization request asks for the response to be sent as an encrypted JWT. The request body
+/// is passed as JSON, since the `response_mode` is shared by SIOPv2 and OID4VP requests.
+pub fn is_direct_post_jwt(authorization_request_body: &Value) -> bool {
+    authorization_request_body["response_mode"] == DIRECT_POST_JWT
+}
+
+/// Encrypts the authorization response parameters to the verifier's encryption key and sends them as the `response`
+/// parameter to the `redirect_uri` (the `response_uri` of the `direct_post.jwt` response mode). The `state` is part of
+/// the encrypted response parameters.
+pub async fn send_encrypted_response(
+    provider_manager: &ProviderManager,
+    redirect_uri: &url::Url,
+    client_metadata: &Value,
+    response_parameters: &Value,
+    mdoc_generated_nonce: Option<&str>,
+    nonce: Option<&str>,
+) -> Result<(), AppError> {
+    let response = encrypt_response(client_metadata, response_parameters, mdoc_generated_nonce, nonce)?;
+
+    info!("sending encrypted response to: {}", redirect_uri);
+
+    provider_manager
+        .send_response(&AuthorizationResponse::<Generic> {
+            redirect_uri: redirect_uri.to_string(),
+            state: None,
+            extension: json!({ "response": response }),
+        })
+        .await
+        .map_err(|_| SendAuthorizationResponseError)?;
+
+    Ok(())
+}
+
+/// Encrypts the response parameters as a JWE (compact serialization) using ECDH-ES key agreement with the P-256 key
+/// from the `jwks` of the client metadata and A256GCM content encryption. The `nonce` of the request is bound to the
+/// key agreement as `apv` and, for mdoc presentations, the `mdoc_generated_nonce` of the session transcript as `apu`.
+/// See: https://www.rfc-editor.org/rfc/rfc7518#section-4.6 and ISO/IEC 18013-7, Annex B
+pub fn encrypt_response(
+    client_metadata: &Value,
+    response_parameters: &Value,
+    mdoc_generated_nonce: Option<&str>,
+    nonce: Option<&str>,
+) -> Result<String, AppError> {
+    // Only the algorithms mandated by HAIP are supported.
+    for (parameter, supported) in [
+        ("authorization_encrypted_response_alg", ALG),
+        ("authorization_encrypted_response_enc", ENC),
+    ] {
+        if let Some(value) = client_metadata[parameter].as_str() {
+            if value != supported {
+                return Err(UnsupportedResponseEncryptionError(value.to_string()));
+            }
+        }
+    }
+
+    let (kid, verifier_key) = encryption_key(client_metadata)?;
+
+    let ephemeral_secret = EphemeralSecret::random(&mut OsRng);
+    let ephemeral_public_key: Value = serde_json::from_str(&PublicKey::from(&ephemeral_secret).to_jwk_string())
+        .map_err(|_| ResponseEncryptionError("failed to encode the ephemeral public key"))?;
+
+    let apu = mdoc_generated_nonce
+        .map(|mdoc_generated_nonce| mdoc_generated_nonce.as_bytes().to_vec())
+        .unwrap_or_default();
+    let apv = nonce.map(|nonce| nonce.as_bytes().to_vec()).unwrap_or_default();
+
+    let mut header = json!({
+        "alg": ALG,
+        "enc": ENC,
+        "epk": ephemeral_public_key,
+    });
+    if let Some(kid) = kid {
+        header["kid"] = json!(kid);
+    }
+    if !apu.is_empty() {
+        header["apu"] = json!(URL_SAFE_NO_PAD.encode(&apu));
+    }
+    if !apv.is_empty() {
+        header["apv"] = json!(URL_SAFE_NO_PAD.encode(&apv));
+    }
+
+    let shared_secret = ephemeral_secret.diffie_hellman(&verifier_key);
+    let content_encryption_key = concat_kdf(shared_secret.raw_secret_bytes(), ENC, &apu, &apv, 32);
+
+    let encoded_header = URL_SAFE_NO_PAD.encode(header.to_string());
+    let iv = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = Aes256Gcm::new_from_slice(&content_encryption_key)
+        .map_err(|_| ResponseEncryptionError("invalid content encryption key"))?
+        .encrypt(
+            &iv,
+            Payload {
+                msg: response_parameters.to_string().as_bytes(),
+                aad: encoded_header.as_bytes(),
+            },
+        )
+        .map_err(|_| ResponseEncryptionError("failed to encrypt the response"))?;
+
+    // The authentication tag is appended to the ciphertext.
+    let (ciphertext, tag) = ciphertext.split_at(ciphertext.len() - 16);
+
+    // With direct key agreement, the encrypted key is empty.
+    Ok(format!(
+        "{}..{}.{}.{}",
+        encoded_header,
+        URL_SAFE_NO_PAD.encode(iv),
+        URL_SAFE_NO_PAD.encode(ciphertext),
+        URL_SAFE_NO_PAD.encode(tag)
+    ))
+}
+
+// Returns the first P-256 key in the `jwks` of the client metadata that can be used for encryption, together with its
+// `kid`.
+fn encryption_key(client_metadata: &Value) -> Result<(Option<String>, PublicKey), AppError> {
+    client_metadata["jwks"]["keys"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|jwk| jwk["kty"] == "EC" && jwk["crv"] == "P-256")
+        .filter(|jwk| jwk.get("use").map_or(true, |r#use| *r#use == "enc"))
+        .filter(|jwk| jwk.get("alg").map_or(true, |alg| *alg == ALG))
+        .find_map(|jwk| {
+            PublicKey::from_jwk_str(&jwk.to_string())
+                .ok()
+                .map(|public_key| (jwk["kid"].as_str().map(ToString::to_string), public_key))
+        })
+        .ok_or(ResponseEncryptionError(
+            "no suitable encryption key in the client metadata",
+        ))
+}
+
+// The Concat KDF with SHA-256 for a key of at most 256 bits (`key_length` in bytes), which only takes a single round.
+// See: https://www.rfc-editor.org/rfc/rfc7518#section-4.6.2
+fn concat_kdf(shared_secret: &[u8], algorithm_id: &str, apu: &[u8], apv: &[u8], key_length: usize) -> Vec<u8> {
+    let with_length = |data: &[u8]| [&(data.len() as u32).to_be_bytes()[..], data].concat();
+
+    Sha256::new()
+        .chain_update(1u32.to_be_bytes())
+        .chain_update(shared_secret)
+        .chain_update(with_length(algorithm_id.as_bytes()))
+        .chain_update(with_length(apu))
+        .chain_update(with_length(apv))
+        .chain_update((key_length as u32 * 8).to_be_bytes())
+        .finalize()[..key_length]
+        .to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use aes_gcm::Nonce;
+    use p256::{ecdh::diffie_hellman, SecretKey};
+
+    #[test]
+    fn response_is_decryptable_by_verifier() {
+        let verifier_secret = SecretKey::random(&mut OsRng);
+        let mut verifier_jwk: Value = serde_json::from_str(&verifier_secret.public_key().to_jwk_string()).unwrap();
+        verifier_jwk["kid"] = json!("verifier-key");
+        verifier_jwk["use"] = json!("enc");
+
+        let client_metadata = json!({
+            "jwks": { "keys": [verifier_jwk] },
+            "authorization_encrypted_response_alg": "ECDH-ES",
+            "authorization_encrypted_response_enc": "A256GCM"
+        });
+        let response_parameters = json!({ "vp_token": "eyJ...", "state": "af0ifjsldkj" });
+
+        let response = encrypt_response(
+            &client_metadata,
+            &response_parameters,
+            Some("2f7c2f9a-1b5e-4d8c-9a3e-6b1f0c7d8e21"),
+            Some("n-0S6_WzA2Mj"),
+        )
+        .unwrap();
+
+        let parts: Vec<&str> = response.split('.').collect();
+        assert_eq!(parts.len(), 5);
+        assert!(parts[1].is_empty());
+
+        let header: Value = serde_json::from_slice(&URL_SAFE_NO_PAD.decode(parts[0]).unwrap()).unwrap();
+        assert_eq!(header["kid"], "verifier-key");
+        assert_eq!(
+            header["apu"],
+            URL_SAFE_NO_PAD.encode("2f7c2f9a-1b5e-4d8c-9a3e-6b1f0c7d8e21")
+        );
+        assert_eq!(header["apv"], URL_SAFE_NO_PAD.encode("n-0S6_WzA2Mj"));
+
+        let ephemeral_public_key = PublicKey::from_jwk_str(&header["epk"].to_string()).unwrap();
+        let shared_secret = diffie_hellman(verifier_secret.to_nonzero_scalar(), ephemeral_public_key.as_affine());
+        let content_encryption_key = concat_kdf(
+            shared_secret.raw_secret_bytes(),
+            ENC,
+            b"2f7c2f9a-1b5e-4d8c-9a3e-6b1f0c7d8e21",
+            b"n-0S6_WzA2Mj",
+            32,
+        );
+
+        let iv = URL_SAFE_NO_PAD.decode(parts[2]).unwrap();
+        let ciphertext = [
+            URL_SAFE_NO_PAD.decode(parts[3]).unwrap(),
+            URL_SAFE_NO_PAD.decode(parts[4]).unwrap(),
+        ]
+        .concat();
+        let plaintext = Aes256Gcm::new_from_slice(&content_encryption_key)
+            .unwrap()
+            .decrypt(
+                Nonce::from_slice(&iv),
+                Payload {
+                    msg: &ciphertext,
+                    aad: parts[0].as_bytes(),
+                },
+            )
+            .unwrap();
+
+        assert_eq!(
+            serde_json::from_slice::<Value>(&plaintext).unwrap(),
+            response_parameters
+        );
+    }
+
+    // The example key derivation of RFC 7518, Appendix C.
+    #[test]
+    fn concat_kdf_derives_the_known_answer() {
+        let shared_secret = [
+            158, 86, 217, 29, 129, 113, 53, 211, 114, 131, 66, 131, 191, 132, 38, 156, 251, 49, 110, 163, 218, 128,
+            106, 72, 246, 218, 167, 121, 140, 254, 144, 196,
+        ];
+
+        assert_eq!(
+            concat_kdf(&shared_secret, "A128GCM", b"Alice", b"Bob", 16),
+            vec![86, 170, 141, 234, 248, 35, 109, 32, 92, 34, 40, 205, 113, 167, 16, 26]
+        );
+    }
+
+    #[test]
+    fn unsupported_encryption_is_rejected() {
+        let client_metadata = json!({ "authorization_encrypted_response_alg": "RSA-OAEP-256" });
+
+        assert!(matches!(
+            encrypt_response(&client_metadata, &json!({}), None, None),
+            Err(UnsupportedResponseEncryptionError(_))
+        ));
+        assert!(matches!(
+            encrypt_response(&json!({ "jwks": { "keys": [] } }), &json!({}), None, None),
+            Err(ResponseEncryptionError(_))
+        ));
+    }
+}
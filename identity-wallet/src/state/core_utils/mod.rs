@@ -1,14 +1,27 @@
+pub mod client_id_scheme;
+pub mod client_metadata;
 pub mod helpers;
 pub mod history_event;
+pub mod jarm;
 
 pub use helpers::DateUtils;
 
+use crate::error::AppError::{self, *};
+use crate::state::connections::reducers::handle_siopv2_authorization_request::get_siopv2_client_name_and_logo_uri;
+use crate::state::credentials::authorization_code_flow::AuthorizationCodeFlow;
+use crate::state::credentials::reducers::handle_oid4vp_authorization_request::{
+    get_oid4vp_client_name_and_logo_uri, OID4VPClientMetadata,
+};
+use crate::state::did::validate_domain_linkage::{DomainValidation, ValidationResult, ValidationStatus};
+use crate::state::SUPPORTED_SIGNING_ALGORITHMS;
 use crate::stronghold::StrongholdManager;
+use crate::subject::Subject;
 
 use oid4vc::{
     oid4vc_core::{
         authorization_request::{AuthorizationRequest, Object},
-        Subject,
+        authorization_response::AuthorizationResponse,
+        openid4vc_extension::Generic,
     },
     oid4vc_manager::ProviderManager,
     oid4vci::{credential_offer::CredentialOfferParameters, Wallet},
@@ -16,7 +29,10 @@ use oid4vc::{
     siopv2::siopv2::SIOPv2,
 };
 
+use identity_iota::did::CoreDID;
+use log::info;
 use serde::{Deserialize, Serialize};
+use serde_json::json;
 use std::sync::Arc;
 
 /// CoreUtils is a struct that contains all the utils that only the rustside needs to perform its tasks.
@@ -27,6 +43,24 @@ pub struct CoreUtils {
     // TODO: These 'active_' fields should either be part of `oid4vc-manager`, or the `IdentityManager` struct.
     pub active_connection_request: Option<ConnectionRequest>,
     pub active_credential_offer: Option<CredentialOfferParameters>,
+    pub active_authorization_code_flow: Option<AuthorizationCodeFlow>,
+    /// The domain validation of the active connection request or credential offer, which is stored on the connection
+    /// once the request or offer is accepted.
+    pub active_domain_validation: Option<DomainValidation>,
+    /// The validation of the `client_id` of the active connection request. The DID of the relying party is only stored
+    /// on the connection if the relying party has authenticated itself with it.
+    pub active_client_id_validation: Option<ValidationResult>,
+}
+
+impl CoreUtils {
+    /// Returns the DID of the relying party of the active connection request, if its `client_id` is a DID that has been
+    /// validated successfully.
+    pub fn validated_client_did(&self, client_id: &str) -> Option<CoreDID> {
+        self.active_client_id_validation
+            .as_ref()
+            .filter(|client_id_validation| client_id_validation.status == ValidationStatus::Success)
+            .and_then(|_| CoreDID::parse(client_id).ok())
+    }
 }
 /// Managers contains both the stronghold manager and the identity manager needed to perform operations on connections & credentials.
 #[derive(Default)]
@@ -37,13 +71,95 @@ pub struct Managers {
 
 /// IdentityManager contains the subject, provider_manager and wallet needed to perform operations within the oid4vc library.
 pub struct IdentityManager {
-    pub subject: Arc<dyn Subject>,
+    pub subject: Arc<Subject>,
     pub provider_manager: ProviderManager,
     pub wallet: Wallet,
 }
 
+impl IdentityManager {
+    /// Returns an `IdentityManager` that signs with the keys at the given index, such as the pairwise keys of a
+    /// connection, for the given DID methods. The keys of this `IdentityManager` are unaffected.
+    pub async fn with_keys(&self, key_index: u32, did_methods: Vec<&'static str>) -> Result<IdentityManager, AppError> {
+        let subject = self.subject.with_keys(key_index).await.map_err(KeyActivationError)?;
+
+        let provider_manager = ProviderManager::new(
+            subject.clone(),
+            did_methods.clone(),
+            Vec::from(SUPPORTED_SIGNING_ALGORITHMS),
+        )
+        .map_err(OID4VCProviderManagerError)?;
+        let wallet = Wallet::new(subject.clone(), did_methods, Vec::from(SUPPORTED_SIGNING_ALGORITHMS))
+            .map_err(OID4VCWalletError)?;
+
+        Ok(IdentityManager {
+            subject,
+            provider_manager,
+            wallet,
+        })
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 pub enum ConnectionRequest {
     SIOPv2(Box<AuthorizationRequest<Object<SIOPv2>>>),
     OID4VP(Box<AuthorizationRequest<Object<OID4VP>>>),
 }
+
+impl ConnectionRequest {
+    /// Returns the name and the connection url of the relying party.
+    pub fn client_name_and_connection_url(&self) -> (String, String) {
+        match self {
+            ConnectionRequest::SIOPv2(siopv2_authorization_request) => {
+                let (client_name, _, connection_url, _) =
+                    get_siopv2_client_name_and_logo_uri(siopv2_authorization_request);
+                (client_name, connection_url)
+            }
+            ConnectionRequest::OID4VP(oid4vp_authorization_request) => {
+                let OID4VPClientMetadata {
+                    client_name,
+                    connection_url,
+                    ..
+                } = get_oid4vp_client_name_and_logo_uri(oid4vp_authorization_request);
+                (client_name, connection_url)
+            }
+        }
+    }
+
+    /// Sends an `access_denied` error response to the relying party, including the `state` of the request, so it does
+    /// not have to wait for the request to time out.
+    /// See: https://openid.net/specs/openid-4-verifiable-presentations-1_0.html#name-error-response
+    pub async fn send_access_denied(
+        &self,
+        provider_manager: &ProviderManager,
+        error_description: &str,
+    ) -> Result<(), AppError> {
+        let (redirect_uri, state) = match self {
+            ConnectionRequest::SIOPv2(siopv2_authorization_request) => (
+                &siopv2_authorization_request.body.redirect_uri,
+                &siopv2_authorization_request.body.state,
+            ),
+            ConnectionRequest::OID4VP(oid4vp_authorization_request) => (
+                &oid4vp_authorization_request.body.redirect_uri,
+                &oid4vp_authorization_request.body.state,
+            ),
+        };
+
+        let error_response = AuthorizationResponse::<Generic> {
+            redirect_uri: redirect_uri.to_string(),
+            state: state.clone(),
+            extension: json!({
+                "error": "access_denied",
+                "error_description": error_description,
+            }),
+        };
+
+        info!("sending error response to: {}", redirect_uri);
+
+        provider_manager
+            .send_response(&error_response)
+            .await
+            .map_err(|_| SendAuthorizationResponseError)?;
+
+        Ok(())
+    }
+}
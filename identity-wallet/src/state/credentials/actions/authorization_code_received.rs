@@ -0,0 +1,26 @@
+use crate::reducer;
+use crate::state::credentials::reducers::exchange_authorization_code::exchange_authorization_code;
+use crate::state::profile_settings::reducers::update_sorting_preference::{sort_connections, sort_credentials};
+use crate::state::{actions::ActionTrait, Reducer};
+
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+/// Action to handle the redirect callback of the authorization server, containing the authorization code.
+#[derive(Serialize, Deserialize, Debug, TS, Clone)]
+#[ts(export, export_to = "bindings/actions/AuthorizationCodeReceived.ts")]
+pub struct AuthorizationCodeReceived {
+    pub code: String,
+    pub state: String,
+}
+
+#[typetag::serde(name = "[Credential Offer] Authorization code received")]
+impl ActionTrait for AuthorizationCodeReceived {
+    fn reducers<'a>(&self) -> Vec<Reducer<'a>> {
+        vec![
+            reducer!(exchange_authorization_code),
+            reducer!(sort_credentials),
+            reducer!(sort_connections),
+        ]
+    }
+}
@@ -11,6 +11,9 @@ use ts_rs::TS;
 #[ts(export, export_to = "bindings/actions/CredentialOffersSelected.ts")]
 pub struct CredentialOffersSelected {
     pub credential_configuration_ids: Vec<String>,
+    /// The transaction code entered by the user, required when the credential offer contains a `tx_code` object.
+    #[ts(optional)]
+    pub tx_code: Option<String>,
 }
 
 #[typetag::serde(name = "[Credential Offer] Selected")]
@@ -3,6 +3,7 @@ use crate::state::credentials::reducers::handle_oid4vp_authorization_request::ha
 use crate::state::{actions::ActionTrait, Reducer};
 
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use ts_rs::TS;
 
 /// Action to authenticate the selected credentials.
@@ -11,6 +12,12 @@ use ts_rs::TS;
 pub struct CredentialsSelected {
     #[ts(type = "Array<string>")]
     pub credential_uuids: Vec<uuid::Uuid>,
+    /// The credential the user has chosen for each input descriptor, by input descriptor id. Input descriptors that are
+    /// left out are not fulfilled. When absent, each input descriptor is fulfilled by the first matching credential of
+    /// `credential_uuids`.
+    #[serde(default)]
+    #[ts(optional, type = "Record<string, string>")]
+    pub input_descriptor_selection: Option<HashMap<String, uuid::Uuid>>,
 }
 
 #[typetag::serde(name = "[Authenticate] Credentials selected")]
@@ -1,4 +1,9 @@
+pub mod authorization_code_received;
 pub mod credential_offers_selected;
 pub mod credentials_selected;
 pub mod delete_credential;
+pub mod poll_deferred_credentials;
+pub mod presentation_declined;
+pub mod refresh_credential;
+pub mod refresh_credential_statuses;
 pub mod update_credential_metadata;
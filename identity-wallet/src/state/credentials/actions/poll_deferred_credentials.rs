@@ -0,0 +1,17 @@
+use crate::reducer;
+use crate::state::credentials::reducers::poll_deferred_credentials::poll_deferred_credentials;
+use crate::state::profile_settings::reducers::update_sorting_preference::sort_credentials;
+use crate::state::{actions::ActionTrait, Reducer};
+
+use serde::{Deserialize, Serialize};
+
+/// Action to check whether any of the pending (deferred) credentials have been issued in the meantime.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PollDeferredCredentials;
+
+#[typetag::serde(name = "[Credential] Poll deferred")]
+impl ActionTrait for PollDeferredCredentials {
+    fn reducers<'a>(&self) -> Vec<Reducer<'a>> {
+        vec![reducer!(poll_deferred_credentials), reducer!(sort_credentials)]
+    }
+}
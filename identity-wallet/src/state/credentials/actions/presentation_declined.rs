@@ -0,0 +1,16 @@
+use crate::reducer;
+use crate::state::credentials::reducers::decline_presentation::decline_presentation;
+use crate::state::{actions::ActionTrait, Reducer};
+
+use serde::{Deserialize, Serialize};
+
+/// Action to decline the active presentation request, for example because none of the stored credentials match it.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PresentationDeclined;
+
+#[typetag::serde(name = "[Authenticate] Presentation declined")]
+impl ActionTrait for PresentationDeclined {
+    fn reducers<'a>(&self) -> Vec<Reducer<'a>> {
+        vec![reducer!(decline_presentation)]
+    }
+}
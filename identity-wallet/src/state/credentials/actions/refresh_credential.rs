@@ -0,0 +1,23 @@
+use crate::reducer;
+use crate::state::credentials::reducers::refresh_credential::refresh_credential;
+use crate::state::profile_settings::reducers::update_sorting_preference::sort_credentials;
+use crate::state::{actions::ActionTrait, Reducer};
+
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+/// Action to replace a (for example expired) credential with a newly issued one for the same credential
+/// configuration.
+#[derive(Serialize, Deserialize, Debug, TS, Clone)]
+#[ts(export, export_to = "bindings/actions/RefreshCredential.ts")]
+pub struct RefreshCredential {
+    #[ts(type = "string")]
+    pub id: uuid::Uuid,
+}
+
+#[typetag::serde(name = "[Credential] Refresh")]
+impl ActionTrait for RefreshCredential {
+    fn reducers<'a>(&self) -> Vec<Reducer<'a>> {
+        vec![reducer!(refresh_credential), reducer!(sort_credentials)]
+    }
+}
@@ -0,0 +1,17 @@
+use crate::reducer;
+use crate::state::credentials::reducers::refresh_credential_statuses::refresh_credential_statuses;
+use crate::state::profile_settings::reducers::update_sorting_preference::sort_credentials;
+use crate::state::{actions::ActionTrait, Reducer};
+
+use serde::{Deserialize, Serialize};
+
+/// Action to check the revocation status of all credentials against the status lists of their issuers.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RefreshCredentialStatuses;
+
+#[typetag::serde(name = "[Credential] Refresh statuses")]
+impl ActionTrait for RefreshCredentialStatuses {
+    fn reducers<'a>(&self) -> Vec<Reducer<'a>> {
+        vec![reducer!(refresh_credential_statuses), reducer!(sort_credentials)]
+    }
+}
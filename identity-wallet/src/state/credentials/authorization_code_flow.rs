@@ -0,0 +1,136 @@
+use crate::state::did::validate_domain_linkage::DomainValidation;
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use rand::{distributions::Alphanumeric, Rng};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+
+/// The redirect URI registered by UniMe. The authorization server redirects the user to this URI after the user has
+/// authenticated, after which the frontend dispatches the `code` and `state` back to the backend.
+pub const AUTHORIZATION_REDIRECT_URI: &str = "unime://oid4vci/callback";
+
+/// Contains everything that needs to be remembered in between sending the user to the authorization endpoint and
+/// receiving the authorization code through the redirect callback.
+#[derive(Clone, Debug)]
+pub struct AuthorizationCodeFlow {
+    pub credential_configuration_ids: Vec<String>,
+    pub code_verifier: String,
+    pub state: String,
+    pub redirect_uri: String,
+    pub logo_uri: Option<String>,
+    /// The domain validation of the credential issuer, which is stored on its connection.
+    pub domain_validation: DomainValidation,
+}
+
+impl AuthorizationCodeFlow {
+    pub fn new(credential_configuration_ids: Vec<String>, logo_uri: Option<String>) -> Self {
+        Self {
+            credential_configuration_ids,
+            code_verifier: random_string(64),
+            state: random_string(32),
+            redirect_uri: AUTHORIZATION_REDIRECT_URI.to_string(),
+            logo_uri,
+            domain_validation: DomainValidation::default(),
+        }
+    }
+
+    /// Returns the PKCE `code_challenge` derived from the `code_verifier` using the `S256` method.
+    /// See: https://datatracker.ietf.org/doc/html/rfc7636#section-4.2
+    pub fn code_challenge(&self) -> String {
+        URL_SAFE_NO_PAD.encode(Sha256::digest(self.code_verifier.as_bytes()))
+    }
+
+    /// Builds the URL the user needs to open in order to authenticate at the authorization server.
+    pub fn authorization_url(
+        &self,
+        mut authorization_endpoint: url::Url,
+        client_id: &str,
+        issuer_state: Option<&str>,
+    ) -> url::Url {
+        let authorization_details = self
+            .credential_configuration_ids
+            .iter()
+            .map(|credential_configuration_id| {
+                json!({
+                    "type": "openid_credential",
+                    "credential_configuration_id": credential_configuration_id,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        {
+            let mut query_pairs = authorization_endpoint.query_pairs_mut();
+            query_pairs
+                .append_pair("response_type", "code")
+                .append_pair("client_id", client_id)
+                .append_pair("redirect_uri", &self.redirect_uri)
+                .append_pair("state", &self.state)
+                .append_pair("code_challenge", &self.code_challenge())
+                .append_pair("code_challenge_method", "S256")
+                .append_pair("authorization_details", &json!(authorization_details).to_string());
+
+            if let Some(issuer_state) = issuer_state {
+                query_pairs.append_pair("issuer_state", issuer_state);
+            }
+        }
+
+        authorization_endpoint
+    }
+}
+
+/// Generates a random string consisting of alphanumeric characters, which are all part of the unreserved characters
+/// allowed in a PKCE `code_verifier`.
+fn random_string(length: usize) -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(length)
+        .map(char::from)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn code_challenge_is_derived_from_code_verifier() {
+        // Example taken from: https://datatracker.ietf.org/doc/html/rfc7636#appendix-B
+        let authorization_code_flow = AuthorizationCodeFlow {
+            code_verifier: "dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk".to_string(),
+            ..AuthorizationCodeFlow::new(vec![], None)
+        };
+
+        assert_eq!(
+            authorization_code_flow.code_challenge(),
+            "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM"
+        );
+    }
+
+    #[test]
+    fn authorization_url_contains_all_parameters() {
+        let authorization_code_flow = AuthorizationCodeFlow::new(vec!["UniversityDegree".to_string()], None);
+
+        let authorization_url = authorization_code_flow.authorization_url(
+            "https://auth.example.com/authorize".parse().unwrap(),
+            "did:key:z6Mkg1XXGUqfkhAKU1kVd1Pmw6UEj1vxiLj1xc91MBz5owNY",
+            Some("eyJhbGciOiJSU0Et"),
+        );
+
+        let query_pairs: std::collections::HashMap<_, _> = authorization_url.query_pairs().into_owned().collect();
+
+        assert_eq!(query_pairs["response_type"], "code");
+        assert_eq!(
+            query_pairs["client_id"],
+            "did:key:z6Mkg1XXGUqfkhAKU1kVd1Pmw6UEj1vxiLj1xc91MBz5owNY"
+        );
+        assert_eq!(query_pairs["redirect_uri"], AUTHORIZATION_REDIRECT_URI);
+        assert_eq!(query_pairs["state"], authorization_code_flow.state);
+        assert_eq!(query_pairs["code_challenge"], authorization_code_flow.code_challenge());
+        assert_eq!(query_pairs["code_challenge_method"], "S256");
+        assert_eq!(query_pairs["issuer_state"], "eyJhbGciOiJSU0Et");
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&query_pairs["authorization_details"]).unwrap(),
+            json!([{ "type": "openid_credential", "credential_configuration_id": "UniversityDegree" }])
+        );
+    }
+}
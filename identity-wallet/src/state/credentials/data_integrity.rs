@@ -0,0 +1,358 @@
+use crate::{
+    error::AppError::{self, *},
+    subject::Subject,
+};
+
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use jsonwebtoken::Algorithm;
+use lazy_static::lazy_static;
+use log::info;
+use oid4vc::oid4vc_core::{Sign, Verify};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use ssi_json_ld::{urdna2015, ContextLoader};
+use std::{collections::HashMap, sync::Mutex};
+
+/// The cryptosuite used for the proofs created by UniMe.
+/// See: https://www.w3.org/TR/vc-di-eddsa/#eddsa-rdfc-2022
+const EDDSA_RDFC_2022: &str = "eddsa-rdfc-2022";
+
+/// The JSON-LD context which defines the terms of a `DataIntegrityProof`.
+/// See: https://www.w3.org/TR/vc-data-integrity/#contexts-and-vocabularies
+pub const DATA_INTEGRITY_V2_CONTEXT: &str = "https://w3id.org/security/data-integrity/v2";
+
+/// The JSON-LD contexts that are included in `ContextLoader::default()` and therefore never fetched.
+const STATIC_CONTEXTS: [&str; 6] = [
+    "https://www.w3.org/2018/credentials/v1",
+    "https://www.w3.org/2018/credentials/examples/v1",
+    "https://www.w3.org/ns/did/v1",
+    "https://w3id.org/security/v1",
+    "https://w3id.org/security/v2",
+    "https://w3id.org/security/suites/ed25519-2020/v1",
+];
+
+lazy_static! {
+    // Remote JSON-LD contexts (such as the Open Badges 3.0 contexts) are cached by their URL, since they are immutable.
+    static ref CONTEXT_CACHE: Mutex<HashMap<String, String>> = Mutex::new(HashMap::new());
+}
+
+/// Returns `true` if the credential is a JSON-LD credential secured with an embedded (Data Integrity) proof (`ldp_vc`).
+pub fn is_ldp_vc(credential: &Value) -> bool {
+    credential.is_object() && credential.get("proof").is_some()
+}
+
+/// Verifies the Data Integrity proof of a JSON-LD credential. Supported are `DataIntegrityProof` with the
+/// `eddsa-rdfc-2022` cryptosuite and the older `Ed25519Signature2020`, which share the same transformation and hashing
+/// algorithms. The proof needs to be an `assertionMethod` proof made with a key of the credential's issuer.
+pub async fn verify_proof(credential: &Value, verifier: &dyn Verify) -> Result<(), AppError> {
+    let proof = credential.get("proof").ok_or(InvalidProofError)?;
+
+    match (proof["type"].as_str(), proof["cryptosuite"].as_str()) {
+        (Some("DataIntegrityProof"), Some(EDDSA_RDFC_2022)) | (Some("Ed25519Signature2020"), _) => {}
+        (proof_type, cryptosuite) => {
+            return Err(UnsupportedProofError(
+                cryptosuite.or(proof_type).unwrap_or("unknown").to_string(),
+            ))
+        }
+    }
+
+    let verification_method = proof["verificationMethod"].as_str().ok_or(InvalidProofError)?;
+    let issuer = credential["issuer"].as_str().or(credential["issuer"]["id"].as_str());
+    if verification_method.split('#').next() != issuer || proof["proofPurpose"] != "assertionMethod" {
+        return Err(InvalidProofError);
+    }
+
+    let proof_value = proof["proofValue"]
+        .as_str()
+        .and_then(|proof_value| multibase::decode(proof_value).ok())
+        .map(|(_, proof_value)| proof_value)
+        .ok_or(InvalidProofError)?;
+
+    let document = without(credential, "proof");
+    let proof_config = without(proof, "proofValue");
+
+    let hash_data = hash_data(&document, proof_config).await?;
+
+    let public_key: [u8; 32] = verifier
+        .public_key(verification_method)
+        .await
+        .ok()
+        .and_then(|public_key| public_key.try_into().ok())
+        .ok_or(InvalidProofError)?;
+    let signature = Signature::from_slice(&proof_value).map_err(|_| InvalidProofError)?;
+
+    VerifyingKey::from_bytes(&public_key)
+        .and_then(|verifying_key| verifying_key.verify(&hash_data, &signature))
+        .map_err(|_| InvalidProofError)
+}
+
+/// Adds an `eddsa-rdfc-2022` proof to the (presentation) document, signed with the holder's Ed25519 key. The
+/// `challenge` and `domain` bind the proof to a single authorization request of a single verifier. The `@context` of
+/// the document needs to include the `DATA_INTEGRITY_V2_CONTEXT`.
+pub async fn add_proof(
+    mut document: Value,
+    subject: &Subject,
+    subject_syntax_type: &str,
+    proof_purpose: &str,
+    challenge: &str,
+    domain: &str,
+) -> Result<Value, AppError> {
+    let verification_method = subject
+        .key_id(subject_syntax_type, Algorithm::EdDSA)
+        .await
+        .ok_or(MissingStateParameterError("verification method"))?;
+
+    let mut proof = json!({
+        "type": "DataIntegrityProof",
+        "cryptosuite": EDDSA_RDFC_2022,
+        "created": chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        "verificationMethod": verification_method,
+        "proofPurpose": proof_purpose,
+        "challenge": challenge,
+        "domain": domain,
+    });
+
+    let hash_data = hash_data(&document, proof.clone()).await?;
+
+    let signature = subject
+        .sign_bytes(&hash_data, Algorithm::EdDSA)
+        .await
+        .map_err(DataIntegrityProofError)?;
+
+    proof["proofValue"] = json!(multibase::encode(multibase::Base::Base58Btc, signature));
+    document["proof"] = proof;
+
+    Ok(document)
+}
+
+/// Returns a copy of the JSON object without the given property.
+pub fn without(object: &Value, property: &str) -> Value {
+    let mut object = object.clone();
+    if let Some(object) = object.as_object_mut() {
+        object.remove(property);
+    }
+    object
+}
+
+// Canonicalizes both the proof configuration (using the `@context` of the document) and the document, and
+// concatenates their SHA-256 hashes.
+// See: https://www.w3.org/TR/vc-di-eddsa/#transformation-eddsa-rdfc-2022
+async fn hash_data(document: &Value, mut proof_config: Value) -> Result<Vec<u8>, AppError> {
+    proof_config["@context"] = document["@context"].clone();
+
+    let proof_config_hash = Sha256::digest(canonicalize(&proof_config).await?.as_bytes());
+    let document_hash = Sha256::digest(canonicalize(document).await?.as_bytes());
+
+    Ok([proof_config_hash, document_hash].concat())
+}
+
+// Canonicalizes a JSON-LD document to N-Quads using the RDF Dataset Canonicalization algorithm (URDNA2015 / RDFC-1.0).
+async fn canonicalize(document: &Value) -> Result<String, AppError> {
+    let json = ssi_json_ld::syntax::to_value_with(document, Default::default)
+        .map_err(|e| JsonLdCanonicalizationError(anyhow::anyhow!("{e}")))?;
+
+    let dataset = ssi_json_ld::json_to_dataset(json, &mut context_loader(document).await?, None, false, None)
+        .await
+        .map_err(|e| JsonLdCanonicalizationError(anyhow::anyhow!("{e}")))?;
+
+    Ok(urdna2015::normalize(dataset.quads().map(Into::into)).into_nquads())
+}
+
+// Returns a context loader with the contexts of `ContextLoader::default()` and the other remote contexts referenced by
+// the document, which are fetched unless they have been fetched before.
+async fn context_loader(document: &Value) -> Result<ContextLoader, AppError> {
+    let mut context_map = HashMap::new();
+
+    for url in context_urls(document) {
+        if STATIC_CONTEXTS.contains(&url.as_str()) {
+            continue;
+        }
+
+        let cached_context = CONTEXT_CACHE.lock().unwrap().get(&url).cloned();
+        let context = match cached_context {
+            Some(context) => context,
+            None => {
+                info!("fetching JSON-LD context: {}", url);
+
+                let context = reqwest::Client::new()
+                    .get(&url)
+                    .header(reqwest::header::ACCEPT, "application/ld+json, application/json")
+                    .send()
+                    .await
+                    .and_then(reqwest::Response::error_for_status)
+                    .map_err(|e| JsonLdCanonicalizationError(e.into()))?
+                    .text()
+                    .await
+                    .map_err(|e| JsonLdCanonicalizationError(e.into()))?;
+
+                CONTEXT_CACHE.lock().unwrap().insert(url.clone(), context.clone());
+                context
+            }
+        };
+
+        context_map.insert(url, context);
+    }
+
+    ContextLoader::default()
+        .with_context_map_from(context_map)
+        .map_err(|e| JsonLdCanonicalizationError(anyhow::anyhow!("{e}")))
+}
+
+// Returns the URLs of all remote contexts referenced in the `@context` properties of the document (including the
+// `@context` properties of nested objects).
+fn context_urls(value: &Value) -> Vec<String> {
+    match value {
+        Value::Object(object) => object
+            .iter()
+            .flat_map(|(key, value)| match (key.as_str(), value) {
+                ("@context", Value::String(url)) => vec![url.clone()],
+                ("@context", Value::Array(contexts)) => contexts
+                    .iter()
+                    .flat_map(|context| match context {
+                        Value::String(url) => vec![url.clone()],
+                        context => context_urls(context),
+                    })
+                    .collect(),
+                (_, value) => context_urls(value),
+            })
+            .collect(),
+        Value::Array(values) => values.iter().flat_map(context_urls).collect(),
+        _ => vec![],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use wiremock::{
+        matchers::{method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    #[test]
+    fn ldp_vc_is_recognized_by_its_embedded_proof() {
+        assert!(is_ldp_vc(&json!({
+            "@context": ["https://www.w3.org/2018/credentials/v1"],
+            "type": ["VerifiableCredential"],
+            "proof": { "type": "Ed25519Signature2020" }
+        })));
+        assert!(!is_ldp_vc(&json!("eyJhbGciOiJFZERTQSJ9.e30.c2ln")));
+        assert!(!is_ldp_vc(&json!({ "type": ["VerifiableCredential"] })));
+    }
+
+    #[tokio::test]
+    async fn proof_needs_to_be_an_assertion_of_the_issuer() {
+        struct NoKeys;
+
+        #[async_trait::async_trait]
+        impl Verify for NoKeys {
+            async fn public_key(&self, did_url: &str) -> anyhow::Result<Vec<u8>> {
+                anyhow::bail!("unknown key: {did_url}")
+            }
+        }
+
+        let credential = |issuer: Value, proof_purpose: &str| {
+            json!({
+                "@context": ["https://www.w3.org/2018/credentials/v1"],
+                "type": ["VerifiableCredential"],
+                "issuer": issuer,
+                "proof": {
+                    "type": "DataIntegrityProof",
+                    "cryptosuite": EDDSA_RDFC_2022,
+                    "verificationMethod": "did:example:issuer#key-1",
+                    "proofPurpose": proof_purpose,
+                    "proofValue": "z3FXQjecWufY46yg5abdVZsXqLhxhueuSoZgNSARiKBk9czhSePTFehP8c3PGfb6a22gk"
+                }
+            })
+        };
+
+        // Proofs made with a key of another DID or for another purpose are rejected before any key is resolved.
+        for credential in [
+            credential(json!("did:example:other"), "assertionMethod"),
+            credential(json!({ "id": "did:example:other" }), "assertionMethod"),
+            credential(json!("did:example:issuer"), "authentication"),
+        ] {
+            assert!(matches!(
+                verify_proof(&credential, &NoKeys).await,
+                Err(InvalidProofError)
+            ));
+        }
+    }
+
+    #[test]
+    fn context_urls_include_nested_contexts() {
+        assert_eq!(
+            context_urls(&json!({
+                "@context": ["https://www.w3.org/2018/credentials/v1", { "ex": "https://example.com/vocab#" }],
+                "verifiableCredential": [{
+                    "@context": "https://purl.imsglobal.org/spec/ob/v3p0/context-3.0.2.json",
+                }]
+            })),
+            vec![
+                "https://www.w3.org/2018/credentials/v1".to_string(),
+                "https://purl.imsglobal.org/spec/ob/v3p0/context-3.0.2.json".to_string()
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn remote_contexts_are_fetched_once_for_expansion() {
+        let mock_server = MockServer::start().await;
+
+        // A subset of the Open Badges 3.0 context.
+        Mock::given(method("GET"))
+            .and(path("/spec/ob/v3p0/context-3.0.2.json"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "@context": {
+                    "id": "@id",
+                    "type": "@type",
+                    "OpenBadgeCredential": {
+                        "@id": "https://purl.imsglobal.org/spec/vc/ob/vocab.html#OpenBadgeCredential"
+                    },
+                    "AchievementSubject": {
+                        "@id": "https://purl.imsglobal.org/spec/vc/ob/vocab.html#AchievementSubject",
+                        "@context": {
+                            "achievement": { "@id": "https://purl.imsglobal.org/spec/vc/ob/vocab.html#achievement" }
+                        }
+                    },
+                    "Achievement": {
+                        "@id": "https://purl.imsglobal.org/spec/vc/ob/vocab.html#Achievement",
+                        "@context": {
+                            "name": { "@id": "https://schema.org/name" }
+                        }
+                    }
+                }
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let credential = json!({
+            "@context": [
+                "https://www.w3.org/2018/credentials/v1",
+                format!("{}/spec/ob/v3p0/context-3.0.2.json", mock_server.uri())
+            ],
+            "id": "urn:uuid:a63a60be-f4af-491c-87fc-2c8fd3007a58",
+            "type": ["VerifiableCredential", "OpenBadgeCredential"],
+            "issuer": "did:key:z6MkpGR4gs4Rc3Zph4vj8wRnjnAxgAPSxcR8MAVKutWspQzc",
+            "issuanceDate": "2024-01-01T00:00:00Z",
+            "credentialSubject": {
+                "id": "did:key:z6MkgE84NCMpMeAx9jK9cf5W4G8gcZ9xuwJvG1e7wNk8KCgt",
+                "type": "AchievementSubject",
+                "achievement": {
+                    "id": "urn:uuid:ac254bd5-8fad-4bb1-9d29-efd938536926",
+                    "type": "Achievement",
+                    "name": "Our Wallet Passed JFF Plugfest #3 2023"
+                }
+            }
+        });
+
+        let nquads = canonicalize(&credential).await.unwrap();
+        assert!(nquads.contains("<https://purl.imsglobal.org/spec/vc/ob/vocab.html#OpenBadgeCredential>"));
+        assert!(nquads.contains("<https://schema.org/name> \"Our Wallet Passed JFF Plugfest #3 2023\""));
+
+        // The context is cached.
+        assert_eq!(canonicalize(&credential).await.unwrap(), nquads);
+    }
+}
@@ -0,0 +1,742 @@
+use crate::{
+    error::AppError::{self, *},
+    state::{
+        core_utils::client_id_scheme::{asn1_ecdsa_signature, validate_certificate_chain},
+        did::validate_domain_linkage::{ValidationResult, ValidationStatus},
+    },
+    subject::Subject,
+};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use ciborium::Value as CborValue;
+use jsonwebtoken::Algorithm;
+use rustls_pki_types::CertificateDer;
+use serde_json::json;
+use sha2::{Digest, Sha256, Sha384, Sha512};
+use std::collections::HashSet;
+use webpki::{EndEntityCert, KeyUsage};
+
+/// The CBOR tag used for embedded CBOR data items (`#6.24(bstr .cbor ...)`).
+const ENCODED_CBOR_DATA_ITEM: u64 = 24;
+
+/// The COSE header parameters for the signature algorithm and the X.509 certificate chain.
+/// See: https://www.iana.org/assignments/cose/cose.xhtml#header-parameters
+const ALG: i64 = 1;
+const X5CHAIN: i64 = 33;
+
+/// The extended key usage of mDL document signer certificates (`1.0.18013.5.1.2`), DER-encoded.
+/// See: ISO/IEC 18013-5, Annex B.1.4
+const MDL_DOCUMENT_SIGNER: &[u8] = &[0x28, 0x81, 0x8c, 0x5d, 0x05, 0x01, 0x02];
+
+/// A single `IssuerSignedItem`. The original `IssuerSignedItemBytes` are kept, since the digests in the Mobile Security
+/// Object are calculated over these exact bytes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IssuerSignedItem {
+    pub issuer_signed_item_bytes: CborValue,
+    pub digest_id: i128,
+    pub element_identifier: String,
+    pub element_value: CborValue,
+}
+
+impl IssuerSignedItem {
+    fn parse(issuer_signed_item_bytes: &CborValue) -> Result<Self, AppError> {
+        let issuer_signed_item = decode_embedded(issuer_signed_item_bytes)?;
+
+        Ok(Self {
+            issuer_signed_item_bytes: issuer_signed_item_bytes.clone(),
+            digest_id: map_get(&issuer_signed_item, "digestID")
+                .and_then(CborValue::as_integer)
+                .map(i128::from)
+                .ok_or(InvalidMdocError("missing `digestID`"))?,
+            element_identifier: map_get(&issuer_signed_item, "elementIdentifier")
+                .and_then(CborValue::as_text)
+                .map(ToString::to_string)
+                .ok_or(InvalidMdocError("missing `elementIdentifier`"))?,
+            element_value: map_get(&issuer_signed_item, "elementValue")
+                .cloned()
+                .ok_or(InvalidMdocError("missing `elementValue`"))?,
+        })
+    }
+}
+
+/// An ISO/IEC 18013-5 mdoc (`mso_mdoc`) as issued through OID4VCI: the base64url-encoded CBOR `IssuerSigned` structure.
+/// See: https://openid.net/specs/openid-4-verifiable-credential-issuance-1_0.html#name-iso-mdl
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mdoc {
+    pub doc_type: String,
+    pub name_spaces: Vec<(String, Vec<IssuerSignedItem>)>,
+    pub issuer_auth: CborValue,
+    pub mobile_security_object: CborValue,
+}
+
+impl Mdoc {
+    /// Returns `true` if the credential is a base64url-encoded CBOR `IssuerSigned` structure.
+    pub fn is_mdoc(credential: &serde_json::Value) -> bool {
+        credential
+            .as_str()
+            .filter(|credential| !credential.contains('.'))
+            .and_then(|credential| URL_SAFE_NO_PAD.decode(credential).ok())
+            .and_then(|bytes| from_cbor(&bytes).ok())
+            .is_some_and(|issuer_signed| map_get(&issuer_signed, "issuerAuth").is_some())
+    }
+
+    pub fn parse(credential: &serde_json::Value) -> Result<Self, AppError> {
+        let issuer_signed = credential
+            .as_str()
+            .and_then(|credential| URL_SAFE_NO_PAD.decode(credential).ok())
+            .ok_or(InvalidCredentialFormatError)
+            .and_then(|bytes| from_cbor(&bytes))?;
+
+        let issuer_auth = map_get(&issuer_signed, "issuerAuth")
+            .cloned()
+            .ok_or(InvalidMdocError("missing `issuerAuth`"))?;
+
+        // The payload of the `issuerAuth` COSE_Sign1 structure contains the `MobileSecurityObjectBytes`.
+        let mobile_security_object = issuer_auth
+            .as_array()
+            .and_then(|cose_sign1| cose_sign1.get(2))
+            .and_then(CborValue::as_bytes)
+            .ok_or(InvalidMdocError("missing `issuerAuth` payload"))
+            .and_then(|payload| from_cbor(payload))
+            .and_then(|mobile_security_object_bytes| decode_embedded(&mobile_security_object_bytes))?;
+
+        let doc_type = map_get(&mobile_security_object, "docType")
+            .and_then(CborValue::as_text)
+            .map(ToString::to_string)
+            .ok_or(InvalidMdocError("missing `docType`"))?;
+
+        let name_spaces = map_get(&issuer_signed, "nameSpaces")
+            .and_then(CborValue::as_map)
+            .into_iter()
+            .flatten()
+            .map(|(name_space, issuer_signed_items)| {
+                Ok((
+                    name_space
+                        .as_text()
+                        .map(ToString::to_string)
+                        .ok_or(InvalidMdocError("invalid namespace"))?,
+                    issuer_signed_items
+                        .as_array()
+                        .into_iter()
+                        .flatten()
+                        .map(IssuerSignedItem::parse)
+                        .collect::<Result<Vec<_>, _>>()?,
+                ))
+            })
+            .collect::<Result<Vec<_>, AppError>>()?;
+
+        Ok(Self {
+            doc_type,
+            name_spaces,
+            issuer_auth,
+            mobile_security_object,
+        })
+    }
+
+    /// Checks that the digest of every `IssuerSignedItem` matches the corresponding `valueDigests` entry of the Mobile
+    /// Security Object, so the disclosed elements cannot have been altered after issuance.
+    pub fn validate_digests(&self) -> Result<(), AppError> {
+        let digest_algorithm = map_get(&self.mobile_security_object, "digestAlgorithm")
+            .and_then(CborValue::as_text)
+            .ok_or(InvalidMdocError("missing `digestAlgorithm`"))?;
+        let value_digests =
+            map_get(&self.mobile_security_object, "valueDigests").ok_or(InvalidMdocError("missing `valueDigests`"))?;
+
+        for (name_space, issuer_signed_items) in &self.name_spaces {
+            for issuer_signed_item in issuer_signed_items {
+                let digest = to_cbor(&issuer_signed_item.issuer_signed_item_bytes);
+                let digest = match digest_algorithm {
+                    "SHA-256" => Sha256::digest(digest).to_vec(),
+                    "SHA-384" => Sha384::digest(digest).to_vec(),
+                    "SHA-512" => Sha512::digest(digest).to_vec(),
+                    _ => return Err(InvalidMdocError("unsupported `digestAlgorithm`")),
+                };
+
+                let expected_digest = map_get(value_digests, name_space)
+                    .and_then(CborValue::as_map)
+                    .and_then(|digests| {
+                        digests.iter().find_map(|(digest_id, digest)| {
+                            (digest_id.as_integer().map(i128::from) == Some(issuer_signed_item.digest_id))
+                                .then_some(digest)
+                        })
+                    })
+                    .and_then(CborValue::as_bytes);
+
+                if expected_digest != Some(&digest) {
+                    return Err(MdocDigestMismatchError(format!(
+                        "{name_space}.{}",
+                        issuer_signed_item.element_identifier
+                    )));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the elements per namespace in a JSON representation that can be displayed by the frontend.
+    pub fn display_data(&self) -> serde_json::Value {
+        let name_spaces: serde_json::Map<String, serde_json::Value> = self
+            .name_spaces
+            .iter()
+            .map(|(name_space, issuer_signed_items)| {
+                let elements: serde_json::Map<String, serde_json::Value> = issuer_signed_items
+                    .iter()
+                    .map(|issuer_signed_item| {
+                        (
+                            issuer_signed_item.element_identifier.clone(),
+                            cbor_to_json(&issuer_signed_item.element_value),
+                        )
+                    })
+                    .collect();
+
+                (name_space.clone(), json!(elements))
+            })
+            .collect();
+
+        json!({
+            "docType": self.doc_type,
+            "nameSpaces": name_spaces,
+        })
+    }
+
+    /// Returns the date at which the Mobile Security Object was signed.
+    pub fn date_signed(&self) -> Option<String> {
+        self.validity_info("signed")
+    }
+
+    /// Returns a date of the `validityInfo` of the Mobile Security Object, i.e. `signed`, `validFrom` or `validUntil`.
+    pub fn validity_info(&self, key: &str) -> Option<String> {
+        map_get(&self.mobile_security_object, "validityInfo")
+            .and_then(|validity_info| map_get(validity_info, key))
+            .map(cbor_to_json)
+            .and_then(|signed| signed.as_str().map(ToString::to_string))
+    }
+
+    /// Returns a copy of the mdoc which only contains the requested elements. An element is requested by the path of
+    /// its namespace and element identifier, e.g. `$['org.iso.18013.5.1']['family_name']`.
+    /// See: ISO/IEC 18013-7, Annex B.3.1.1
+    pub fn select_elements(&self, requested_claim_paths: &HashSet<Vec<String>>) -> Self {
+        let name_spaces = self
+            .name_spaces
+            .iter()
+            .map(|(name_space, issuer_signed_items)| {
+                (
+                    name_space.clone(),
+                    issuer_signed_items
+                        .iter()
+                        .filter(|issuer_signed_item| {
+                            requested_claim_paths
+                                .contains(&vec![name_space.clone(), issuer_signed_item.element_identifier.clone()])
+                        })
+                        .cloned()
+                        .collect::<Vec<_>>(),
+                )
+            })
+            .filter(|(_, issuer_signed_items)| !issuer_signed_items.is_empty())
+            .collect();
+
+        Self {
+            name_spaces,
+            ..self.clone()
+        }
+    }
+
+    /// Verifies the issuer signature (`issuerAuth`): a COSE_Sign1 signature over the Mobile Security Object made with
+    /// the key of the document signer certificate in its `x5chain` header. The outcome is only successful if that
+    /// certificate chains up to an IACA certificate in the trust store.
+    /// See: ISO/IEC 18013-5, section 9.1.2.4 and https://datatracker.ietf.org/doc/html/rfc9052#section-4.4
+    pub fn verify_issuer_signature(&self) -> ValidationResult {
+        let Some([protected_header, unprotected_header, payload, signature]) = self
+            .issuer_auth
+            .as_array()
+            .and_then(|cose_sign1| <&[CborValue; 4]>::try_from(cose_sign1.as_slice()).ok())
+        else {
+            return failure("The `issuerAuth` is not a COSE_Sign1 structure".to_string());
+        };
+        let (Some(protected_header_bytes), Some(payload), Some(signature)) =
+            (protected_header.as_bytes(), payload.as_bytes(), signature.as_bytes())
+        else {
+            return failure("The `issuerAuth` is not a COSE_Sign1 structure".to_string());
+        };
+        // An empty protected header stands for an empty map.
+        let protected_header = match protected_header_bytes.is_empty() {
+            true => CborValue::Map(vec![]),
+            false => match from_cbor(protected_header_bytes) {
+                Ok(protected_header) => protected_header,
+                Err(e) => return failure(e.to_string()),
+            },
+        };
+
+        // The `x5chain` contains a single certificate or an array of certificates, starting with the document signer
+        // certificate.
+        let certificate_chain: Vec<CertificateDer<'static>> =
+            match label_get(&protected_header, X5CHAIN).or(label_get(unprotected_header, X5CHAIN)) {
+                Some(CborValue::Bytes(certificate)) => vec![CertificateDer::from(certificate.clone())],
+                Some(CborValue::Array(certificates)) => certificates
+                    .iter()
+                    .filter_map(CborValue::as_bytes)
+                    .map(|certificate| CertificateDer::from(certificate.clone()))
+                    .collect(),
+                _ => return failure("The `issuerAuth` does not contain a certificate chain (`x5chain`)".to_string()),
+            };
+        let Some(document_signer_certificate) = certificate_chain
+            .first()
+            .and_then(|certificate| EndEntityCert::try_from(certificate).ok())
+        else {
+            return failure("The `x5chain` does not contain a valid certificate".to_string());
+        };
+
+        let (signature_algorithm, signature) = match label_get(&protected_header, ALG)
+            .and_then(CborValue::as_integer)
+            .map(i128::from)
+        {
+            Some(-7) => (webpki::ring::ECDSA_P256_SHA256, asn1_ecdsa_signature(signature)),
+            Some(-35) => (webpki::ring::ECDSA_P384_SHA384, asn1_ecdsa_signature(signature)),
+            Some(-8) => (webpki::ring::ED25519, signature.clone()),
+            algorithm => {
+                return ValidationResult {
+                    status: ValidationStatus::Unknown,
+                    message: Some(format!("Unsupported signature algorithm: {algorithm:?}")),
+                }
+            }
+        };
+
+        let sig_structure = to_cbor(&CborValue::Array(vec![
+            CborValue::Text("Signature1".to_string()),
+            CborValue::Bytes(protected_header_bytes.clone()),
+            CborValue::Bytes(vec![]),
+            CborValue::Bytes(payload.clone()),
+        ]));
+
+        if let Err(e) = document_signer_certificate.verify_signature(signature_algorithm, &sig_structure, &signature) {
+            return failure(format!("The issuer signature of the mdoc is invalid: {e:?}"));
+        }
+
+        // A valid signature of a document signer that is not trusted does not mean that the mdoc has been forged.
+        match validate_certificate_chain(&certificate_chain, KeyUsage::required(MDL_DOCUMENT_SIGNER)) {
+            Ok(_) => ValidationResult {
+                status: ValidationStatus::Success,
+                message: None,
+            },
+            Err(result) => ValidationResult {
+                status: ValidationStatus::Unknown,
+                message: result.message,
+            },
+        }
+    }
+
+    /// Builds the `Document` for a `DeviceResponse`, including the device signature over the `DeviceAuthentication`
+    /// structure. No device-signed elements are returned.
+    /// See: ISO/IEC 18013-5, section 9.1.3
+    pub async fn document(
+        &self,
+        session_transcript: &CborValue,
+        subject: &Subject,
+        algorithm: Algorithm,
+    ) -> Result<CborValue, AppError> {
+        let device_name_spaces_bytes = embed(&CborValue::Map(vec![]));
+
+        let device_authentication_bytes = embed(&CborValue::Array(vec![
+            CborValue::Text("DeviceAuthentication".to_string()),
+            session_transcript.clone(),
+            CborValue::Text(self.doc_type.clone()),
+            device_name_spaces_bytes.clone(),
+        ]));
+
+        // COSE_Sign1 with a detached payload.
+        // See: https://datatracker.ietf.org/doc/html/rfc9052#section-4.4
+        let protected_header = to_cbor(&CborValue::Map(vec![(
+            CborValue::Integer(1.into()),
+            CborValue::Integer(cose_algorithm(algorithm)?.into()),
+        )]));
+        let sig_structure = to_cbor(&CborValue::Array(vec![
+            CborValue::Text("Signature1".to_string()),
+            CborValue::Bytes(protected_header.clone()),
+            CborValue::Bytes(vec![]),
+            CborValue::Bytes(to_cbor(&device_authentication_bytes)),
+        ]));
+
+        let signature = subject
+            .sign_bytes(&sig_structure, algorithm)
+            .await
+            .map_err(DeviceSignatureError)?;
+
+        let device_signature = CborValue::Array(vec![
+            CborValue::Bytes(protected_header),
+            CborValue::Map(vec![]),
+            CborValue::Null,
+            CborValue::Bytes(signature),
+        ]);
+
+        let issuer_name_spaces = self
+            .name_spaces
+            .iter()
+            .map(|(name_space, issuer_signed_items)| {
+                (
+                    CborValue::Text(name_space.clone()),
+                    CborValue::Array(
+                        issuer_signed_items
+                            .iter()
+                            .map(|issuer_signed_item| issuer_signed_item.issuer_signed_item_bytes.clone())
+                            .collect(),
+                    ),
+                )
+            })
+            .collect();
+
+        Ok(cbor_map([
+            ("docType", CborValue::Text(self.doc_type.clone())),
+            (
+                "issuerSigned",
+                cbor_map([
+                    ("nameSpaces", CborValue::Map(issuer_name_spaces)),
+                    ("issuerAuth", self.issuer_auth.clone()),
+                ]),
+            ),
+            (
+                "deviceSigned",
+                cbor_map([
+                    ("nameSpaces", device_name_spaces_bytes),
+                    ("deviceAuth", cbor_map([("deviceSignature", device_signature)])),
+                ]),
+            ),
+        ]))
+    }
+}
+
+/// Returns the `SessionTranscript` used when presenting mdocs through OID4VP.
+/// See: ISO/IEC 18013-7, Annex B.4.4
+pub fn oid4vp_session_transcript(
+    client_id: &str,
+    response_uri: &str,
+    nonce: &str,
+    mdoc_generated_nonce: &str,
+) -> CborValue {
+    let hash = |value: &str| {
+        CborValue::Bytes(
+            Sha256::digest(to_cbor(&CborValue::Array(vec![
+                CborValue::Text(value.to_string()),
+                CborValue::Text(mdoc_generated_nonce.to_string()),
+            ])))
+            .to_vec(),
+        )
+    };
+
+    CborValue::Array(vec![
+        CborValue::Null,
+        CborValue::Null,
+        CborValue::Array(vec![
+            hash(client_id),
+            hash(response_uri),
+            CborValue::Text(nonce.to_string()),
+        ]),
+    ])
+}
+
+/// Returns the base64url-encoded `DeviceResponse` containing the given documents, which is used as the `vp_token`.
+pub fn device_response(documents: Vec<CborValue>) -> String {
+    URL_SAFE_NO_PAD.encode(to_cbor(&cbor_map([
+        ("version", CborValue::Text("1.0".to_string())),
+        ("documents", CborValue::Array(documents)),
+        ("status", CborValue::Integer(0.into())),
+    ])))
+}
+
+// Helpers
+
+fn cose_algorithm(algorithm: Algorithm) -> Result<i64, AppError> {
+    match algorithm {
+        Algorithm::ES256 => Ok(-7),
+        Algorithm::ES384 => Ok(-35),
+        Algorithm::EdDSA => Ok(-8),
+        _ => Err(InvalidMdocError("unsupported device signature algorithm")),
+    }
+}
+
+fn to_cbor(value: &CborValue) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    ciborium::into_writer(value, &mut bytes).expect("serializing a CBOR value into a vector cannot fail");
+    bytes
+}
+
+fn from_cbor(bytes: &[u8]) -> Result<CborValue, AppError> {
+    ciborium::from_reader(bytes).map_err(|_| InvalidMdocError("invalid CBOR"))
+}
+
+// Wraps the encoded value in an embedded CBOR data item (`#6.24(bstr)`).
+fn embed(value: &CborValue) -> CborValue {
+    CborValue::Tag(ENCODED_CBOR_DATA_ITEM, Box::new(CborValue::Bytes(to_cbor(value))))
+}
+
+fn decode_embedded(value: &CborValue) -> Result<CborValue, AppError> {
+    match value {
+        CborValue::Tag(ENCODED_CBOR_DATA_ITEM, embedded) => embedded
+            .as_bytes()
+            .ok_or(InvalidMdocError("invalid embedded CBOR data item"))
+            .and_then(|bytes| from_cbor(bytes)),
+        _ => Err(InvalidMdocError("expected an embedded CBOR data item")),
+    }
+}
+
+fn map_get<'a>(map: &'a CborValue, key: &str) -> Option<&'a CborValue> {
+    map.as_map()?
+        .iter()
+        .find_map(|(map_key, value)| (map_key.as_text() == Some(key)).then_some(value))
+}
+
+// Returns the value of a COSE header parameter, which has an integer label.
+fn label_get(map: &CborValue, label: i64) -> Option<&CborValue> {
+    map.as_map()?
+        .iter()
+        .find_map(|(map_key, value)| (map_key.as_integer() == Some(label.into())).then_some(value))
+}
+
+fn failure(message: String) -> ValidationResult {
+    ValidationResult {
+        status: ValidationStatus::Failure,
+        message: Some(message),
+    }
+}
+
+fn cbor_map<const N: usize>(entries: [(&str, CborValue); N]) -> CborValue {
+    CborValue::Map(
+        entries
+            .into_iter()
+            .map(|(key, value)| (CborValue::Text(key.to_string()), value))
+            .collect(),
+    )
+}
+
+fn cbor_to_json(value: &CborValue) -> serde_json::Value {
+    match value {
+        CborValue::Integer(integer) => json!(i128::from(*integer) as i64),
+        // Binary data, such as a portrait, is displayed as a base64url-encoded string.
+        CborValue::Bytes(bytes) => json!(URL_SAFE_NO_PAD.encode(bytes)),
+        CborValue::Float(float) => json!(float),
+        CborValue::Text(text) => json!(text),
+        CborValue::Bool(bool) => json!(bool),
+        // Tags such as `tdate` (0) and `full-date` (1004) are displayed as their tagged value.
+        CborValue::Tag(_, tagged) => cbor_to_json(tagged),
+        CborValue::Array(array) => serde_json::Value::Array(array.iter().map(cbor_to_json).collect()),
+        CborValue::Map(map) => serde_json::Value::Object(
+            map.iter()
+                .map(|(key, value)| {
+                    let key = match key {
+                        CborValue::Text(text) => text.clone(),
+                        key => cbor_to_json(key).to_string(),
+                    };
+                    (key, cbor_to_json(value))
+                })
+                .collect(),
+        ),
+        _ => serde_json::Value::Null,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{persistence::TRUST_STORE_DIR, state::core_utils::helpers::requested_claim_paths};
+
+    const NAME_SPACE: &str = "org.iso.18013.5.1";
+
+    fn issuer_signed_item(digest_id: i64, element_identifier: &str, element_value: CborValue) -> CborValue {
+        embed(&cbor_map([
+            ("digestID", CborValue::Integer(digest_id.into())),
+            ("random", CborValue::Bytes(vec![digest_id as u8; 16])),
+            ("elementIdentifier", CborValue::Text(element_identifier.to_string())),
+            ("elementValue", element_value),
+        ]))
+    }
+
+    // Returns a base64url-encoded `IssuerSigned` structure with an unsigned `issuerAuth`.
+    fn mdoc_credential(tamper: bool) -> serde_json::Value {
+        let family_name = issuer_signed_item(0, "family_name", CborValue::Text("Crabman".to_string()));
+        let birth_date = issuer_signed_item(
+            1,
+            "birth_date",
+            CborValue::Tag(1004, Box::new(CborValue::Text("1985-05-21".to_string()))),
+        );
+
+        let mobile_security_object = cbor_map([
+            ("version", CborValue::Text("1.0".to_string())),
+            ("digestAlgorithm", CborValue::Text("SHA-256".to_string())),
+            (
+                "valueDigests",
+                cbor_map([(
+                    NAME_SPACE,
+                    CborValue::Map(
+                        [&family_name, &birth_date]
+                            .into_iter()
+                            .enumerate()
+                            .map(|(digest_id, issuer_signed_item)| {
+                                (
+                                    CborValue::Integer((digest_id as i64).into()),
+                                    CborValue::Bytes(Sha256::digest(to_cbor(issuer_signed_item)).to_vec()),
+                                )
+                            })
+                            .collect(),
+                    ),
+                )]),
+            ),
+            ("docType", CborValue::Text("org.iso.18013.5.1.mDL".to_string())),
+            (
+                "validityInfo",
+                cbor_map([(
+                    "signed",
+                    CborValue::Tag(0, Box::new(CborValue::Text("2024-01-01T00:00:00Z".to_string()))),
+                )]),
+            ),
+        ]);
+
+        let family_name = match tamper {
+            true => issuer_signed_item(0, "family_name", CborValue::Text("Ferris".to_string())),
+            false => family_name,
+        };
+
+        let issuer_signed = cbor_map([
+            (
+                "nameSpaces",
+                cbor_map([(NAME_SPACE, CborValue::Array(vec![family_name, birth_date]))]),
+            ),
+            (
+                "issuerAuth",
+                CborValue::Array(vec![
+                    CborValue::Bytes(vec![]),
+                    CborValue::Map(vec![]),
+                    CborValue::Bytes(to_cbor(&embed(&mobile_security_object))),
+                    CborValue::Bytes(vec![]),
+                ]),
+            ),
+        ]);
+
+        json!(URL_SAFE_NO_PAD.encode(to_cbor(&issuer_signed)))
+    }
+
+    #[test]
+    fn mdoc_is_parsed_and_displayed() {
+        let credential = mdoc_credential(false);
+        assert!(Mdoc::is_mdoc(&credential));
+        assert!(!Mdoc::is_mdoc(&json!("eyJhbGciOiJFZERTQSJ9.e30.c2ln")));
+
+        let mdoc = Mdoc::parse(&credential).unwrap();
+        assert!(mdoc.validate_digests().is_ok());
+        assert_eq!(mdoc.date_signed(), Some("2024-01-01T00:00:00Z".to_string()));
+        assert_eq!(
+            mdoc.display_data(),
+            json!({
+                "docType": "org.iso.18013.5.1.mDL",
+                "nameSpaces": {
+                    "org.iso.18013.5.1": {
+                        "family_name": "Crabman",
+                        "birth_date": "1985-05-21",
+                    }
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn mdoc_with_altered_element_is_rejected() {
+        let mdoc = Mdoc::parse(&mdoc_credential(true)).unwrap();
+
+        assert!(matches!(
+            mdoc.validate_digests(),
+            Err(MdocDigestMismatchError(element)) if element == "org.iso.18013.5.1.family_name"
+        ));
+    }
+
+    // Signs the Mobile Security Object of the mdoc with the key of the test document signer certificate.
+    fn signed_issuer_auth(mdoc: &Mdoc) -> CborValue {
+        let payload = mdoc.issuer_auth.as_array().unwrap()[2].clone();
+        let protected_header = to_cbor(&CborValue::Map(vec![(
+            CborValue::Integer(ALG.into()),
+            CborValue::Integer((-7).into()),
+        )]));
+
+        let sig_structure = to_cbor(&CborValue::Array(vec![
+            CborValue::Text("Signature1".to_string()),
+            CborValue::Bytes(protected_header.clone()),
+            CborValue::Bytes(vec![]),
+            payload.clone(),
+        ]));
+        let key_pair = ring::signature::EcdsaKeyPair::from_pkcs8(
+            &ring::signature::ECDSA_P256_SHA256_FIXED_SIGNING,
+            include_bytes!("../../../resources/test/mdoc/document-signer-key.der"),
+            &ring::rand::SystemRandom::new(),
+        )
+        .unwrap();
+        let signature = key_pair.sign(&ring::rand::SystemRandom::new(), &sig_structure).unwrap();
+
+        CborValue::Array(vec![
+            CborValue::Bytes(protected_header),
+            CborValue::Map(vec![(
+                CborValue::Integer(X5CHAIN.into()),
+                CborValue::Bytes(include_bytes!("../../../resources/test/mdoc/document-signer.der").to_vec()),
+            )]),
+            payload,
+            CborValue::Bytes(signature.as_ref().to_vec()),
+        ])
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn issuer_signature_is_verified_with_the_document_signer_certificate() {
+        *TRUST_STORE_DIR.lock().unwrap() = concat!(env!("CARGO_MANIFEST_DIR"), "/resources/test/trust_store").into();
+
+        let mdoc = Mdoc::parse(&mdoc_credential(false)).unwrap();
+        let signed_mdoc = Mdoc {
+            issuer_auth: signed_issuer_auth(&mdoc),
+            ..mdoc.clone()
+        };
+        assert_eq!(signed_mdoc.verify_issuer_signature().status, ValidationStatus::Success);
+
+        // A signature over another Mobile Security Object is invalid.
+        let mut issuer_auth = signed_issuer_auth(&mdoc).into_array().unwrap();
+        issuer_auth[2] = CborValue::Bytes(to_cbor(&embed(&cbor_map([(
+            "docType",
+            CborValue::Text("org.iso.18013.5.1.mDL".to_string()),
+        )]))));
+        let altered_mdoc = Mdoc {
+            issuer_auth: CborValue::Array(issuer_auth),
+            ..mdoc.clone()
+        };
+        assert_eq!(altered_mdoc.verify_issuer_signature().status, ValidationStatus::Failure);
+
+        // An mdoc without a certificate chain cannot be verified.
+        assert_eq!(mdoc.verify_issuer_signature().status, ValidationStatus::Failure);
+
+        // A valid signature of a document signer that is not trusted is inconclusive.
+        *TRUST_STORE_DIR.lock().unwrap() = concat!(env!("CARGO_MANIFEST_DIR"), "/resources/test/mdoc").into();
+        assert_eq!(signed_mdoc.verify_issuer_signature().status, ValidationStatus::Unknown);
+    }
+
+    #[test]
+    fn only_requested_elements_are_selected() {
+        let mdoc = Mdoc::parse(&mdoc_credential(false)).unwrap();
+
+        let presentation_definition = json!({
+            "id": "mDL",
+            "input_descriptors": [{
+                "id": "org.iso.18013.5.1.mDL",
+                "format": { "mso_mdoc": { "alg": ["ES256"] } },
+                "constraints": {
+                    "limit_disclosure": "required",
+                    "fields": [
+                        { "path": ["$['org.iso.18013.5.1']['family_name']"], "intent_to_retain": false },
+                    ]
+                }
+            }]
+        });
+
+        let selected = mdoc.select_elements(&requested_claim_paths(&presentation_definition));
+
+        assert_eq!(
+            selected.display_data()["nameSpaces"],
+            json!({ "org.iso.18013.5.1": { "family_name": "Crabman" } })
+        );
+        // The selected elements still match the digests of the Mobile Security Object.
+        assert!(selected.validate_digests().is_ok());
+    }
+}
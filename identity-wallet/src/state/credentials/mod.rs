@@ -1,14 +1,33 @@
 pub mod actions;
+pub mod authorization_code_flow;
+pub mod data_integrity;
+pub mod deferred_credential;
+pub mod mdoc;
+pub mod presentation_exchange;
 pub mod reducers;
+pub mod refresh;
+pub mod sd_jwt;
+pub mod status;
+pub mod verification;
 
 use super::{core_utils::helpers::get_unverified_jwt_claims, FeatTrait};
-use crate::{error::AppError, state::core_utils::DateUtils};
+use crate::{
+    error::AppError,
+    state::{core_utils::DateUtils, did::holder_keys::HolderKeys},
+};
 
+use chrono::{DateTime, Duration, Utc};
+use data_integrity::{is_ldp_vc, without};
 use derivative::Derivative;
+use mdoc::Mdoc;
+use refresh::CredentialIssuance;
+use sd_jwt::SdJwt;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
+use status::CredentialStatus;
 use ts_rs::TS;
 use uuid::Uuid;
+use verification::CredentialVerification;
 
 /// A credential displayable by the frontend.
 #[derive(Debug, Serialize, Deserialize, Clone, Eq, Derivative, TS, Default)]
@@ -24,6 +43,21 @@ pub struct DisplayCredential {
     #[ts(optional)]
     pub connection_id: Option<String>,
     pub display_name: String,
+    #[serde(default)]
+    pub format: CredentialFormat,
+}
+
+impl DisplayCredential {
+    /// Returns the claims against which the input descriptors of a presentation definition are evaluated. The claims of
+    /// a `jwt_vc_json` credential are nested in the `vc` claim, whereas the claims of an SD-JWT VC and a JSON-LD
+    /// credential are top-level and the elements of an mdoc are grouped per namespace.
+    pub fn presentation_claims(&self) -> serde_json::Value {
+        match self.format {
+            CredentialFormat::JwtVcJson => json!({ "vc": self.data }),
+            CredentialFormat::VcSdJwt | CredentialFormat::LdpVc => self.data.clone(),
+            CredentialFormat::MsoMdoc => self.data["nameSpaces"].clone(),
+        }
+    }
 }
 
 #[typetag::serde(name = "display_credential")]
@@ -40,12 +74,145 @@ pub struct CredentialMetadata {
     #[derivative(PartialEq = "ignore")]
     pub date_added: String,
     pub date_issued: String,
+    #[serde(default)]
+    #[ts(optional)]
+    pub date_expires: Option<String>,
+    /// Whether the credential has expired. Computed when the storage is unlocked.
+    #[serde(default)]
+    pub is_expired: bool,
+    /// Whether the credential expires within the next `EXPIRES_SOON_DAYS` days. Computed when the storage is unlocked.
+    #[serde(default)]
+    pub expires_soon: bool,
+    /// The result of the verification of the credential when it was received.
+    #[serde(default)]
+    pub verification: CredentialVerification,
+    /// The revocation status of the credential, as published by its issuer.
+    #[serde(default)]
+    pub status: CredentialStatus,
+    /// The index of the holder keys to which the credential is bound, which are the keys of the issuer's connection.
+    #[serde(default)]
+    #[derivative(PartialEq = "ignore")]
+    pub key_index: u32,
+    /// Whether the holder keys to which the credential is bound have been retired by a rotation, in which case the
+    /// credential should be issued again. Computed when the storage is unlocked and when the keys are rotated.
+    #[serde(default)]
+    pub key_retired: bool,
+}
+
+/// The number of days before its expiry date from which a credential is considered to expire soon.
+pub const EXPIRES_SOON_DAYS: i64 = 30;
+
+impl CredentialMetadata {
+    /// Returns the expiry date of the credential, if it has one.
+    pub fn expiry_date(&self) -> Option<DateTime<Utc>> {
+        self.date_expires
+            .as_deref()
+            .and_then(|date_expires| DateTime::parse_from_rfc3339(date_expires).ok())
+            .map(|date_expires| date_expires.with_timezone(&Utc))
+    }
+
+    /// Returns `true` if the credential has expired at the given moment.
+    pub fn is_expired_at(&self, moment: DateTime<Utc>) -> bool {
+        self.expiry_date().is_some_and(|date_expires| date_expires <= moment)
+    }
+
+    /// Recomputes `is_expired` and `expires_soon` relative to the current time.
+    pub fn update_expiry_state(&mut self) {
+        let now = Utc::now();
+        self.is_expired = self.is_expired_at(now);
+        self.expires_soon = !self.is_expired && self.is_expired_at(now + Duration::days(EXPIRES_SOON_DAYS));
+    }
+
+    /// Recomputes `key_retired` from the keys that have been replaced or retired by a rotation.
+    pub fn update_key_state(&mut self, holder_keys: &HolderKeys) {
+        self.key_retired = holder_keys.is_retired(self.key_index);
+    }
+}
+
+/// The format in which a credential has been issued.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, TS, Default)]
+#[ts(export, export_to = "bindings/credentials/CredentialFormat.ts")]
+pub enum CredentialFormat {
+    #[default]
+    #[serde(rename = "jwt_vc_json")]
+    JwtVcJson,
+    #[serde(rename = "vc+sd-jwt")]
+    VcSdJwt,
+    #[serde(rename = "mso_mdoc")]
+    MsoMdoc,
+    #[serde(rename = "ldp_vc")]
+    LdpVc,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Eq, PartialEq)]
 pub struct VerifiableCredentialRecord {
     pub verifiable_credential: serde_json::Value,
     pub display_credential: DisplayCredential,
+    /// Present when the credential has been issued through OID4VCI, so it can be refreshed later on.
+    #[serde(default)]
+    pub issuance: Option<CredentialIssuance>,
+}
+
+impl VerifiableCredentialRecord {
+    /// Fills in the `date_expires` of a credential that was stored before expiry dates were tracked, based on the
+    /// credential itself. Returns `true` if an expiry date has been filled in.
+    pub fn backfill_expiry_date(&mut self) -> bool {
+        if self.display_credential.metadata.date_expires.is_some() {
+            return false;
+        }
+
+        self.display_credential.metadata.date_expires =
+            VerifiableCredentialRecord::try_from(self.verifiable_credential.clone())
+                .ok()
+                .and_then(|verifiable_credential_record| {
+                    verifiable_credential_record.display_credential.metadata.date_expires
+                });
+
+        self.display_credential.metadata.date_expires.is_some()
+    }
+
+    /// Returns the identifier of the issuer of the credential: the `iss` claim of a JWT or SD-JWT, or the `issuer` of a
+    /// JSON-LD credential. An mdoc identifies its issuer by its document signer certificate instead.
+    pub fn issuer(&self) -> Option<String> {
+        let data = &self.display_credential.data;
+        match self.display_credential.format {
+            CredentialFormat::JwtVcJson => get_unverified_jwt_claims(&self.verifiable_credential)
+                .ok()
+                .and_then(|claims| claims["iss"].as_str().map(ToString::to_string))
+                .or(data["issuer"]
+                    .as_str()
+                    .or(data["issuer"]["id"].as_str())
+                    .map(ToString::to_string)),
+            CredentialFormat::VcSdJwt => data["iss"].as_str().map(ToString::to_string),
+            CredentialFormat::LdpVc => data["issuer"]
+                .as_str()
+                .or(data["issuer"]["id"].as_str())
+                .map(ToString::to_string),
+            CredentialFormat::MsoMdoc => None,
+        }
+    }
+
+    /// Returns the sorted types of the credential: the `type` of a W3C credential, the `vct` of an SD-JWT VC or the
+    /// `docType` of an mdoc.
+    pub fn credential_types(&self) -> Vec<String> {
+        let data = &self.display_credential.data;
+        let types = match self.display_credential.format {
+            CredentialFormat::JwtVcJson | CredentialFormat::LdpVc => &data["type"],
+            CredentialFormat::VcSdJwt => &data["vct"],
+            CredentialFormat::MsoMdoc => &data["docType"],
+        };
+
+        let mut types: Vec<String> = match types {
+            serde_json::Value::Array(types) => types
+                .iter()
+                .filter_map(serde_json::Value::as_str)
+                .map(ToString::to_string)
+                .collect(),
+            types => types.as_str().map(ToString::to_string).into_iter().collect(),
+        };
+        types.sort();
+        types
+    }
 }
 
 // TODO: remove this function and find a cleaner implementation for this functionality.
@@ -53,8 +220,35 @@ impl TryFrom<serde_json::Value> for VerifiableCredentialRecord {
     type Error = AppError;
 
     fn try_from(verifiable_credential: serde_json::Value) -> Result<Self, AppError> {
+        if SdJwt::is_sd_jwt(&verifiable_credential) {
+            return Ok(Self {
+                display_credential: sd_jwt_display_credential(&SdJwt::parse(&verifiable_credential)?)?,
+                verifiable_credential,
+                issuance: None,
+            });
+        }
+
+        if Mdoc::is_mdoc(&verifiable_credential) {
+            return Ok(Self {
+                display_credential: mdoc_display_credential(&Mdoc::parse(&verifiable_credential)?)?,
+                verifiable_credential,
+                issuance: None,
+            });
+        }
+
         let display_credential = {
-            let credential_display = get_unverified_jwt_claims(&verifiable_credential)?["vc"].clone();
+            // A JSON-LD credential is displayed without its proof.
+            let (credential_display, format, exp) = match is_ldp_vc(&verifiable_credential) {
+                true => (without(&verifiable_credential, "proof"), CredentialFormat::LdpVc, None),
+                false => {
+                    let claims = get_unverified_jwt_claims(&verifiable_credential)?;
+                    (
+                        claims["vc"].clone(),
+                        CredentialFormat::JwtVcJson,
+                        claims["exp"].as_i64(),
+                    )
+                }
+            };
 
             // Derive the hash from the credential display.
             let hash = {
@@ -86,6 +280,15 @@ impl TryFrom<serde_json::Value> for VerifiableCredentialRecord {
                 .map(ToString::to_string)
                 .unwrap_or_default();
 
+            // The `exp` claim of a JWT is the encoding of the `expirationDate` of the credential.
+            let expiry_date = credential_display["expirationDate"]
+                .as_str()
+                .or(credential_display["validUntil"].as_str())
+                .map(ToString::to_string)
+                .or(exp
+                    .and_then(|exp| DateTime::from_timestamp(exp, 0))
+                    .map(|exp| exp.to_rfc3339()));
+
             DisplayCredential {
                 id: Uuid::from_slice(&hash.as_bytes()[..16]).unwrap().to_string(),
                 data: credential_display,
@@ -93,7 +296,15 @@ impl TryFrom<serde_json::Value> for VerifiableCredentialRecord {
                     is_favorite: false,
                     date_added: DateUtils::new_date_string(),
                     date_issued: issuance_date.to_string(),
+                    date_expires: expiry_date,
+                    is_expired: false,
+                    expires_soon: false,
+                    verification: Default::default(),
+                    status: Default::default(),
+                    key_index: 0,
+                    key_retired: false,
                 },
+                format,
                 // The other fields will be filled in at a later stage.
                 ..Default::default()
             }
@@ -102,6 +313,151 @@ impl TryFrom<serde_json::Value> for VerifiableCredentialRecord {
         Ok(Self {
             verifiable_credential,
             display_credential,
+            issuance: None,
         })
     }
 }
+
+/// Builds the `DisplayCredential` from the disclosed claims of an SD-JWT VC.
+fn sd_jwt_display_credential(sd_jwt: &SdJwt) -> Result<DisplayCredential, AppError> {
+    let disclosed_claims = sd_jwt.disclosed_claims()?;
+
+    // Derive the hash from the credential type and the disclosed claims, leaving out the claims that differ per
+    // issuance.
+    let hash = {
+        let mut claims = disclosed_claims.clone();
+        if let Some(claims) = claims.as_object_mut() {
+            for claim_name in ["iat", "nbf", "exp", "cnf", "status"] {
+                claims.remove(claim_name);
+            }
+        }
+
+        sha256::digest(json!(claims).to_string())
+    };
+
+    let issuance_date = disclosed_claims["iat"]
+        .as_i64()
+        .and_then(|iat| DateTime::from_timestamp(iat, 0))
+        .map(|iat| iat.to_rfc3339())
+        .unwrap_or_default();
+
+    let expiry_date = disclosed_claims["exp"]
+        .as_i64()
+        .and_then(|exp| DateTime::from_timestamp(exp, 0))
+        .map(|exp| exp.to_rfc3339());
+
+    Ok(DisplayCredential {
+        id: Uuid::from_slice(&hash.as_bytes()[..16]).unwrap().to_string(),
+        data: disclosed_claims,
+        metadata: CredentialMetadata {
+            is_favorite: false,
+            date_added: DateUtils::new_date_string(),
+            date_issued: issuance_date,
+            date_expires: expiry_date,
+            is_expired: false,
+            expires_soon: false,
+            verification: Default::default(),
+            status: Default::default(),
+            key_index: 0,
+            key_retired: false,
+        },
+        format: CredentialFormat::VcSdJwt,
+        // The other fields will be filled in at a later stage.
+        ..Default::default()
+    })
+}
+
+/// Builds the `DisplayCredential` from the elements of an mdoc, after validating them against the digests in its Mobile
+/// Security Object.
+fn mdoc_display_credential(mdoc: &Mdoc) -> Result<DisplayCredential, AppError> {
+    mdoc.validate_digests()?;
+
+    let data = mdoc.display_data();
+
+    Ok(DisplayCredential {
+        id: Uuid::from_slice(&sha256::digest(data.to_string()).as_bytes()[..16])
+            .unwrap()
+            .to_string(),
+        data,
+        metadata: CredentialMetadata {
+            is_favorite: false,
+            date_added: DateUtils::new_date_string(),
+            date_issued: mdoc.date_signed().unwrap_or_default(),
+            date_expires: mdoc.validity_info("validUntil"),
+            is_expired: false,
+            expires_soon: false,
+            verification: Default::default(),
+            status: Default::default(),
+            key_index: 0,
+            key_retired: false,
+        },
+        format: CredentialFormat::MsoMdoc,
+        // The other fields will be filled in at a later stage.
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+
+    #[test]
+    fn expiry_date_is_backfilled_from_the_credential() {
+        let claims = json!({
+            "iss": "did:example:issuer",
+            "exp": 1767225600,
+            "vc": {
+                "type": ["VerifiableCredential", "PersonalInformation"],
+                "issuanceDate": "2024-01-01T00:00:00Z",
+                "credentialSubject": { "givenName": "Ferris" }
+            }
+        });
+        let jwt = format!(
+            "{}.{}.c2ln",
+            URL_SAFE_NO_PAD.encode(json!({ "alg": "EdDSA" }).to_string()),
+            URL_SAFE_NO_PAD.encode(claims.to_string())
+        );
+
+        let mut verifiable_credential_record = VerifiableCredentialRecord::try_from(json!(jwt)).unwrap();
+        verifiable_credential_record.display_credential.metadata.date_expires = None;
+
+        assert!(verifiable_credential_record.backfill_expiry_date());
+        assert_eq!(
+            verifiable_credential_record.display_credential.metadata.date_expires,
+            Some("2026-01-01T00:00:00+00:00".to_string())
+        );
+
+        // An expiry date that is already known is kept.
+        assert!(!verifiable_credential_record.backfill_expiry_date());
+    }
+
+    #[test]
+    fn issuer_and_types_are_read_from_the_credential() {
+        let jwt = format!(
+            "{}.{}.c2ln",
+            URL_SAFE_NO_PAD.encode(json!({ "alg": "EdDSA" }).to_string()),
+            URL_SAFE_NO_PAD.encode(
+                json!({
+                    "iss": "did:example:issuer",
+                    "vc": {
+                        "type": ["VerifiableCredential", "PersonalInformation"],
+                        "credentialSubject": { "givenName": "Ferris" }
+                    }
+                })
+                .to_string()
+            )
+        );
+        let verifiable_credential_record = VerifiableCredentialRecord::try_from(json!(jwt)).unwrap();
+
+        assert_eq!(
+            verifiable_credential_record.issuer(),
+            Some("did:example:issuer".to_string())
+        );
+        assert_eq!(
+            verifiable_credential_record.credential_types(),
+            vec!["PersonalInformation".to_string(), "VerifiableCredential".to_string()]
+        );
+    }
+}
@@ -0,0 +1,550 @@
+use crate::{
+    error::AppError::{self, *},
+    state::{
+        credentials::VerifiableCredentialRecord,
+        user_prompt::{InputDescriptorOptions, RequestedInputDescriptor},
+    },
+};
+
+use oid4vc::oid4vc_core::authorization_request::{AuthorizationRequest, Object};
+use oid4vc::oid4vp::{evaluate_input, oid4vp::OID4VP};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+/// A rule of the `submission_requirements` of a presentation definition, which determines which of the input
+/// descriptors (or nested rules) need to be fulfilled.
+/// See: https://identity.foundation/presentation-exchange/spec/v2.0.0/#submission-requirements
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+struct SubmissionRequirement {
+    rule: Rule,
+    count: Option<usize>,
+    min: Option<usize>,
+    max: Option<usize>,
+    from: Option<String>,
+    from_nested: Option<Vec<SubmissionRequirement>>,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum Rule {
+    All,
+    Pick,
+}
+
+impl SubmissionRequirement {
+    // With `exact`, the upper bounds (`count` and `max`) of `pick` rules must be respected as well. This is only the
+    // case for the final selection, since a candidate that is offered to the user does not need to be selected.
+    fn is_satisfied(&self, groups: &[(String, Vec<String>)], fulfilled: &HashSet<String>, exact: bool) -> bool {
+        let (total, satisfied) = match (&self.from, &self.from_nested) {
+            (Some(group), _) => {
+                let members: Vec<_> = groups
+                    .iter()
+                    .filter(|(_, member_of)| member_of.contains(group))
+                    .map(|(id, _)| id)
+                    .collect();
+                (
+                    members.len(),
+                    members.into_iter().filter(|id| fulfilled.contains(*id)).count(),
+                )
+            }
+            (None, Some(nested)) => (
+                nested.len(),
+                nested
+                    .iter()
+                    .filter(|requirement| requirement.is_satisfied(groups, fulfilled, exact))
+                    .count(),
+            ),
+            (None, None) => (0, 0),
+        };
+
+        match self.rule {
+            Rule::All => satisfied == total,
+            Rule::Pick => {
+                let lower_bound = self.count.or(self.min).unwrap_or(0);
+                satisfied >= lower_bound && (!exact || satisfied <= self.upper_bound())
+            }
+        }
+    }
+
+    // Returns `true` if more input descriptors (or nested rules) are fulfilled than a `pick` rule allows.
+    fn exceeds_upper_bound(&self, groups: &[(String, Vec<String>)], fulfilled: &HashSet<String>) -> bool {
+        let satisfied = match (&self.from, &self.from_nested) {
+            (Some(group), _) => groups
+                .iter()
+                .filter(|(id, member_of)| member_of.contains(group) && fulfilled.contains(id))
+                .count(),
+            (None, Some(nested)) => {
+                if nested
+                    .iter()
+                    .any(|requirement| requirement.exceeds_upper_bound(groups, fulfilled))
+                {
+                    return true;
+                }
+                nested
+                    .iter()
+                    .filter(|requirement| requirement.is_satisfied(groups, fulfilled, false))
+                    .count()
+            }
+            (None, None) => 0,
+        };
+
+        self.rule == Rule::Pick && satisfied > self.upper_bound()
+    }
+
+    fn upper_bound(&self) -> usize {
+        self.count.or(self.max).unwrap_or(usize::MAX)
+    }
+}
+
+/// The input descriptors of a presentation definition together with its (optional) submission requirements.
+struct PresentationDefinition {
+    /// The id and groups of every input descriptor, in the order of the presentation definition.
+    groups: Vec<(String, Vec<String>)>,
+    submission_requirements: Option<Vec<SubmissionRequirement>>,
+}
+
+impl PresentationDefinition {
+    fn from_authorization_request(
+        oid4vp_authorization_request: &AuthorizationRequest<Object<OID4VP>>,
+    ) -> Result<Self, AppError> {
+        let presentation_definition = json!(oid4vp_authorization_request.body.extension.presentation_definition);
+
+        let groups = presentation_definition["input_descriptors"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|input_descriptor| {
+                (
+                    input_descriptor["id"].as_str().unwrap_or_default().to_string(),
+                    input_descriptor["group"]
+                        .as_array()
+                        .into_iter()
+                        .flatten()
+                        .filter_map(Value::as_str)
+                        .map(ToString::to_string)
+                        .collect(),
+                )
+            })
+            .collect();
+
+        let submission_requirements = match presentation_definition.get("submission_requirements") {
+            Some(submission_requirements) => Some(
+                serde_json::from_value(submission_requirements.clone())
+                    .map_err(|_| InvalidSubmissionRequirementsError)?,
+            ),
+            None => None,
+        };
+
+        Ok(Self {
+            groups,
+            submission_requirements,
+        })
+    }
+
+    // Without submission requirements, all input descriptors need to be fulfilled.
+    fn is_satisfied(&self, fulfilled: &HashSet<String>, exact: bool) -> bool {
+        match &self.submission_requirements {
+            Some(submission_requirements) => submission_requirements
+                .iter()
+                .all(|requirement| requirement.is_satisfied(&self.groups, fulfilled, exact)),
+            None => self.groups.iter().all(|(id, _)| fulfilled.contains(id)),
+        }
+    }
+
+    fn exceeds_upper_bound(&self, fulfilled: &HashSet<String>) -> bool {
+        self.submission_requirements
+            .iter()
+            .flatten()
+            .any(|requirement| requirement.exceeds_upper_bound(&self.groups, fulfilled))
+    }
+}
+
+/// Lists all credentials that fulfill each of the input descriptors of the presentation definition. An input
+/// descriptor is `required` when the submission requirements cannot be satisfied without it. Fails with
+/// `NoMatchingCredentialError` when the available credentials cannot satisfy the submission requirements.
+pub fn input_descriptor_options(
+    oid4vp_authorization_request: &AuthorizationRequest<Object<OID4VP>>,
+    verifiable_credential_records: &[VerifiableCredentialRecord],
+) -> Result<Vec<InputDescriptorOptions>, AppError> {
+    let presentation_definition = PresentationDefinition::from_authorization_request(oid4vp_authorization_request)?;
+
+    let mut input_descriptor_options: Vec<InputDescriptorOptions> = oid4vp_authorization_request
+        .body
+        .extension
+        .presentation_definition
+        .input_descriptors()
+        .iter()
+        .map(|input_descriptor| {
+            let input_descriptor_json = json!(input_descriptor);
+
+            InputDescriptorOptions {
+                id: input_descriptor_json["id"].as_str().unwrap_or_default().to_string(),
+                name: input_descriptor_json["name"].as_str().map(ToString::to_string),
+                purpose: input_descriptor_json["purpose"].as_str().map(ToString::to_string),
+                options: verifiable_credential_records
+                    .iter()
+                    .filter(|verifiable_credential_record| {
+                        evaluate_input(
+                            input_descriptor,
+                            &verifiable_credential_record.display_credential.presentation_claims(),
+                        )
+                    })
+                    .map(|verifiable_credential_record| verifiable_credential_record.display_credential.id.clone())
+                    .collect(),
+                required: false,
+            }
+        })
+        .collect();
+
+    let fulfillable: HashSet<String> = input_descriptor_options
+        .iter()
+        .filter(|input_descriptor| !input_descriptor.options.is_empty())
+        .map(|input_descriptor| input_descriptor.id.clone())
+        .collect();
+
+    if !presentation_definition.is_satisfied(&fulfillable, false) {
+        return Err(NoMatchingCredentialError);
+    }
+
+    for input_descriptor in input_descriptor_options.iter_mut() {
+        let mut without_input_descriptor = fulfillable.clone();
+        without_input_descriptor.remove(&input_descriptor.id);
+
+        input_descriptor.required = !presentation_definition.is_satisfied(&without_input_descriptor, false);
+    }
+
+    Ok(input_descriptor_options)
+}
+
+/// Describes what each of the input descriptors of the presentation definition asks for, so the user can be told why
+/// none of the stored credentials match. An input descriptor is `required` when the submission requirements cannot be
+/// satisfied without it.
+pub fn requested_input_descriptors(
+    oid4vp_authorization_request: &AuthorizationRequest<Object<OID4VP>>,
+) -> Result<Vec<RequestedInputDescriptor>, AppError> {
+    let presentation_definition = PresentationDefinition::from_authorization_request(oid4vp_authorization_request)?;
+    let all: HashSet<String> = presentation_definition
+        .groups
+        .iter()
+        .map(|(id, _)| id.clone())
+        .collect();
+
+    Ok(oid4vp_authorization_request
+        .body
+        .extension
+        .presentation_definition
+        .input_descriptors()
+        .iter()
+        .map(|input_descriptor| {
+            let input_descriptor = json!(input_descriptor);
+            let id = input_descriptor["id"].as_str().unwrap_or_default().to_string();
+
+            let mut without_input_descriptor = all.clone();
+            without_input_descriptor.remove(&id);
+
+            RequestedInputDescriptor {
+                required: !presentation_definition.is_satisfied(&without_input_descriptor, false),
+                name: input_descriptor["name"].as_str().map(ToString::to_string),
+                purpose: input_descriptor["purpose"].as_str().map(ToString::to_string),
+                fields: input_descriptor["constraints"]["fields"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter(|field| field["optional"] != true)
+                    .filter_map(|field| field["path"].as_array()?.first()?.as_str().map(ToString::to_string))
+                    .collect(),
+                id,
+            }
+        })
+        .collect())
+}
+
+/// Returns the selected credential for each of the fulfilled input descriptors, in the order of the presentation
+/// definition. Without an explicit `input_descriptor_selection`, the input descriptors are fulfilled in order by the
+/// first of the `credential_uuids` that matches them, until the submission requirements are met. Fails when the
+/// selection does not satisfy the submission requirements or when a selected credential does not match its input
+/// descriptor.
+pub fn select_credentials(
+    oid4vp_authorization_request: &AuthorizationRequest<Object<OID4VP>>,
+    verifiable_credential_records: &[VerifiableCredentialRecord],
+    credential_uuids: &[Uuid],
+    input_descriptor_selection: Option<&HashMap<String, Uuid>>,
+) -> Result<Vec<(String, VerifiableCredentialRecord)>, AppError> {
+    let presentation_definition = PresentationDefinition::from_authorization_request(oid4vp_authorization_request)?;
+
+    let find_record = |uuid: &Uuid| {
+        verifiable_credential_records
+            .iter()
+            .find(|verifiable_credential_record| verifiable_credential_record.display_credential.id == uuid.to_string())
+    };
+
+    let mut selection = vec![];
+    let mut fulfilled: HashSet<String> = HashSet::new();
+    for input_descriptor in oid4vp_authorization_request
+        .body
+        .extension
+        .presentation_definition
+        .input_descriptors()
+    {
+        let id = json!(input_descriptor)["id"].as_str().unwrap_or_default().to_string();
+        let matches = |verifiable_credential_record: &&VerifiableCredentialRecord| {
+            evaluate_input(
+                input_descriptor,
+                &verifiable_credential_record.display_credential.presentation_claims(),
+            )
+        };
+
+        let verifiable_credential_record = match input_descriptor_selection {
+            Some(input_descriptor_selection) => match input_descriptor_selection.get(&id) {
+                Some(uuid) => Some(
+                    find_record(uuid)
+                        .filter(matches)
+                        .ok_or(InvalidCredentialSelectionError(id.clone()))?,
+                ),
+                None => None,
+            },
+            None => {
+                if presentation_definition.is_satisfied(&fulfilled, true) {
+                    break;
+                }
+
+                // An input descriptor is skipped when fulfilling it would exceed the `count` or `max` of a `pick` rule.
+                let mut with_input_descriptor = fulfilled.clone();
+                with_input_descriptor.insert(id.clone());
+                match presentation_definition.exceeds_upper_bound(&with_input_descriptor) {
+                    true => None,
+                    false => credential_uuids.iter().filter_map(find_record).find(matches),
+                }
+            }
+        };
+
+        if let Some(verifiable_credential_record) = verifiable_credential_record {
+            fulfilled.insert(id.clone());
+            selection.push((id, verifiable_credential_record.clone()));
+        }
+    }
+
+    if !presentation_definition.is_satisfied(&fulfilled, true) {
+        return Err(SubmissionRequirementsNotSatisfiedError);
+    }
+
+    Ok(selection)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+
+    // A `jwt_vc_json` credential of the given type.
+    fn record(credential_type: &str, given_name: &str) -> VerifiableCredentialRecord {
+        let claims = json!({
+            "iss": "did:example:issuer",
+            "vc": {
+                "type": ["VerifiableCredential", credential_type],
+                "issuanceDate": "2024-01-01T00:00:00Z",
+                "credentialSubject": { "givenName": given_name }
+            }
+        });
+
+        VerifiableCredentialRecord::try_from(json!(format!(
+            "{}.{}.c2ln",
+            URL_SAFE_NO_PAD.encode(json!({ "alg": "EdDSA" }).to_string()),
+            URL_SAFE_NO_PAD.encode(claims.to_string())
+        )))
+        .unwrap()
+    }
+
+    fn input_descriptor(id: &str, credential_type: &str, group: &str) -> Value {
+        json!({
+            "id": id,
+            "group": [group],
+            "constraints": {
+                "fields": [{
+                    "path": ["$.vc.type"],
+                    "filter": { "type": "array", "contains": { "const": credential_type } }
+                }]
+            }
+        })
+    }
+
+    // Requests one of a passport or a driver's license, and an email credential.
+    fn authorization_request() -> AuthorizationRequest<Object<OID4VP>> {
+        serde_json::from_value(json!({
+            "response_type": "vp_token",
+            "client_id": "did:example:verifier",
+            "redirect_uri": "https://example.com/callback",
+            "nonce": "nonce",
+            "presentation_definition": {
+                "id": "identification",
+                "input_descriptors": [
+                    input_descriptor("passport", "Passport", "A"),
+                    input_descriptor("drivers_license", "DriversLicense", "A"),
+                    input_descriptor("email", "Email", "B"),
+                ],
+                "submission_requirements": [
+                    { "rule": "pick", "count": 1, "from": "A" },
+                    { "rule": "all", "from": "B" }
+                ]
+            },
+            "client_metadata": { "vp_formats": { "jwt_vc_json": { "alg": ["EdDSA"] } } }
+        }))
+        .unwrap()
+    }
+
+    fn uuid(verifiable_credential_record: &VerifiableCredentialRecord) -> Uuid {
+        verifiable_credential_record.display_credential.id.parse().unwrap()
+    }
+
+    fn groups() -> Vec<(String, Vec<String>)> {
+        vec![
+            ("passport".to_string(), vec!["A".to_string()]),
+            ("drivers_license".to_string(), vec!["A".to_string()]),
+            ("email".to_string(), vec!["B".to_string()]),
+        ]
+    }
+
+    fn fulfilled(ids: &[&str]) -> HashSet<String> {
+        ids.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn pick_rule_respects_count() {
+        let requirement: SubmissionRequirement =
+            serde_json::from_value(json!({ "rule": "pick", "count": 1, "from": "A" })).unwrap();
+
+        assert!(requirement.is_satisfied(&groups(), &fulfilled(&["passport"]), true));
+        assert!(!requirement.is_satisfied(&groups(), &fulfilled(&["email"]), true));
+        // Both credentials can be offered, but only one of them can be submitted.
+        assert!(requirement.is_satisfied(&groups(), &fulfilled(&["passport", "drivers_license"]), false));
+        assert!(!requirement.is_satisfied(&groups(), &fulfilled(&["passport", "drivers_license"]), true));
+    }
+
+    #[test]
+    fn nested_rules_are_evaluated() {
+        let requirement: SubmissionRequirement = serde_json::from_value(json!({
+            "rule": "all",
+            "from_nested": [
+                { "rule": "pick", "min": 1, "from": "A" },
+                { "rule": "all", "from": "B" }
+            ]
+        }))
+        .unwrap();
+
+        assert!(requirement.is_satisfied(&groups(), &fulfilled(&["drivers_license", "email"]), true));
+        assert!(!requirement.is_satisfied(&groups(), &fulfilled(&["passport", "drivers_license"]), true));
+    }
+
+    #[test]
+    fn input_descriptor_options_list_all_matching_credentials() {
+        let records = [
+            record("Passport", "Ferris"),
+            record("DriversLicense", "Ferris"),
+            record("Email", "Ferris"),
+            record("Email", "Crab"),
+        ];
+
+        let options = input_descriptor_options(&authorization_request(), &records).unwrap();
+
+        assert_eq!(
+            options
+                .iter()
+                .map(|input_descriptor| (
+                    input_descriptor.id.as_str(),
+                    input_descriptor.options.len(),
+                    input_descriptor.required
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                ("passport", 1, false),
+                ("drivers_license", 1, false),
+                ("email", 2, true)
+            ]
+        );
+
+        // Without a credential for group B, the submission requirements cannot be satisfied.
+        assert!(matches!(
+            input_descriptor_options(&authorization_request(), &records[..2]),
+            Err(NoMatchingCredentialError)
+        ));
+    }
+
+    #[test]
+    fn select_credentials_stops_once_the_submission_requirements_are_met() {
+        let records = [
+            record("Passport", "Ferris"),
+            record("DriversLicense", "Ferris"),
+            record("Email", "Ferris"),
+        ];
+        let uuids: Vec<Uuid> = records.iter().map(uuid).collect();
+
+        // Only one of the passport and the driver's license is submitted.
+        let selection = select_credentials(&authorization_request(), &records, &uuids, None).unwrap();
+        assert_eq!(
+            selection
+                .iter()
+                .map(|(id, verifiable_credential_record)| (id.as_str(), uuid(verifiable_credential_record)))
+                .collect::<Vec<_>>(),
+            vec![("passport", uuids[0]), ("email", uuids[2])]
+        );
+
+        // An explicit selection is respected, as long as it satisfies the submission requirements.
+        let input_descriptor_selection = HashMap::from([
+            ("drivers_license".to_string(), uuids[1]),
+            ("email".to_string(), uuids[2]),
+        ]);
+        let selection = select_credentials(
+            &authorization_request(),
+            &records,
+            &uuids,
+            Some(&input_descriptor_selection),
+        )
+        .unwrap();
+        assert_eq!(selection[0].0, "drivers_license");
+
+        let input_descriptor_selection = HashMap::from([
+            ("passport".to_string(), uuids[0]),
+            ("drivers_license".to_string(), uuids[1]),
+            ("email".to_string(), uuids[2]),
+        ]);
+        assert!(matches!(
+            select_credentials(
+                &authorization_request(),
+                &records,
+                &uuids,
+                Some(&input_descriptor_selection)
+            ),
+            Err(SubmissionRequirementsNotSatisfiedError)
+        ));
+
+        // A selected credential needs to match its input descriptor.
+        let input_descriptor_selection =
+            HashMap::from([("passport".to_string(), uuids[2]), ("email".to_string(), uuids[2])]);
+        assert!(matches!(
+            select_credentials(
+                &authorization_request(),
+                &records,
+                &uuids,
+                Some(&input_descriptor_selection)
+            ),
+            Err(InvalidCredentialSelectionError(id)) if id == "passport"
+        ));
+    }
+
+    #[test]
+    fn requested_input_descriptors_describe_the_request() {
+        let requested = requested_input_descriptors(&authorization_request()).unwrap();
+
+        assert_eq!(
+            requested
+                .iter()
+                .map(|input_descriptor| (input_descriptor.id.as_str(), input_descriptor.required))
+                .collect::<Vec<_>>(),
+            vec![("passport", false), ("drivers_license", false), ("email", true)]
+        );
+        assert_eq!(requested[2].fields, vec!["$.vc.type".to_string()]);
+    }
+}
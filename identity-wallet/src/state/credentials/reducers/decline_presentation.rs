@@ -0,0 +1,146 @@
+use crate::{
+    error::AppError::{self, *},
+    state::{
+        actions::{listen, Action},
+        common::reducers::cancel_user_flow::decline_connection_request,
+        core_utils::ConnectionRequest,
+        credentials::actions::presentation_declined::PresentationDeclined,
+        user_prompt::CurrentUserPrompt,
+        AppState,
+    },
+};
+
+/// Notifies the verifier that the user declined the active presentation request with an `access_denied` error
+/// response. Unlike `CancelUserFlow`, this fails when there is no active presentation request.
+pub async fn decline_presentation(state: AppState, action: Action) -> Result<AppState, AppError> {
+    if listen::<PresentationDeclined>(action).is_some() {
+        if !matches!(
+            state.core_utils.active_connection_request,
+            Some(ConnectionRequest::OID4VP(_))
+        ) {
+            return Err(MissingAuthorizationRequestError);
+        }
+
+        let error_description = match state.current_user_prompt {
+            Some(CurrentUserPrompt::NoMatchingCredentials { .. }) => {
+                "The wallet has no credentials matching the request"
+            }
+            _ => "The user declined the request",
+        };
+
+        let state = decline_connection_request(state, error_description).await;
+
+        return Ok(AppState {
+            current_user_prompt: Some(CurrentUserPrompt::Redirect {
+                target: "me".to_string(),
+            }),
+            ..state
+        });
+    }
+
+    Ok(state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        persistence::STRONGHOLD,
+        state::{
+            core_utils::{history_event::EventType, IdentityManager, Managers},
+            did::available_did_methods,
+            SUPPORTED_SIGNING_ALGORITHMS,
+        },
+        stronghold::StrongholdManager,
+        subject::subject,
+    };
+
+    use oid4vc::{oid4vc_manager::ProviderManager, oid4vci::Wallet};
+    use serde_json::json;
+    use std::sync::Arc;
+    use tempfile::NamedTempFile;
+    use wiremock::{
+        matchers::{body_string_contains, method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    #[tokio::test]
+    async fn declining_requires_an_active_presentation_request() {
+        let state = AppState {
+            current_user_prompt: Some(CurrentUserPrompt::Redirect {
+                target: "me".to_string(),
+            }),
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            decline_presentation(state, Arc::new(PresentationDeclined)).await,
+            Err(MissingAuthorizationRequestError)
+        ));
+    }
+
+    #[tokio::test]
+    #[serial_test::serial]
+    async fn declining_sends_an_access_denied_error_response() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/callback"))
+            .and(body_string_contains("error=access_denied"))
+            .and(body_string_contains("state=af0ifjsldkj"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let path = NamedTempFile::new().unwrap().into_temp_path();
+        *STRONGHOLD.lock().unwrap() = path.as_os_str().into();
+        let stronghold_manager = Arc::new(StrongholdManager::create("sup3rSecr3t").unwrap());
+
+        let state = AppState::default();
+        let subject = subject(stronghold_manager.clone(), "sup3rSecr3t".to_string(), 0).await;
+        let did_methods = available_did_methods(&state.profile_settings.preferred_did_methods, &state.dids);
+        let identity_manager = IdentityManager {
+            subject: subject.clone(),
+            provider_manager: ProviderManager::new(
+                subject.clone(),
+                did_methods.clone(),
+                Vec::from(SUPPORTED_SIGNING_ALGORITHMS),
+            )
+            .unwrap(),
+            wallet: Wallet::new(subject, did_methods, Vec::from(SUPPORTED_SIGNING_ALGORITHMS)).unwrap(),
+        };
+
+        let oid4vp_authorization_request = serde_json::from_value(json!({
+            "response_type": "vp_token",
+            "client_id": format!("{}/callback", mock_server.uri()),
+            "redirect_uri": format!("{}/callback", mock_server.uri()),
+            "state": "af0ifjsldkj",
+            "nonce": "nonce",
+            "presentation_definition": { "id": "identification", "input_descriptors": [] },
+            "client_metadata": { "client_name": "Verifier" }
+        }))
+        .unwrap();
+
+        let mut state = state;
+        state.core_utils.managers = Arc::new(tauri::async_runtime::Mutex::new(Managers {
+            stronghold_manager: Some(stronghold_manager),
+            identity_manager: Some(identity_manager),
+        }));
+        state.core_utils.active_connection_request =
+            Some(ConnectionRequest::OID4VP(Box::new(oid4vp_authorization_request)));
+
+        let state = decline_presentation(state, Arc::new(PresentationDeclined))
+            .await
+            .unwrap();
+
+        assert!(state.core_utils.active_connection_request.is_none());
+        assert_eq!(state.history[0].event_type, EventType::RequestDeclined);
+        assert_eq!(
+            state.current_user_prompt,
+            Some(CurrentUserPrompt::Redirect {
+                target: "me".to_string()
+            })
+        );
+    }
+}
@@ -0,0 +1,122 @@
+use crate::{
+    error::AppError::{self, *},
+    state::{
+        actions::{listen, Action},
+        credentials::{
+            actions::authorization_code_received::AuthorizationCodeReceived,
+            reducers::send_credential_request::receive_credentials,
+        },
+        AppState,
+    },
+};
+
+use log::info;
+use oid4vc::oid4vci::token_request::TokenRequest;
+
+/// Exchanges the authorization code received through the redirect callback of the authorization server for an access
+/// token, after which the credentials are requested from the credential issuer.
+pub async fn exchange_authorization_code(state: AppState, action: Action) -> Result<AppState, AppError> {
+    info!("exchange_authorization_code");
+
+    if let Some(AuthorizationCodeReceived {
+        code,
+        state: authorization_state,
+    }) = listen::<AuthorizationCodeReceived>(action)
+    {
+        let authorization_code_flow = state
+            .core_utils
+            .active_authorization_code_flow
+            .clone()
+            .ok_or(MissingStateParameterError("active authorization code flow"))?;
+
+        // Protect against CSRF by checking that the `state` matches the one sent in the authorization request.
+        if authorization_code_flow.state != authorization_state {
+            return Err(AuthorizationStateMismatchError);
+        }
+
+        let credential_issuer_url = state
+            .core_utils
+            .active_credential_offer
+            .as_ref()
+            .ok_or(MissingStateParameterError("active credential offer"))?
+            .credential_issuer
+            .clone();
+
+        let state_guard = state.core_utils.managers.lock().await;
+        let wallet = &state_guard
+            .identity_manager
+            .as_ref()
+            .ok_or(MissingManagerError("identity"))?
+            .wallet;
+
+        // Get the authorization server metadata.
+        let authorization_server_metadata = wallet
+            .get_authorization_server_metadata(credential_issuer_url)
+            .await
+            .map_err(GetAuthorizationServerMetadataError)?;
+
+        // Create a token request with grant_type `authorization_code` including the PKCE `code_verifier`.
+        let token_request = TokenRequest::AuthorizationCode {
+            code,
+            code_verifier: Some(authorization_code_flow.code_verifier),
+            redirect_uri: Some(authorization_code_flow.redirect_uri),
+        };
+
+        info!("token_request: {:?}", token_request);
+
+        // Get an access token.
+        let token_response = wallet
+            .get_access_token(
+                authorization_server_metadata
+                    .token_endpoint
+                    .ok_or(MissingStateParameterError("token endpoint"))?,
+                token_request,
+            )
+            .await
+            .map_err(GetAccessTokenError)?;
+
+        info!("token_response: {:?}", token_response);
+
+        drop(state_guard);
+        return receive_credentials(
+            state,
+            authorization_code_flow.credential_configuration_ids,
+            token_response,
+            authorization_code_flow.logo_uri,
+            authorization_code_flow.domain_validation,
+        )
+        .await;
+    }
+
+    Ok(state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::{core_utils::CoreUtils, credentials::authorization_code_flow::AuthorizationCodeFlow};
+
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn authorization_code_with_mismatching_state_is_rejected() {
+        let state = AppState {
+            core_utils: CoreUtils {
+                active_authorization_code_flow: Some(AuthorizationCodeFlow::new(vec![], None)),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let result = exchange_authorization_code(
+            state,
+            Arc::new(AuthorizationCodeReceived {
+                code: "SplxlOBeZQQYbYS6WxSbIA".to_string(),
+                state: "unexpected-state".to_string(),
+            }),
+        )
+        .await;
+
+        assert!(matches!(result, Err(AuthorizationStateMismatchError)));
+    }
+}
@@ -3,49 +3,64 @@ use crate::{
     persistence::{hash, persist_asset},
     state::{
         actions::{listen, Action},
+        connections::trust_registry::TrustedRole,
         core_utils::{
-            helpers::get_unverified_jwt_claims,
+            client_metadata::{cached_client_metadata, jwt_vc_json_algorithm, resolve_client_metadata},
+            helpers::requested_claim_paths,
             history_event::{EventType, HistoryCredential, HistoryEvent},
-            ConnectionRequest,
+            jarm::{is_direct_post_jwt, send_encrypted_response},
+            ConnectionRequest, IdentityManager,
         },
-        credentials::actions::credentials_selected::CredentialsSelected,
+        credentials::{
+            actions::credentials_selected::CredentialsSelected,
+            data_integrity::{add_proof, DATA_INTEGRITY_V2_CONTEXT},
+            mdoc::{device_response, oid4vp_session_transcript, Mdoc},
+            presentation_exchange::select_credentials,
+            sd_jwt::SdJwt,
+            CredentialFormat, VerifiableCredentialRecord,
+        },
+        did::available_did_methods,
         user_prompt::CurrentUserPrompt,
-        AppState,
+        AppState, SUPPORTED_SIGNING_ALGORITHMS,
     },
 };
 
+use chrono::Utc;
 use identity_credential::{credential::Jwt, presentation::Presentation};
-use identity_iota::did::CoreDID;
 use jsonwebtoken::Algorithm;
 use log::info;
-use oid4vc::oid4vc_manager::managers::presentation::create_presentation_submission;
+use oid4vc::oid4vc_core::Subject as _;
 use oid4vc::oid4vp::authorization_request::ClientMetadataParameters;
 use oid4vc::oid4vp::oid4vp;
 use oid4vc::oid4vp::oid4vp::OID4VP;
+use oid4vc::oid4vp::oid4vp_params::Oid4vpParams;
 use oid4vc::{
     oid4vc_core::{
         authorization_request::{AuthorizationRequest, Object},
+        authorization_response::AuthorizationResponse,
         client_metadata::ClientMetadataResource,
     },
+    oid4vc_manager::ProviderManager,
     oid4vp::{ClaimFormatDesignation, ClaimFormatProperty},
 };
+use serde_json::json;
+use uuid::Uuid;
 
 // Sends the authorization response including the verifiable credentials.
 pub async fn handle_oid4vp_authorization_request(state: AppState, action: Action) -> Result<AppState, AppError> {
     info!("handle_presentation_request");
 
-    if let Some(credential_uuids) = listen::<CredentialsSelected>(action).map(|payload| payload.credential_uuids) {
+    if let Some(CredentialsSelected {
+        credential_uuids,
+        input_descriptor_selection,
+    }) = listen::<CredentialsSelected>(action)
+    {
         let state_guard = state.core_utils.managers.lock().await;
 
         let stronghold_manager = state_guard
             .stronghold_manager
             .as_ref()
             .ok_or(MissingManagerError("stronghold"))?;
-        let provider_manager = &state_guard
-            .identity_manager
-            .as_ref()
-            .ok_or(MissingManagerError("identity"))?
-            .provider_manager;
 
         let oid4vp_authorization_request =
             match serde_json::from_value(serde_json::json!(state.core_utils.active_connection_request)).unwrap() {
@@ -53,42 +68,56 @@ pub async fn handle_oid4vp_authorization_request(state: AppState, action: Action
                 ConnectionRequest::SIOPv2(_) => unreachable!(),
             };
 
-        let mut history_credentials = Vec::new();
+        let verifiable_credential_records: Vec<VerifiableCredentialRecord> =
+            stronghold_manager.values().map_err(StrongholdValuesError)?.unwrap();
 
-        let verifiable_credentials: Vec<serde_json::Value> = stronghold_manager
-            .values()
-            .map_err(StrongholdValuesError)?
-            .unwrap()
-            .iter()
-            .filter_map(|verifiable_credential_record| {
-                let share_credential = credential_uuids
-                    .contains(&verifiable_credential_record.display_credential.id.parse().unwrap())
-                    .then_some(verifiable_credential_record.verifiable_credential.clone());
-
-                if share_credential.is_some() {
-                    history_credentials.push(HistoryCredential::from_credential(verifiable_credential_record));
-                }
+        // The credential that fulfills each of the input descriptors chosen by the user.
+        let selection = select_credentials(
+            &oid4vp_authorization_request,
+            &verifiable_credential_records,
+            &credential_uuids,
+            input_descriptor_selection.as_ref(),
+        )?;
+        let selected_credential_records = selected_credential_records(&selection);
 
-                share_credential
-            })
+        let history_credentials = selected_credential_records
+            .iter()
+            .map(HistoryCredential::from_credential)
             .collect();
 
-        let presentation_submission = create_presentation_submission(
-            &oid4vp_authorization_request.body.extension.presentation_definition,
-            &verifiable_credentials
-                .iter()
-                .map(get_unverified_jwt_claims)
-                .collect::<Result<Vec<_>, _>>()?,
-        )
-        .map_err(PresentationSubmissionError)?;
-
-        info!("get the subject did");
-
         let identity_manager = state_guard
             .identity_manager
             .as_ref()
             .ok_or(MissingManagerError("identity"))?;
 
+        // Credentials can only be presented with the holder keys they are bound to.
+        let mut key_indices = selected_credential_records
+            .iter()
+            .map(|verifiable_credential_record| verifiable_credential_record.display_credential.metadata.key_index);
+        let key_index = key_indices.next().unwrap_or_default();
+        if key_indices.any(|other_key_index| other_key_index != key_index) {
+            return Err(MixedHolderKeysError);
+        }
+        // Keys that have been replaced by a rotation can only be used during the grace period.
+        if !state.holder_keys.is_usable_at(key_index, Utc::now()) {
+            return Err(RetiredHolderKeysError(key_index));
+        }
+        let identity_manager = &identity_manager
+            .with_keys(
+                key_index,
+                available_did_methods(&state.profile_settings.preferred_did_methods, &state.dids),
+            )
+            .await?;
+        let provider_manager = &identity_manager.provider_manager;
+
+        // The client metadata document that has been resolved when the request was read may have expired since, while
+        // the algorithm and the encryption of the response depend on it.
+        if let ClientMetadataResource::ClientMetadataUri(client_metadata_uri) =
+            &oid4vp_authorization_request.body.extension.client_metadata
+        {
+            resolve_client_metadata(&client_metadata_uri.to_string()).await?;
+        }
+
         let OID4VPClientMetadata {
             client_name,
             logo_uri,
@@ -97,52 +126,136 @@ pub async fn handle_oid4vp_authorization_request(state: AppState, action: Action
             algorithm,
         } = get_oid4vp_client_name_and_logo_uri(&oid4vp_authorization_request);
 
-        let subject_did = identity_manager
-            .subject
-            .identifier(state.profile_settings.preferred_did_methods.first().unwrap(), algorithm)
-            .await
-            .expect("No default DID method");
-
-        let mut presentation_builder =
-            Presentation::builder(subject_did.parse().map_err(|_| DidParseError)?, Default::default());
-        for verifiable_credential in verifiable_credentials {
-            presentation_builder = presentation_builder.credential(Jwt::from(
-                verifiable_credential
-                    .as_str()
-                    .ok_or(InvalidCredentialFormatError)?
-                    .to_string(),
-            ));
+        // All shared credentials need to be of the same format, since each format is presented in its own way.
+        let credential_format = selected_credential_records
+            .first()
+            .map(|verifiable_credential_record| verifiable_credential_record.display_credential.format.clone())
+            .unwrap_or_default();
+        if selected_credential_records.iter().any(|verifiable_credential_record| {
+            verifiable_credential_record.display_credential.format != credential_format
+        }) {
+            return Err(MixedCredentialFormatsError);
         }
 
-        let verifiable_presentation: Presentation<Jwt, _> =
-            presentation_builder.build().map_err(PresentationBuilderError)?;
+        let subject_syntax_type = state.profile_settings.preferred_did_methods.first().unwrap();
 
-        info!("get the provider_manager");
-
-        info!("generating response");
-        let response = provider_manager
-            .generate_response(
+        if credential_format == CredentialFormat::VcSdJwt {
+            info!("generating SD-JWT response");
+            send_sd_jwt_response(
                 &oid4vp_authorization_request,
-                oid4vp::AuthorizationResponseInput {
-                    verifiable_presentation,
-                    presentation_submission,
-                },
+                &selection,
+                identity_manager,
+                subject_syntax_type,
             )
-            .await
-            .map_err(GenerateAuthorizationResponseError)?;
-        info!("response generated: {:?}", response);
+            .await?;
+            info!("response successfully sent");
+        } else if credential_format == CredentialFormat::MsoMdoc {
+            info!("generating mdoc response");
+            send_mdoc_response(&oid4vp_authorization_request, &selection, identity_manager, algorithm).await?;
+            info!("response successfully sent");
+        } else if credential_format == CredentialFormat::LdpVc {
+            info!("generating ldp_vp response");
+            send_ldp_vp_response(
+                &oid4vp_authorization_request,
+                &selection,
+                identity_manager,
+                subject_syntax_type,
+            )
+            .await?;
+            info!("response successfully sent");
+        } else {
+            let verifiable_credentials: Vec<serde_json::Value> = selected_credential_records
+                .into_iter()
+                .map(|verifiable_credential_record| verifiable_credential_record.verifiable_credential)
+                .collect();
+
+            let presentation_submission = serde_json::from_value(presentation_submission(
+                &oid4vp_authorization_request,
+                &selection,
+                |index| {
+                    json!({
+                        "format": "jwt_vp_json",
+                        "path": "$",
+                        "path_nested": {
+                            "format": "jwt_vc_json",
+                            "path": format!("$.vp.verifiableCredential[{index}]")
+                        },
+                    })
+                },
+            )?)
+            .map_err(|e| PresentationSubmissionError(e.into()))?;
+
+            info!("get the subject did");
+
+            let subject_did = identity_manager
+                .subject
+                .identifier(subject_syntax_type, algorithm)
+                .await
+                .expect("No default DID method");
+
+            let mut presentation_builder =
+                Presentation::builder(subject_did.parse().map_err(|_| DidParseError)?, Default::default());
+            for verifiable_credential in verifiable_credentials {
+                presentation_builder = presentation_builder.credential(Jwt::from(
+                    verifiable_credential
+                        .as_str()
+                        .ok_or(InvalidCredentialFormatError)?
+                        .to_string(),
+                ));
+            }
+
+            let verifiable_presentation: Presentation<Jwt, _> =
+                presentation_builder.build().map_err(PresentationBuilderError)?;
+
+            info!("get the provider_manager");
+
+            info!("generating response");
+            let response = provider_manager
+                .generate_response(
+                    &oid4vp_authorization_request,
+                    oid4vp::AuthorizationResponseInput {
+                        verifiable_presentation,
+                        presentation_submission,
+                    },
+                )
+                .await
+                .map_err(GenerateAuthorizationResponseError)?;
+            info!("response generated: {:?}", response);
+
+            if is_direct_post_jwt(&json!(oid4vp_authorization_request.body)) {
+                let mut response_parameters = json!(response);
+                if let Some(response_parameters) = response_parameters.as_object_mut() {
+                    response_parameters.remove("redirect_uri");
+                }
 
-        if provider_manager.send_response(&response).await.is_err() {
-            info!("failed to send response");
-            return Err(SendAuthorizationResponseError);
+                send_encrypted_response(
+                    provider_manager,
+                    &oid4vp_authorization_request.body.redirect_uri,
+                    &oid4vp_client_metadata(&oid4vp_authorization_request),
+                    &response_parameters,
+                    None,
+                    Some(oid4vp_authorization_request.body.extension.nonce.as_str()),
+                )
+                .await?;
+            } else if provider_manager.send_response(&response).await.is_err() {
+                info!("failed to send response");
+                return Err(SendAuthorizationResponseError);
+            }
+            info!("response successfully sent");
         }
-        info!("response successfully sent");
 
-        let did = CoreDID::parse(client_id).ok();
+        let did = state.core_utils.validated_client_did(client_id);
 
         let previously_connected = state.connections.contains(connection_url.as_str(), &client_name);
+        // The domain validation that has been shown to the user is stored on the connection.
+        let domain_validation = state.core_utils.active_domain_validation.clone().unwrap_or_default();
+
         let mut connections = state.connections;
-        let connection = connections.update_or_insert(&connection_url, &client_name, did);
+        let connection = connections.update_or_insert(&connection_url, &client_name, did, TrustedRole::Verifier);
+        connection.domain_validation = domain_validation;
+        if !previously_connected {
+            connection.key_index = key_index;
+        }
 
         let file_name = match logo_uri {
             Some(logo_uri) => hash(logo_uri.as_str()),
@@ -186,6 +299,293 @@ pub async fn handle_oid4vp_authorization_request(state: AppState, action: Action
 
 // Helper
 
+/// Sends the authorization response for SD-JWT VCs. Every SD-JWT only contains the disclosures requested by the
+/// presentation definition and is bound to the holder by a Key Binding JWT for the verifier's `client_id` and `nonce`,
+/// signed with the holder key in its `cnf` claim.
+async fn send_sd_jwt_response(
+    oid4vp_authorization_request: &AuthorizationRequest<Object<OID4VP>>,
+    selection: &[(String, VerifiableCredentialRecord)],
+    identity_manager: &IdentityManager,
+    subject_syntax_type: &str,
+) -> Result<(), AppError> {
+    let sd_jwt_credential_records = selected_credential_records(selection);
+    let requested_claim_paths = requested_claim_paths(&json!(
+        oid4vp_authorization_request.body.extension.presentation_definition
+    ));
+
+    let mut vp_tokens = Vec::new();
+    for verifiable_credential_record in &sd_jwt_credential_records {
+        vp_tokens.push(
+            SdJwt::parse(&verifiable_credential_record.verifiable_credential)?
+                .select_disclosures(&requested_claim_paths)?
+                .with_key_binding(
+                    identity_manager.subject.as_ref(),
+                    subject_syntax_type,
+                    &oid4vp_authorization_request.body.client_id,
+                    &oid4vp_authorization_request.body.extension.nonce,
+                )
+                .await?,
+        );
+    }
+
+    // A single presentation is sent as is, multiple presentations are sent as an array.
+    let (vp_token, presentation_submission) = match vp_tokens.as_slice() {
+        [vp_token] => (
+            vp_token.clone(),
+            presentation_submission(
+                oid4vp_authorization_request,
+                selection,
+                |_| json!({ "format": "vc+sd-jwt", "path": "$" }),
+            )?,
+        ),
+        vp_tokens => (
+            json!(vp_tokens).to_string(),
+            presentation_submission(
+                oid4vp_authorization_request,
+                selection,
+                |index| json!({ "format": "vc+sd-jwt", "path": format!("$[{index}]") }),
+            )?,
+        ),
+    };
+
+    send_direct_post_response(
+        oid4vp_authorization_request,
+        &identity_manager.provider_manager,
+        vp_token,
+        presentation_submission,
+        None,
+    )
+    .await
+}
+
+/// Sends the authorization response for mdocs: a single `DeviceResponse` containing a document per mdoc, each of which
+/// only contains the requested elements and is signed with the holder's device key. The response is always encrypted,
+/// with the `mdoc_generated_nonce` as `apu`.
+/// See: ISO/IEC 18013-7, Annex B
+async fn send_mdoc_response(
+    oid4vp_authorization_request: &AuthorizationRequest<Object<OID4VP>>,
+    selection: &[(String, VerifiableCredentialRecord)],
+    identity_manager: &IdentityManager,
+    algorithm: Algorithm,
+) -> Result<(), AppError> {
+    // Only verifiers that explicitly support `mso_mdoc` are able to process a `DeviceResponse`.
+    let vp_formats = oid4vp_client_metadata(oid4vp_authorization_request)["vp_formats"].clone();
+    if vp_formats.get("mso_mdoc").is_none() {
+        return Err(UnsupportedVpFormatError("mso_mdoc"));
+    }
+    // The `mdoc_generated_nonce` of the session transcript is only known to the verifier through the `apu` of the
+    // encrypted response.
+    if !is_direct_post_jwt(&json!(oid4vp_authorization_request.body)) {
+        return Err(UnsupportedMdocResponseModeError);
+    }
+
+    let requested_claim_paths = requested_claim_paths(&json!(
+        oid4vp_authorization_request.body.extension.presentation_definition
+    ));
+
+    let mdoc_generated_nonce = Uuid::new_v4().to_string();
+    let session_transcript = oid4vp_session_transcript(
+        &oid4vp_authorization_request.body.client_id,
+        oid4vp_authorization_request.body.redirect_uri.as_str(),
+        &oid4vp_authorization_request.body.extension.nonce,
+        &mdoc_generated_nonce,
+    );
+
+    let mut documents = Vec::new();
+    for verifiable_credential_record in selected_credential_records(selection) {
+        documents.push(
+            Mdoc::parse(&verifiable_credential_record.verifiable_credential)?
+                .select_elements(&requested_claim_paths)
+                .document(&session_transcript, &identity_manager.subject, algorithm)
+                .await?,
+        );
+    }
+
+    let presentation_submission = presentation_submission(
+        oid4vp_authorization_request,
+        selection,
+        |_| json!({ "format": "mso_mdoc", "path": "$" }),
+    )?;
+
+    send_direct_post_response(
+        oid4vp_authorization_request,
+        &identity_manager.provider_manager,
+        device_response(documents),
+        presentation_submission,
+        Some(&mdoc_generated_nonce),
+    )
+    .await
+}
+
+/// Sends the authorization response for JSON-LD credentials: an `ldp_vp` containing the credentials as they were
+/// issued, secured with a Data Integrity proof of the holder for the verifier's `client_id` and `nonce`.
+async fn send_ldp_vp_response(
+    oid4vp_authorization_request: &AuthorizationRequest<Object<OID4VP>>,
+    selection: &[(String, VerifiableCredentialRecord)],
+    identity_manager: &IdentityManager,
+    subject_syntax_type: &str,
+) -> Result<(), AppError> {
+    let holder = identity_manager
+        .subject
+        .identifier(subject_syntax_type, Algorithm::EdDSA)
+        .await
+        .map_err(OID4VCSubjectIdentifierError)?;
+
+    let verifiable_presentation = add_proof(
+        json!({
+            "@context": ["https://www.w3.org/2018/credentials/v1", DATA_INTEGRITY_V2_CONTEXT],
+            "type": ["VerifiablePresentation"],
+            "holder": holder,
+            "verifiableCredential": selected_credential_records(selection)
+                .iter()
+                .map(|verifiable_credential_record| verifiable_credential_record.verifiable_credential.clone())
+                .collect::<Vec<_>>(),
+        }),
+        &identity_manager.subject,
+        subject_syntax_type,
+        "authentication",
+        &oid4vp_authorization_request.body.extension.nonce,
+        &oid4vp_authorization_request.body.client_id,
+    )
+    .await?;
+
+    let presentation_submission = presentation_submission(oid4vp_authorization_request, selection, |index| {
+        json!({
+            "format": "ldp_vp",
+            "path": "$",
+            "path_nested": { "format": "ldp_vc", "path": format!("$.verifiableCredential[{index}]") },
+        })
+    })?;
+
+    send_direct_post_response(
+        oid4vp_authorization_request,
+        &identity_manager.provider_manager,
+        verifiable_presentation.to_string(),
+        presentation_submission,
+        None,
+    )
+    .await
+}
+
+/// Returns the selected credentials without duplicates, since a single credential can fulfill multiple input
+/// descriptors.
+fn selected_credential_records(selection: &[(String, VerifiableCredentialRecord)]) -> Vec<VerifiableCredentialRecord> {
+    let mut verifiable_credential_records: Vec<VerifiableCredentialRecord> = vec![];
+    for (_, verifiable_credential_record) in selection {
+        if !verifiable_credential_records.contains(verifiable_credential_record) {
+            verifiable_credential_records.push(verifiable_credential_record.clone());
+        }
+    }
+    verifiable_credential_records
+}
+
+/// Maps every fulfilled input descriptor of the presentation definition to the credential selected for it. The
+/// `descriptor` function returns the `format` and `path` of the descriptor for the index of that credential among the
+/// presented credentials.
+fn presentation_submission(
+    oid4vp_authorization_request: &AuthorizationRequest<Object<OID4VP>>,
+    selection: &[(String, VerifiableCredentialRecord)],
+    descriptor: impl Fn(usize) -> serde_json::Value,
+) -> Result<serde_json::Value, AppError> {
+    let verifiable_credential_records = selected_credential_records(selection);
+
+    let descriptor_map = selection
+        .iter()
+        .map(|(id, verifiable_credential_record)| {
+            let index = verifiable_credential_records
+                .iter()
+                .position(|presented_credential_record| presented_credential_record == verifiable_credential_record)
+                .ok_or(NoMatchingCredentialError)?;
+
+            let mut descriptor = descriptor(index);
+            descriptor["id"] = json!(id);
+
+            Ok(descriptor)
+        })
+        .collect::<Result<Vec<_>, AppError>>()?;
+
+    Ok(json!({
+        "id": Uuid::new_v4().to_string(),
+        "definition_id": json!(oid4vp_authorization_request.body.extension.presentation_definition)["id"],
+        "descriptor_map": descriptor_map,
+    }))
+}
+
+/// Sends the `vp_token` and `presentation_submission` to the verifier using the `direct_post` response mode, or
+/// encrypted using the `direct_post.jwt` response mode if the verifier asks for it. The `mdoc_generated_nonce` of an
+/// mdoc presentation is bound to the encryption.
+async fn send_direct_post_response(
+    oid4vp_authorization_request: &AuthorizationRequest<Object<OID4VP>>,
+    provider_manager: &ProviderManager,
+    vp_token: String,
+    presentation_submission: serde_json::Value,
+    mdoc_generated_nonce: Option<&str>,
+) -> Result<(), AppError> {
+    if is_direct_post_jwt(&json!(oid4vp_authorization_request.body)) {
+        // Within the JWT, a `vp_token` containing multiple presentations (or a JSON-LD presentation) is JSON itself.
+        let vp_token = serde_json::from_str::<serde_json::Value>(&vp_token)
+            .ok()
+            .filter(|vp_token| vp_token.is_array() || vp_token.is_object())
+            .unwrap_or(json!(vp_token));
+
+        let mut response_parameters = json!({
+            "vp_token": vp_token,
+            "presentation_submission": presentation_submission,
+        });
+        if let Some(state) = &oid4vp_authorization_request.body.state {
+            response_parameters["state"] = json!(state);
+        }
+
+        return send_encrypted_response(
+            provider_manager,
+            &oid4vp_authorization_request.body.redirect_uri,
+            &oid4vp_client_metadata(oid4vp_authorization_request),
+            &response_parameters,
+            mdoc_generated_nonce,
+            Some(oid4vp_authorization_request.body.extension.nonce.as_str()),
+        )
+        .await;
+    }
+
+    let response = AuthorizationResponse::<OID4VP> {
+        redirect_uri: oid4vp_authorization_request.body.redirect_uri.to_string(),
+        state: oid4vp_authorization_request.body.state.clone(),
+        extension: oid4vp::AuthorizationResponseParameters {
+            oid4vp_parameters: Oid4vpParams::Params {
+                vp_token,
+                presentation_submission: serde_json::from_value(presentation_submission)
+                    .map_err(|e| PresentationSubmissionError(e.into()))?,
+            },
+        },
+    };
+
+    if provider_manager.send_response(&response).await.is_err() {
+        info!("failed to send response");
+        return Err(SendAuthorizationResponseError);
+    }
+
+    Ok(())
+}
+
+/// Returns the client metadata of the verifier as JSON, including the parameters that are not modelled explicitly, such
+/// as `jwks`.
+fn oid4vp_client_metadata(oid4vp_authorization_request: &AuthorizationRequest<Object<OID4VP>>) -> serde_json::Value {
+    match &oid4vp_authorization_request.body.extension.client_metadata {
+        ClientMetadataResource::ClientMetadata {
+            extension: ClientMetadataParameters { vp_formats },
+            other,
+            ..
+        } => {
+            let mut client_metadata = json!(other);
+            client_metadata["vp_formats"] = json!(vp_formats);
+            client_metadata
+        }
+        ClientMetadataResource::ClientMetadataUri(client_metadata_uri) => {
+            cached_client_metadata(&client_metadata_uri.to_string()).unwrap_or_default()
+        }
+    }
+}
+
 pub struct OID4VPClientMetadata {
     pub client_name: String,
     pub logo_uri: Option<String>,
@@ -219,11 +619,14 @@ pub fn get_oid4vp_client_name_and_logo_uri(
 
             // TODO: These helper functions become more and more complicated. This functionality needs to be implemented
             // in oid4vc-manager soon.
-            // Get the algorithm from the client_metadata if it exists or default to EdDSA.
+            // Get the first supported algorithm from the client_metadata if it exists or default to EdDSA.
             let algorithm = vp_formats
                 .get(&ClaimFormatDesignation::JwtVcJson)
                 .and_then(|claim_format_property| match claim_format_property {
-                    ClaimFormatProperty::Alg(alg) => alg.first().cloned(),
+                    ClaimFormatProperty::Alg(alg) => alg
+                        .iter()
+                        .find(|algorithm| SUPPORTED_SIGNING_ALGORITHMS.contains(algorithm))
+                        .cloned(),
                     // TODO: implement `ProofType`.
                     ClaimFormatProperty::ProofType(_) => None,
                 })
@@ -237,8 +640,19 @@ pub fn get_oid4vp_client_name_and_logo_uri(
                 algorithm,
             })
         }
-        // TODO: support `client_metadata_uri`
-        ClientMetadataResource::ClientMetadataUri(_) => None,
+        // The client metadata document is resolved when the authorization request is read.
+        ClientMetadataResource::ClientMetadataUri(client_metadata_uri) => {
+            cached_client_metadata(&client_metadata_uri.to_string()).map(|client_metadata| OID4VPClientMetadata {
+                client_name: client_metadata["client_name"]
+                    .as_str()
+                    .unwrap_or(connection_url)
+                    .to_string(),
+                logo_uri: client_metadata["logo_uri"].as_str().map(ToString::to_string),
+                connection_url: connection_url.to_string(),
+                client_id: client_id.clone(),
+                algorithm: jwt_vc_json_algorithm(&client_metadata["vp_formats"]).unwrap_or(Algorithm::EdDSA),
+            })
+        }
     }
     // Otherwise use the connection_url as the client_name.
     .unwrap_or(OID4VPClientMetadata {
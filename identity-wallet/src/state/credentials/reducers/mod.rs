@@ -1,4 +1,9 @@
+pub mod decline_presentation;
 pub mod delete_credential;
+pub mod exchange_authorization_code;
 pub mod handle_oid4vp_authorization_request;
+pub mod poll_deferred_credentials;
+pub mod refresh_credential;
+pub mod refresh_credential_statuses;
 pub mod send_credential_request;
 pub mod update_credential_metadata;
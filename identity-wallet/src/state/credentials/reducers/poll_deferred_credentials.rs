@@ -0,0 +1,190 @@
+use crate::{
+    error::AppError::{self, *},
+    state::{
+        actions::{listen, Action},
+        core_utils::{
+            history_event::{EventType, HistoryCredential, HistoryEvent},
+            DateUtils,
+        },
+        credentials::{
+            actions::poll_deferred_credentials::PollDeferredCredentials,
+            deferred_credential::fetch_deferred_credential,
+            reducers::send_credential_request::get_credential_display_name,
+            verification::{holder_dids, verify_credential},
+            VerifiableCredentialRecord,
+        },
+        AppState,
+    },
+};
+
+use log::{info, warn};
+use serde_json::json;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// Polls the deferred credential endpoints of the credential issuers for all pending credentials. Every credential that
+/// has been issued in the meantime replaces its pending entry.
+pub async fn poll_deferred_credentials(state: AppState, action: Action) -> Result<AppState, AppError> {
+    if listen::<PollDeferredCredentials>(action).is_some() {
+        let state_guard = state.core_utils.managers.lock().await;
+        let stronghold_manager = state_guard
+            .stronghold_manager
+            .as_ref()
+            .ok_or(MissingManagerError("stronghold"))?;
+        let identity_manager = state_guard
+            .identity_manager
+            .as_ref()
+            .ok_or(MissingManagerError("identity"))?;
+
+        let mut history = state.history;
+
+        for deferred_credential_record in stronghold_manager.deferred_values().map_err(StrongholdValuesError)? {
+            let pending_credential = deferred_credential_record.pending_credential.clone();
+
+            let credential = match fetch_deferred_credential(&deferred_credential_record).await {
+                Ok(Some(credential)) => credential,
+                Ok(None) => {
+                    info!("credential `{}` is still pending", pending_credential.id);
+                    continue;
+                }
+                // The credential remains pending, so it can be retried at a later point in time.
+                Err(e) => {
+                    warn!(
+                        "failed to retrieve deferred credential `{}`: {:?}",
+                        pending_credential.id, e
+                    );
+                    continue;
+                }
+            };
+
+            // Errors from here on only concern this credential. It remains pending, so the other credentials are still
+            // retrieved.
+            let mut verifiable_credential_record: VerifiableCredentialRecord = match credential.try_into() {
+                Ok(verifiable_credential_record) => verifiable_credential_record,
+                Err(e) => {
+                    warn!(
+                        "failed to read deferred credential `{}`: {:?}",
+                        pending_credential.id, e
+                    );
+                    continue;
+                }
+            };
+
+            // The credential is bound to the holder keys of the connection at the time it was requested.
+            let key_index = pending_credential
+                .connection_id
+                .as_deref()
+                .and_then(|connection_id| state.connections.key_index_by_id(connection_id))
+                .unwrap_or_default();
+            let subject = identity_manager
+                .subject
+                .with_keys(key_index)
+                .await
+                .map_err(KeyActivationError)?;
+            let holder_dids = holder_dids(&state.dids, &subject).await;
+
+            let verification = match verify_credential(
+                &verifiable_credential_record,
+                identity_manager.subject.as_ref(),
+                &holder_dids,
+            )
+            .await
+            {
+                Ok(verification) => verification,
+                Err(e) => {
+                    warn!(
+                        "failed to verify deferred credential `{}`: {:?}",
+                        pending_credential.id, e
+                    );
+                    continue;
+                }
+            };
+
+            // A rejected credential will never become valid, so it is no longer pending either.
+            if verification.is_rejected() {
+                warn!(
+                    "deferred credential `{}` is rejected: {:?}",
+                    pending_credential.id, verification.signature.message
+                );
+                stronghold_manager
+                    .remove_deferred(pending_credential.id.parse().map_err(InvalidUuidError)?)
+                    .map_err(StrongholdDeletionError)?;
+                continue;
+            }
+            verifiable_credential_record.display_credential.metadata.verification = verification;
+            verifiable_credential_record.display_credential.metadata.key_index = key_index;
+
+            verifiable_credential_record
+                .display_credential
+                .issuer_name
+                .clone_from(&pending_credential.issuer_name);
+            verifiable_credential_record
+                .display_credential
+                .connection_id
+                .clone_from(&pending_credential.connection_id);
+
+            // Set the display name of the credential.
+            verifiable_credential_record.display_credential.display_name = get_credential_display_name(
+                &HashMap::from([(
+                    deferred_credential_record.credential_configuration_id.clone(),
+                    deferred_credential_record.credential_configuration,
+                )]),
+                &deferred_credential_record.credential_configuration_id,
+                &verifiable_credential_record,
+            );
+
+            let key: Uuid = verifiable_credential_record
+                .display_credential
+                .id
+                .parse()
+                .map_err(InvalidUuidError)?;
+
+            // Remove the old credential from the stronghold if it exists.
+            stronghold_manager.remove(key).map_err(StrongholdDeletionError)?;
+
+            stronghold_manager
+                .insert(key, json!(verifiable_credential_record).to_string().as_bytes().to_vec())
+                .map_err(StrongholdInsertionError)?;
+
+            stronghold_manager
+                .remove_deferred(pending_credential.id.parse().map_err(InvalidUuidError)?)
+                .map_err(StrongholdDeletionError)?;
+
+            info!("deferred credential `{}` has been issued", pending_credential.id);
+
+            // History
+            history.push(HistoryEvent {
+                connection_name: pending_credential.issuer_name,
+                event_type: EventType::CredentialsAdded,
+                connection_id: pending_credential.connection_id.unwrap_or_default(),
+                date: DateUtils::new_date_string(),
+                credentials: vec![HistoryCredential::from_credential(&verifiable_credential_record)],
+            });
+        }
+
+        let credentials = stronghold_manager
+            .values()
+            .map_err(StrongholdValuesError)?
+            .unwrap_or_default()
+            .into_iter()
+            .map(|verifiable_credential_record| verifiable_credential_record.display_credential)
+            .collect();
+
+        let pending_credentials = stronghold_manager
+            .deferred_values()
+            .map_err(StrongholdValuesError)?
+            .into_iter()
+            .map(|deferred_credential_record| deferred_credential_record.pending_credential)
+            .collect();
+
+        drop(state_guard);
+        return Ok(AppState {
+            credentials,
+            pending_credentials,
+            history,
+            ..state
+        });
+    }
+
+    Ok(state)
+}
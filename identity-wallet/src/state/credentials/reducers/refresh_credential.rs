@@ -0,0 +1,191 @@
+use crate::{
+    error::AppError::{self, *},
+    state::{
+        actions::{listen, Action},
+        core_utils::{
+            history_event::{EventType, HistoryCredential, HistoryEvent},
+            DateUtils,
+        },
+        credentials::{
+            actions::refresh_credential::RefreshCredential,
+            refresh::{fetch_from_refresh_service, refresh_access_token, CredentialIssuance},
+            verification::{holder_dids, verify_credential},
+            VerifiableCredentialRecord,
+        },
+        did::available_did_methods,
+        AppState,
+    },
+};
+
+use log::info;
+use oid4vc::oid4vci::credential_response::CredentialResponseType;
+use serde_json::json;
+
+/// Requests a new credential for the same credential configuration, either with the refresh token that was stored when
+/// the credential was issued or through the `refreshService` of the credential. The new credential replaces the old one
+/// under the same id, keeping its favorite status and display name.
+pub async fn refresh_credential(state: AppState, action: Action) -> Result<AppState, AppError> {
+    if let Some(RefreshCredential { id: credential_id }) = listen::<RefreshCredential>(action) {
+        let state_guard = state.core_utils.managers.lock().await;
+        let stronghold_manager = state_guard
+            .stronghold_manager
+            .as_ref()
+            .ok_or(MissingManagerError("stronghold"))?;
+        let identity_manager = state_guard
+            .identity_manager
+            .as_ref()
+            .ok_or(MissingManagerError("identity"))?;
+
+        let old_record: VerifiableCredentialRecord = stronghold_manager
+            .values()
+            .map_err(StrongholdValuesError)?
+            .unwrap_or_default()
+            .into_iter()
+            .find(|record| record.display_credential.id == credential_id.to_string())
+            .ok_or(StrongholdMissingCredentialError(credential_id))?;
+
+        // The refreshed credential is bound to the same holder keys as the old one, unless those have been replaced by
+        // a rotation, in which case it is bound to the current holder keys.
+        let key_index = match old_record.display_credential.metadata.key_index {
+            key_index if state.holder_keys.is_retired(key_index) => state.holder_keys.active_index,
+            key_index => key_index,
+        };
+        let identity_manager = identity_manager
+            .with_keys(
+                key_index,
+                available_did_methods(&state.profile_settings.preferred_did_methods, &state.dids),
+            )
+            .await?;
+        let wallet = &identity_manager.wallet;
+        let holder_dids = holder_dids(&state.dids, &identity_manager.subject).await;
+
+        let (credential, issuance) = match old_record.issuance.clone() {
+            Some(CredentialIssuance {
+                credential_issuer,
+                credential_configuration_id,
+                refresh_token: Some(refresh_token),
+            }) => {
+                let token_endpoint = wallet
+                    .get_authorization_server_metadata(credential_issuer.clone())
+                    .await
+                    .map_err(GetAuthorizationServerMetadataError)?
+                    .token_endpoint
+                    .ok_or(MissingStateParameterError("token endpoint"))?;
+
+                let token_response = refresh_access_token(token_endpoint, &refresh_token).await?;
+
+                let credential_issuer_metadata = wallet
+                    .get_credential_issuer_metadata(credential_issuer.clone())
+                    .await
+                    .map_err(GetCredentialIssuerMetadataError)?;
+                let credential_configuration = credential_issuer_metadata
+                    .credential_configurations_supported
+                    .get(&credential_configuration_id)
+                    .ok_or(UnknownCredentialConfigurationIdError(
+                        credential_configuration_id.clone(),
+                    ))?;
+
+                let credential = match wallet
+                    .get_credential(
+                        credential_issuer_metadata.clone(),
+                        &token_response,
+                        credential_configuration,
+                    )
+                    .await
+                    .map_err(GetCredentialError)?
+                    .credential
+                {
+                    CredentialResponseType::Immediate { credential, .. } => credential,
+                    CredentialResponseType::Deferred { .. } => return Err(DeferredCredentialRefreshError),
+                };
+
+                // The authorization server may issue a new refresh token, which replaces the old one.
+                let issuance = CredentialIssuance {
+                    credential_issuer,
+                    credential_configuration_id,
+                    refresh_token: token_response.refresh_token.or(Some(refresh_token)),
+                };
+
+                (credential, Some(issuance))
+            }
+            issuance => {
+                let credential = fetch_from_refresh_service(&old_record.display_credential.data)
+                    .await?
+                    .ok_or(CredentialRefreshUnavailableError)?;
+
+                (credential, issuance)
+            }
+        };
+
+        let mut verifiable_credential_record: VerifiableCredentialRecord = credential.try_into()?;
+
+        let verification = verify_credential(
+            &verifiable_credential_record,
+            identity_manager.subject.as_ref(),
+            &holder_dids,
+        )
+        .await?;
+        if verification.is_rejected() {
+            return Err(CredentialVerificationError(
+                verification.signature.message.unwrap_or_default(),
+            ));
+        }
+        // A refreshed credential may not replace a credential of another issuer or another type.
+        if verifiable_credential_record.issuer() != old_record.issuer()
+            || verifiable_credential_record.credential_types() != old_record.credential_types()
+        {
+            return Err(RefreshedCredentialMismatchError);
+        }
+
+        // The refreshed credential takes the place of the old one.
+        let old_display_credential = old_record.display_credential;
+        let display_credential = &mut verifiable_credential_record.display_credential;
+        display_credential.id = old_display_credential.id;
+        display_credential.issuer_name = old_display_credential.issuer_name;
+        display_credential.connection_id = old_display_credential.connection_id;
+        display_credential.display_name = old_display_credential.display_name;
+        display_credential.metadata.is_favorite = old_display_credential.metadata.is_favorite;
+        display_credential.metadata.verification = verification;
+        display_credential.metadata.key_index = key_index;
+        display_credential.metadata.update_expiry_state();
+        display_credential.metadata.update_key_state(&state.holder_keys);
+        verifiable_credential_record.issuance = issuance;
+
+        // Inserting under the same id overwrites the old credential in a single commit.
+        stronghold_manager
+            .insert(
+                credential_id,
+                json!(verifiable_credential_record).to_string().as_bytes().to_vec(),
+            )
+            .map_err(StrongholdInsertionError)?;
+
+        info!("credential `{}` has been refreshed", credential_id);
+
+        let mut credentials = state.credentials;
+        credentials.retain(|credential| credential.id != credential_id.to_string());
+        credentials.push(verifiable_credential_record.display_credential.clone());
+
+        // History
+        let mut history = state.history;
+        history.push(HistoryEvent {
+            connection_name: verifiable_credential_record.display_credential.issuer_name.clone(),
+            event_type: EventType::CredentialsRefreshed,
+            connection_id: verifiable_credential_record
+                .display_credential
+                .connection_id
+                .clone()
+                .unwrap_or_default(),
+            date: DateUtils::new_date_string(),
+            credentials: vec![HistoryCredential::from_credential(&verifiable_credential_record)],
+        });
+
+        drop(state_guard);
+        return Ok(AppState {
+            credentials,
+            history,
+            ..state
+        });
+    }
+
+    Ok(state)
+}
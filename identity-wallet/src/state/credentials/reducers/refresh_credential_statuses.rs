@@ -0,0 +1,45 @@
+use crate::{
+    error::AppError::{self, *},
+    state::{
+        actions::{listen, Action},
+        credentials::{actions::refresh_credential_statuses::RefreshCredentialStatuses, status::update_statuses},
+        AppState,
+    },
+};
+
+use log::info;
+
+/// Checks the status of all credentials and stores the results in their metadata.
+pub async fn refresh_credential_statuses(state: AppState, action: Action) -> Result<AppState, AppError> {
+    if listen::<RefreshCredentialStatuses>(action).is_some() {
+        let state_guard = state.core_utils.managers.lock().await;
+        let stronghold_manager = state_guard
+            .stronghold_manager
+            .as_ref()
+            .ok_or(MissingManagerError("stronghold"))?;
+        let identity_manager = state_guard
+            .identity_manager
+            .as_ref()
+            .ok_or(MissingManagerError("identity"))?;
+
+        let credentials = update_statuses(
+            stronghold_manager,
+            stronghold_manager
+                .values()
+                .map_err(StrongholdValuesError)?
+                .unwrap_or_default(),
+            identity_manager.subject.as_ref(),
+        )
+        .await?
+        .into_iter()
+        .map(|verifiable_credential_record| verifiable_credential_record.display_credential)
+        .collect();
+
+        info!("credential statuses refreshed");
+
+        drop(state_guard);
+        return Ok(AppState { credentials, ..state });
+    }
+
+    Ok(state)
+}
@@ -3,14 +3,20 @@ use crate::{
     persistence::{hash, persist_asset},
     state::{
         actions::{listen, Action},
+        connections::{pairwise::activate_connection_keys, trust_registry::TrustedRole},
         core_utils::{
             history_event::{EventType, HistoryCredential, HistoryEvent},
-            CoreUtils,
+            CoreUtils, DateUtils,
         },
         credentials::{
-            actions::credential_offers_selected::CredentialOffersSelected, DisplayCredential,
-            VerifiableCredentialRecord,
+            actions::credential_offers_selected::CredentialOffersSelected,
+            authorization_code_flow::AuthorizationCodeFlow,
+            deferred_credential::{DeferredCredentialRecord, PendingCredential},
+            refresh::CredentialIssuance,
+            verification::{holder_dids, verify_credential},
+            DisplayCredential, VerifiableCredentialRecord,
         },
+        did::{available_did_methods, validate_domain_linkage::DomainValidation},
         user_prompt::CurrentUserPrompt,
         AppState,
     },
@@ -19,7 +25,7 @@ use crate::{
 use log::info;
 use oid4vc::oid4vci::{
     credential_issuer::credential_configurations_supported::CredentialConfigurationsSupportedObject,
-    credential_offer::Grants, credential_response::CredentialResponseType, token_request::TokenRequest,
+    credential_response::CredentialResponseType, token_request::TokenRequest, token_response::TokenResponse,
 };
 use serde_json::json;
 use std::collections::HashMap;
@@ -28,15 +34,12 @@ use uuid::Uuid;
 pub async fn send_credential_request(state: AppState, action: Action) -> Result<AppState, AppError> {
     info!("send_credential_request");
 
-    if let Some(credential_configuration_ids) =
-        listen::<CredentialOffersSelected>(action).map(|payload| payload.credential_configuration_ids)
+    if let Some(CredentialOffersSelected {
+        credential_configuration_ids,
+        tx_code,
+    }) = listen::<CredentialOffersSelected>(action)
     {
         let state_guard = state.core_utils.managers.lock().await;
-        let stronghold_manager = state_guard
-            .stronghold_manager
-            .as_ref()
-            .ok_or(MissingManagerError("stronghold"))?;
-
         let wallet = &state_guard
             .identity_manager
             .as_ref()
@@ -50,12 +53,24 @@ pub async fn send_credential_request(state: AppState, action: Action) -> Result<
 
         info!("current_user_prompt: {:?}", current_user_prompt);
 
-        let credential_offer = state.core_utils.active_credential_offer.unwrap();
-        let logo_uri = match current_user_prompt {
-            CurrentUserPrompt::CredentialOffer { logo_uri, .. } => logo_uri,
-            _ => unreachable!(),
+        let credential_offer = state
+            .core_utils
+            .active_credential_offer
+            .clone()
+            .ok_or(MissingStateParameterError("active credential offer"))?;
+        let (issuer_name, logo_uri, expected_tx_code) = match current_user_prompt {
+            CurrentUserPrompt::CredentialOffer {
+                issuer_name,
+                logo_uri,
+                tx_code,
+                ..
+            } => (issuer_name, logo_uri, tx_code),
+            _ => return Err(MissingStateParameterError("credential offer prompt")),
         };
 
+        // The domain validation that has been shown to the user is stored on the connection.
+        let domain_validation = state.core_utils.active_domain_validation.clone().unwrap_or_default();
+
         // The credential offer contains a credential issuer url.
         let credential_issuer_url = credential_offer.credential_issuer.clone();
 
@@ -69,222 +84,462 @@ pub async fn send_credential_request(state: AppState, action: Action) -> Result<
 
         info!("authorization server metadata: {:?}", authorization_server_metadata);
 
-        // Get the credential issuer metadata.
-        let credential_issuer_metadata = wallet
-            .get_credential_issuer_metadata(credential_issuer_url.clone())
-            .await
-            .map_err(GetCredentialIssuerMetadataError)?;
-
-        info!("credential issuer metadata: {:?}", credential_issuer_metadata);
-
-        // Get the credential issuer display.
-        let display = credential_issuer_metadata
-            .display
+        let pre_authorized_code = credential_offer
+            .grants
             .as_ref()
-            .and_then(|display| display.first().cloned());
-
-        // Get the connection url from the credential issuer url host (or use the credential issuer url if it does not
-        // contain a host).
-        let connection_url = credential_issuer_url
-            .host_str()
-            .unwrap_or(credential_issuer_url.as_str());
-
-        // Get the credential issuer name or use the credential issuer url.
-        let issuer_name = display
-            .map(|display| {
-                let issuer_name = display["name"]
-                    .as_str()
-                    .map(ToString::to_string)
-                    // TODO(ngdil): Remove this fallback.
-                    .or_else(|| display["client_name"].as_str().map(ToString::to_string))
-                    .unwrap_or(connection_url.to_string());
-                issuer_name
-            })
-            .unwrap_or(connection_url.to_string());
-
-        let mut credential_configurations_supported =
-            credential_issuer_metadata.credential_configurations_supported.clone();
-
-        // Create or update the connection.
-        let previously_connected = state.connections.contains(connection_url, &issuer_name);
-        let mut connections = state.connections;
-        let connection = connections.update_or_insert(connection_url, &issuer_name, None);
+            .and_then(|grants| grants.pre_authorized_code.clone());
+
+        // When the credential offer does not contain a `pre-authorized_code` grant, the user first needs to
+        // authenticate at the authorization server (Authorization Code Flow).
+        let Some(pre_authorized_code) = pre_authorized_code else {
+            let authorization_endpoint = authorization_server_metadata
+                .authorization_endpoint
+                .ok_or(MissingAuthorizationEndpointError)?;
+
+            let issuer_state = credential_offer
+                .grants
+                .as_ref()
+                .and_then(|grants| grants.authorization_code.as_ref())
+                .and_then(|authorization_code| authorization_code.issuer_state.clone());
+
+            // The DID of the preferred DID method is used as the `client_id`.
+            let client_id = state
+                .profile_settings
+                .preferred_did_methods
+                .first()
+                .and_then(|did_method| state.dids.get(did_method))
+                .cloned()
+                .ok_or(MissingStateParameterError("DID"))?;
+
+            let authorization_code_flow = AuthorizationCodeFlow {
+                domain_validation,
+                ..AuthorizationCodeFlow::new(credential_configuration_ids, logo_uri.clone())
+            };
+            let authorization_url =
+                authorization_code_flow.authorization_url(authorization_endpoint, &client_id, issuer_state.as_deref());
+
+            info!("authorization_url: {}", authorization_url);
+
+            drop(state_guard);
+            return Ok(AppState {
+                current_user_prompt: Some(CurrentUserPrompt::Authorize {
+                    issuer_name,
+                    logo_uri,
+                    authorization_url: authorization_url.to_string(),
+                }),
+                core_utils: CoreUtils {
+                    active_authorization_code_flow: Some(authorization_code_flow),
+                    ..state.core_utils
+                },
+                ..state
+            });
+        };
+
+        // When the credential issuer requires a transaction code, the user must have entered a valid one.
+        let tx_code = match (expected_tx_code, tx_code) {
+            (Some(expected_tx_code), Some(tx_code)) => {
+                if !expected_tx_code.is_valid(&tx_code) {
+                    return Err(InvalidTransactionCodeError(anyhow::anyhow!(
+                        "the transaction code does not match the expected input mode or length"
+                    )));
+                }
+                Some(tx_code)
+            }
+            (Some(_), None) => return Err(MissingTransactionCodeError),
+            (None, _) => None,
+        };
 
         // Create a token request with grant_type `pre_authorized_code`.
-        let token_request = match credential_offer.grants.clone() {
-            Some(Grants {
-                pre_authorized_code, ..
-            }) => TokenRequest::PreAuthorizedCode {
-                pre_authorized_code: pre_authorized_code.unwrap().pre_authorized_code,
-                tx_code: None,
-            },
-            None => unreachable!(),
+        let token_request = TokenRequest::PreAuthorizedCode {
+            pre_authorized_code: pre_authorized_code.pre_authorized_code,
+            tx_code: tx_code.clone(),
         };
 
         info!("token_request: {:?}", token_request);
 
-        // Get an access token.
-        let token_response = wallet
-            .get_access_token(authorization_server_metadata.token_endpoint.unwrap(), token_request)
-            .await
-            .map_err(GetAccessTokenError)?;
+        // Get an access token. Since the state is not updated on errors, the offer remains active so the user can try
+        // again after entering an incorrect transaction code.
+        let token_endpoint = authorization_server_metadata
+            .token_endpoint
+            .ok_or(MissingStateParameterError("token endpoint"))?;
+        let token_response = get_access_token(token_endpoint, &token_request).await?;
 
         info!("token_response: {:?}", token_response);
 
-        credential_configurations_supported.retain(|credential_configuration_id, _| {
-            credential_configuration_ids.contains(credential_configuration_id)
-        });
+        drop(state_guard);
+        return receive_credentials(
+            state,
+            credential_configuration_ids,
+            token_response,
+            logo_uri,
+            domain_validation,
+        )
+        .await;
+    }
 
-        let credentials: Vec<(String, serde_json::Value)> = match credential_configuration_ids.len() {
-            0 => vec![],
-            1 => {
-                let credential_configuration_id = credential_configuration_ids[0].clone();
-
-                let credential_configuration = credential_configurations_supported
-                    .get(&credential_configuration_id)
-                    .ok_or(UnknownCredentialConfigurationIdError(
-                        credential_configuration_id.clone(),
-                    ))?;
-
-                // Get the credential.
-                let credential_response = wallet
-                    .get_credential(credential_issuer_metadata, &token_response, credential_configuration)
-                    .await
-                    .map_err(GetCredentialError)?;
-
-                let credential = match credential_response.credential {
-                    CredentialResponseType::Immediate { credential, .. } => credential,
-                    _ => panic!("Credential was not a jwt_vc_json."),
-                };
-
-                vec![(credential_configuration_id, credential)]
+    Ok(state)
+}
+
+/// Exchanges the pre-authorized code for an access token at the token endpoint. Unlike `Wallet::get_access_token`,
+/// this keeps the error code of a rejected token request, since only an `invalid_grant` error means that the
+/// transaction code is incorrect.
+/// See: https://openid.net/specs/openid-4-verifiable-credential-issuance-1_0.html#name-token-error-response
+async fn get_access_token(token_endpoint: url::Url, token_request: &TokenRequest) -> Result<TokenResponse, AppError> {
+    let response = reqwest::Client::new()
+        .post(token_endpoint)
+        .form(token_request)
+        .send()
+        .await
+        .map_err(|e| GetAccessTokenError(e.into()))?;
+
+    let status = response.status();
+    let body = response
+        .json::<serde_json::Value>()
+        .await
+        .map_err(|e| GetAccessTokenError(e.into()))?;
+
+    if status.is_success() {
+        return serde_json::from_value(body).map_err(|e| GetAccessTokenError(e.into()));
+    }
+
+    let error = anyhow::anyhow!(
+        "the authorization server responded with error: {}",
+        body["error"].as_str().unwrap_or("unknown")
+    );
+    match (body["error"].as_str(), token_request) {
+        (Some("invalid_grant"), TokenRequest::PreAuthorizedCode { tx_code: Some(_), .. }) => {
+            Err(InvalidTransactionCodeError(error))
+        }
+        _ => Err(GetAccessTokenError(error)),
+    }
+}
+
+/// Uses the access token to retrieve the credentials from the credential issuer, stores them in the Stronghold and
+/// adds the corresponding connection and history events to the state. This is the final step of both the
+/// Pre-Authorized Code Flow and the Authorization Code Flow.
+pub(crate) async fn receive_credentials(
+    state: AppState,
+    credential_configuration_ids: Vec<String>,
+    token_response: TokenResponse,
+    logo_uri: Option<String>,
+    domain_validation: DomainValidation,
+) -> Result<AppState, AppError> {
+    let state_guard = state.core_utils.managers.lock().await;
+    let stronghold_manager = state_guard
+        .stronghold_manager
+        .as_ref()
+        .ok_or(MissingManagerError("stronghold"))?;
+
+    let identity_manager = state_guard
+        .identity_manager
+        .as_ref()
+        .ok_or(MissingManagerError("identity"))?;
+    let wallet = &identity_manager.wallet;
+
+    let credential_issuer_url = state
+        .core_utils
+        .active_credential_offer
+        .as_ref()
+        .ok_or(MissingStateParameterError("active credential offer"))?
+        .credential_issuer
+        .clone();
+
+    // Get the credential issuer metadata.
+    let credential_issuer_metadata = wallet
+        .get_credential_issuer_metadata(credential_issuer_url.clone())
+        .await
+        .map_err(GetCredentialIssuerMetadataError)?;
+
+    info!("credential issuer metadata: {:?}", credential_issuer_metadata);
+
+    // Get the credential issuer display.
+    let display = credential_issuer_metadata
+        .display
+        .as_ref()
+        .and_then(|display| display.first().cloned());
+
+    // Get the connection url from the credential issuer url host (or use the credential issuer url if it does not
+    // contain a host).
+    let connection_url = credential_issuer_url
+        .host_str()
+        .unwrap_or(credential_issuer_url.as_str());
+
+    // Get the credential issuer name or use the credential issuer url.
+    let issuer_name = display
+        .map(|display| {
+            let issuer_name = display["name"]
+                .as_str()
+                .map(ToString::to_string)
+                // TODO(ngdil): Remove this fallback.
+                .or_else(|| display["client_name"].as_str().map(ToString::to_string))
+                .unwrap_or(connection_url.to_string());
+            issuer_name
+        })
+        .unwrap_or(connection_url.to_string());
+
+    let mut credential_configurations_supported =
+        credential_issuer_metadata.credential_configurations_supported.clone();
+
+    // The credentials are bound to the pairwise DID of the connection.
+    let (key_index, identity_manager) = activate_connection_keys(
+        &state.connections,
+        connection_url,
+        &issuer_name,
+        identity_manager,
+        &state.holder_keys,
+        available_did_methods(&state.profile_settings.preferred_did_methods, &state.dids),
+    )
+    .await?;
+    let wallet = &identity_manager.wallet;
+    let holder_dids = holder_dids(&state.dids, &identity_manager.subject).await;
+
+    // Create or update the connection.
+    let previously_connected = state.connections.contains(connection_url, &issuer_name);
+    let mut connections = state.connections;
+    let connection = connections.update_or_insert(connection_url, &issuer_name, None, TrustedRole::Issuer);
+    connection.domain_validation = domain_validation;
+    connection.key_index = key_index;
+
+    credential_configurations_supported
+        .retain(|credential_configuration_id, _| credential_configuration_ids.contains(credential_configuration_id));
+
+    // The credential issuer can either issue a credential immediately or return a `transaction_id` that can be used to
+    // retrieve the credential at a later point in time (Deferred Credential Issuance).
+    let deferred_credential_endpoint = credential_issuer_metadata.deferred_credential_endpoint.clone();
+    let mut credentials: Vec<(String, serde_json::Value)> = vec![];
+    let mut transaction_ids: Vec<(String, String)> = vec![];
+
+    match credential_configuration_ids.len() {
+        0 => {}
+        1 => {
+            let credential_configuration_id = credential_configuration_ids[0].clone();
+
+            let credential_configuration = credential_configurations_supported
+                .get(&credential_configuration_id)
+                .ok_or(UnknownCredentialConfigurationIdError(
+                    credential_configuration_id.clone(),
+                ))?;
+
+            // Get the credential.
+            let credential_response = wallet
+                .get_credential(credential_issuer_metadata, &token_response, credential_configuration)
+                .await
+                .map_err(GetCredentialError)?;
+
+            match credential_response.credential {
+                CredentialResponseType::Immediate { credential, .. } => {
+                    credentials.push((credential_configuration_id, credential))
+                }
+                CredentialResponseType::Deferred { transaction_id } => {
+                    transaction_ids.push((credential_configuration_id, transaction_id))
+                }
             }
-            _batch => {
-                let (credential_configuration_ids, credential_configurations): (Vec<_>, Vec<_>) =
-                    credential_configurations_supported.clone().into_iter().unzip();
-
-                let batch_credential_response = wallet
-                    .get_batch_credential(credential_issuer_metadata, &token_response, &credential_configurations)
-                    .await
-                    .map_err(GetBatchCredentialError)?;
-
-                credential_configuration_ids
-                    .into_iter()
-                    .zip(batch_credential_response.credential_responses.into_iter())
-                    .filter_map(
-                        |(credential_configuration_id, credential_response)| match credential_response {
-                            CredentialResponseType::Immediate { credential, .. } => {
-                                Some((credential_configuration_id, credential))
-                            }
-                            // TODO: add support for deffered credentials.
-                            CredentialResponseType::Deferred { .. } => None,
-                        },
-                    )
-                    .collect()
+        }
+        _batch => {
+            let (credential_configuration_ids, credential_configurations): (Vec<_>, Vec<_>) =
+                credential_configurations_supported.clone().into_iter().unzip();
+
+            let batch_credential_response = wallet
+                .get_batch_credential(credential_issuer_metadata, &token_response, &credential_configurations)
+                .await
+                .map_err(GetBatchCredentialError)?;
+
+            for (credential_configuration_id, credential_response) in credential_configuration_ids
+                .into_iter()
+                .zip(batch_credential_response.credential_responses.into_iter())
+            {
+                match credential_response {
+                    CredentialResponseType::Immediate { credential, .. } => {
+                        credentials.push((credential_configuration_id, credential))
+                    }
+                    CredentialResponseType::Deferred { transaction_id } => {
+                        transaction_ids.push((credential_configuration_id, transaction_id))
+                    }
+                }
             }
-        };
+        }
+    };
 
-        info!("credentials: {:?}", credentials);
+    info!("credentials: {:?}", credentials);
 
-        let mut history_credentials = vec![];
+    // Verify all credentials before anything is stored, so a rejected credential does not leave the others behind.
+    let mut verifiable_credential_records = vec![];
+    for (credential_configuration_id, credential) in credentials.into_iter() {
+        let mut verifiable_credential_record: VerifiableCredentialRecord = credential.try_into()?;
 
-        for (credential_configuration_id, credential) in credentials.into_iter() {
-            let mut verifiable_credential_record: VerifiableCredentialRecord = credential.try_into()?;
-            verifiable_credential_record
-                .display_credential
-                .issuer_name
-                .clone_from(&issuer_name);
-            verifiable_credential_record.display_credential.connection_id = Some(connection.id.clone());
+        let verification = verify_credential(
+            &verifiable_credential_record,
+            identity_manager.subject.as_ref(),
+            &holder_dids,
+        )
+        .await?;
+        info!("credential verification: {:?}", verification);
+
+        if verification.is_rejected() {
+            return Err(CredentialVerificationError(
+                verification.signature.message.unwrap_or_default(),
+            ));
+        }
+        verifiable_credential_record.display_credential.metadata.verification = verification;
 
-            // Set the display name of the credential.
-            verifiable_credential_record.display_credential.display_name = get_credential_display_name(
-                &credential_configurations_supported,
-                &credential_configuration_id,
-                &verifiable_credential_record,
-            );
+        verifiable_credential_records.push((credential_configuration_id, verifiable_credential_record));
+    }
 
-            let key: Uuid = verifiable_credential_record
-                .display_credential
-                .id
-                .parse()
-                .expect("invalid uuid");
+    info!("transaction_ids of deferred credentials: {:?}", transaction_ids);
 
-            info!("generated hash-key: {:?}", key);
+    let has_deferred_credentials = !transaction_ids.is_empty();
 
-            persist_asset(
-                format!("credential_{credential_configuration_id}").as_str(),
-                key.to_string().as_str(),
-            )
-            .ok();
+    // Store the deferred credentials so they can be retrieved later on.
+    for (credential_configuration_id, transaction_id) in transaction_ids.into_iter() {
+        let credential_configuration = credential_configurations_supported
+            .get(&credential_configuration_id)
+            .cloned()
+            .ok_or(UnknownCredentialConfigurationIdError(
+                credential_configuration_id.clone(),
+            ))?;
 
-            // Remove the old credential from the stronghold if it exists.
-            stronghold_manager.remove(key).map_err(StrongholdDeletionError)?;
+        let display_name = credential_configuration
+            .display
+            .first()
+            .and_then(|display| display["name"].as_str())
+            .map(ToString::to_string)
+            .unwrap_or(credential_configuration_id.clone());
+
+        let key = Uuid::new_v4();
+
+        let deferred_credential_record = DeferredCredentialRecord {
+            transaction_id,
+            deferred_credential_endpoint: deferred_credential_endpoint
+                .clone()
+                .ok_or(MissingDeferredCredentialEndpointError)?,
+            access_token: token_response.access_token.clone(),
+            credential_configuration_id,
+            credential_configuration,
+            pending_credential: PendingCredential {
+                id: key.to_string(),
+                issuer_name: issuer_name.clone(),
+                display_name,
+                connection_id: Some(connection.id.clone()),
+                date_requested: DateUtils::new_date_string(),
+            },
+        };
 
-            stronghold_manager
-                .insert(key, json!(verifiable_credential_record).to_string().as_bytes().to_vec())
-                .map_err(StrongholdInsertionError)?;
+        stronghold_manager
+            .insert_deferred(key, json!(deferred_credential_record).to_string().as_bytes().to_vec())
+            .map_err(StrongholdInsertionError)?;
+    }
 
-            // Add history event
-            history_credentials.push(HistoryCredential::from_credential(&verifiable_credential_record));
-        }
+    let mut history_credentials = vec![];
+
+    for (credential_configuration_id, mut verifiable_credential_record) in verifiable_credential_records.into_iter() {
+        verifiable_credential_record
+            .display_credential
+            .issuer_name
+            .clone_from(&issuer_name);
+        verifiable_credential_record.display_credential.connection_id = Some(connection.id.clone());
+        verifiable_credential_record.display_credential.metadata.key_index = key_index;
+        verifiable_credential_record.issuance = Some(CredentialIssuance {
+            credential_issuer: credential_issuer_url.clone(),
+            credential_configuration_id: credential_configuration_id.clone(),
+            refresh_token: token_response.refresh_token.clone(),
+        });
 
-        let credentials: Vec<DisplayCredential> = stronghold_manager
-            .values()
-            .map_err(StrongholdValuesError)?
-            .unwrap()
-            .into_iter()
-            .map(|verifiable_credential_record| verifiable_credential_record.display_credential)
-            .collect();
-
-        let file_name = match logo_uri {
-            Some(logo_uri) => hash(logo_uri.as_str()),
-            None => "_".to_string(),
-        };
-        persist_asset(&file_name, &connection.id).ok();
-
-        // History
-        let mut history = state.history;
-        if !history_credentials.is_empty() {
-            // Only add a `ConnectionAdded` event if the connection was not previously connected.
-            if !previously_connected {
-                history.push(HistoryEvent {
-                    connection_name: connection.name.clone(),
-                    event_type: EventType::ConnectionAdded,
-                    connection_id: connection.id.clone(),
-                    date: connection.last_interacted.clone(),
-                    credentials: vec![],
-                });
-            }
-            history.push(HistoryEvent {
-                connection_name: connection.name.clone(),
-                event_type: EventType::CredentialsAdded,
-                connection_id: connection.id.clone(),
-                date: connection.last_interacted.clone(),
-                credentials: history_credentials,
-            });
-        }
+        // Set the display name of the credential.
+        verifiable_credential_record.display_credential.display_name = get_credential_display_name(
+            &credential_configurations_supported,
+            &credential_configuration_id,
+            &verifiable_credential_record,
+        );
 
-        drop(state_guard);
-        return Ok(AppState {
-            connections,
-            credentials,
-            current_user_prompt: Some(CurrentUserPrompt::Redirect {
-                target: "me".to_string(),
-            }),
-            history,
-            core_utils: CoreUtils {
-                active_credential_offer: None,
-                ..state.core_utils
-            },
-            ..state
+        let key: Uuid = verifiable_credential_record
+            .display_credential
+            .id
+            .parse()
+            .expect("invalid uuid");
+
+        info!("generated hash-key: {:?}", key);
+
+        persist_asset(
+            format!("credential_{credential_configuration_id}").as_str(),
+            key.to_string().as_str(),
+        )
+        .ok();
+
+        // Remove the old credential from the stronghold if it exists.
+        stronghold_manager.remove(key).map_err(StrongholdDeletionError)?;
+
+        stronghold_manager
+            .insert(key, json!(verifiable_credential_record).to_string().as_bytes().to_vec())
+            .map_err(StrongholdInsertionError)?;
+
+        // Add history event
+        history_credentials.push(HistoryCredential::from_credential(&verifiable_credential_record));
+    }
+
+    let credentials: Vec<DisplayCredential> = stronghold_manager
+        .values()
+        .map_err(StrongholdValuesError)?
+        .unwrap()
+        .into_iter()
+        .map(|verifiable_credential_record| verifiable_credential_record.display_credential)
+        .collect();
+
+    let pending_credentials: Vec<PendingCredential> = stronghold_manager
+        .deferred_values()
+        .map_err(StrongholdValuesError)?
+        .into_iter()
+        .map(|deferred_credential_record| deferred_credential_record.pending_credential)
+        .collect();
+
+    let file_name = match logo_uri {
+        Some(logo_uri) => hash(logo_uri.as_str()),
+        None => "_".to_string(),
+    };
+    persist_asset(&file_name, &connection.id).ok();
+
+    // History
+    let mut history = state.history;
+    // Only add a `ConnectionAdded` event if the connection was not previously connected.
+    if !previously_connected && (!history_credentials.is_empty() || has_deferred_credentials) {
+        history.push(HistoryEvent {
+            connection_name: connection.name.clone(),
+            event_type: EventType::ConnectionAdded,
+            connection_id: connection.id.clone(),
+            date: connection.last_interacted.clone(),
+            credentials: vec![],
+        });
+    }
+    if !history_credentials.is_empty() {
+        history.push(HistoryEvent {
+            connection_name: connection.name.clone(),
+            event_type: EventType::CredentialsAdded,
+            connection_id: connection.id.clone(),
+            date: connection.last_interacted.clone(),
+            credentials: history_credentials,
         });
     }
 
-    Ok(state)
+    drop(state_guard);
+    Ok(AppState {
+        connections,
+        credentials,
+        pending_credentials,
+        current_user_prompt: Some(CurrentUserPrompt::Redirect {
+            target: "me".to_string(),
+        }),
+        history,
+        core_utils: CoreUtils {
+            active_credential_offer: None,
+            active_authorization_code_flow: None,
+            active_domain_validation: None,
+            ..state.core_utils
+        },
+        ..state
+    })
 }
 
 /// Helper function to get the display name of a credential.
-fn get_credential_display_name(
+pub(crate) fn get_credential_display_name(
     credential_configurations_supported: &HashMap<String, CredentialConfigurationsSupportedObject>,
     credential_configuration_id: &str,
     verifiable_credential_record: &VerifiableCredentialRecord,
@@ -305,6 +560,10 @@ fn get_credential_display_name(
                         .and_then(|types| types.last())
                         .and_then(|last_type| last_type.as_str()),
                 )
+                // Or, if the credential is an SD-JWT VC, use its `vct` property as the name of the credential.
+                .or(verifiable_credential_record.display_credential.data["vct"].as_str())
+                // Or, if the credential is an mdoc, use its `docType` as the name of the credential.
+                .or(verifiable_credential_record.display_credential.data["docType"].as_str())
         })
         .map(ToString::to_string)
         // Fallback to `Credential` if the credential is not a valid W3C Verifiable Credential.
@@ -315,6 +574,44 @@ fn get_credential_display_name(
 mod tests {
     use super::*;
 
+    use wiremock::{
+        matchers::{body_string_contains, method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    #[tokio::test]
+    async fn only_invalid_grant_is_an_invalid_transaction_code() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/token"))
+            .and(body_string_contains("tx_code=493536"))
+            .respond_with(ResponseTemplate::new(400).set_body_json(json!({ "error": "invalid_grant" })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/token"))
+            .and(body_string_contains("tx_code=123456"))
+            .respond_with(ResponseTemplate::new(503).set_body_json(json!({ "error": "temporarily_unavailable" })))
+            .mount(&mock_server)
+            .await;
+
+        let token_endpoint: url::Url = format!("{}/token", mock_server.uri()).parse().unwrap();
+        let token_request = |tx_code: &str| TokenRequest::PreAuthorizedCode {
+            pre_authorized_code: "SplxlOBeZQQYbYS6WxSbIA".to_string(),
+            tx_code: Some(tx_code.to_string()),
+        };
+
+        assert!(matches!(
+            get_access_token(token_endpoint.clone(), &token_request("493536")).await,
+            Err(InvalidTransactionCodeError(_))
+        ));
+        assert!(matches!(
+            get_access_token(token_endpoint, &token_request("123456")).await,
+            Err(GetAccessTokenError(_))
+        ));
+    }
+
     #[test]
     fn display_name_is_successfully_read_from_credential_configuration() {
         let credential_configuration_id = "credential_configuration_id";
@@ -338,6 +635,7 @@ mod tests {
                 }),
                 ..Default::default()
             },
+            issuance: None,
         };
 
         // Get the display name of the credential.
@@ -373,6 +671,7 @@ mod tests {
                 }),
                 ..Default::default()
             },
+            issuance: None,
         };
 
         // Get the display name of the credential.
@@ -409,6 +708,7 @@ mod tests {
                 }),
                 ..Default::default()
             },
+            issuance: None,
         };
 
         // Get the display name of the credential.
@@ -0,0 +1,142 @@
+use crate::error::AppError::{self, *};
+
+use log::info;
+use oid4vc::oid4vci::token_response::TokenResponse;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+/// Contains what is needed to request a new credential for the same credential configuration from the credential
+/// issuer. Since it can contain a refresh token, it is only stored in the Stronghold.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct CredentialIssuance {
+    pub credential_issuer: url::Url,
+    pub credential_configuration_id: String,
+    pub refresh_token: Option<String>,
+}
+
+/// Exchanges the refresh token for a new access token at the token endpoint of the authorization server.
+/// See: https://openid.net/specs/openid-4-verifiable-credential-issuance-1_0.html#name-token-endpoint and
+/// https://www.rfc-editor.org/rfc/rfc6749#section-6
+pub async fn refresh_access_token(token_endpoint: url::Url, refresh_token: &str) -> Result<TokenResponse, AppError> {
+    info!("refreshing access token at: {}", token_endpoint);
+
+    reqwest::Client::new()
+        .post(token_endpoint)
+        .form(&[("grant_type", "refresh_token"), ("refresh_token", refresh_token)])
+        .send()
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .map_err(|e| GetAccessTokenError(e.into()))?
+        .json()
+        .await
+        .map_err(|e| GetAccessTokenError(e.into()))
+}
+
+/// Requests a new credential from the `refreshService` of the credential, if it has one. Only refresh services of type
+/// `VerifiableCredentialRefreshService2021` that respond with the refreshed credential directly are supported.
+/// See: https://w3c-ccg.github.io/vc-refresh-2021/
+pub async fn fetch_from_refresh_service(credential: &Value) -> Result<Option<Value>, AppError> {
+    let refresh_services = match &credential["refreshService"] {
+        Value::Array(refresh_services) => refresh_services.clone(),
+        refresh_service @ Value::Object(_) => vec![refresh_service.clone()],
+        _ => return Ok(None),
+    };
+
+    let Some(url) = refresh_services.iter().find_map(|refresh_service| {
+        (refresh_service["type"] == "VerifiableCredentialRefreshService2021")
+            .then(|| refresh_service["url"].as_str().or(refresh_service["id"].as_str()))
+            .flatten()
+    }) else {
+        return Err(UnsupportedRefreshServiceError);
+    };
+
+    info!("requesting refreshed credential from: {}", url);
+
+    let response: Value = reqwest::Client::new()
+        .post(url)
+        .json(&json!({}))
+        .send()
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .map_err(|e| GetCredentialError(e.into()))?
+        .json()
+        .await
+        .map_err(|e| GetCredentialError(e.into()))?;
+
+    // A refresh service that requires the holder to authenticate responds with a `verifiablePresentationRequest`
+    // instead, which is not supported.
+    match &response["verifiablePresentation"]["verifiableCredential"] {
+        Value::Array(credentials) => credentials.first().cloned().map(Some),
+        credential @ Value::Object(_) => Some(Some(credential.clone())),
+        _ => None,
+    }
+    .ok_or(UnsupportedRefreshServiceError)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use wiremock::{
+        matchers::{body_string_contains, method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    #[tokio::test]
+    async fn refresh_token_is_exchanged_for_access_token() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/token"))
+            .and(body_string_contains("grant_type=refresh_token"))
+            .and(body_string_contains("refresh_token=tGzv3JOkF0XG5Qx2TlKWIA"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "access_token": "czZCaGRSa3F0MzpnWDFmQmF0M2JW",
+                "token_type": "bearer",
+                "refresh_token": "8xLOxBtZp8"
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let token_response = refresh_access_token(
+            format!("{}/token", mock_server.uri()).parse().unwrap(),
+            "tGzv3JOkF0XG5Qx2TlKWIA",
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(token_response.access_token, "czZCaGRSa3F0MzpnWDFmQmF0M2JW");
+        assert_eq!(token_response.refresh_token, Some("8xLOxBtZp8".to_string()));
+    }
+
+    #[tokio::test]
+    async fn refreshed_credential_is_returned_by_refresh_service() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/refresh/3732"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "verifiablePresentation": {
+                    "type": "VerifiablePresentation",
+                    "verifiableCredential": [{ "type": ["VerifiableCredential"] }]
+                }
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let credential = json!({
+            "refreshService": {
+                "type": "VerifiableCredentialRefreshService2021",
+                "url": format!("{}/refresh/3732", mock_server.uri())
+            }
+        });
+
+        assert_eq!(
+            fetch_from_refresh_service(&credential).await.unwrap(),
+            Some(json!({ "type": ["VerifiableCredential"] }))
+        );
+        assert_eq!(fetch_from_refresh_service(&json!({})).await.unwrap(), None);
+    }
+}
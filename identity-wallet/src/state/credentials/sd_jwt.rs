@@ -0,0 +1,518 @@
+use crate::{
+    error::AppError::{self, *},
+    state::core_utils::helpers::get_unverified_jwt_claims,
+};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use jsonwebtoken::Algorithm;
+use oid4vc::oid4vc_core::Sign;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+
+/// The character separating the Issuer-signed JWT, the disclosures and the Key Binding JWT of an SD-JWT.
+const SD_JWT_SEPARATOR: char = '~';
+
+/// A single disclosure of an SD-JWT. Disclosures of array elements do not have a claim name.
+/// See: https://www.ietf.org/archive/id/draft-ietf-oauth-selective-disclosure-jwt-08.html#name-disclosures
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Disclosure {
+    pub encoded: String,
+    pub claim_name: Option<String>,
+    pub claim_value: Value,
+}
+
+impl Disclosure {
+    pub fn parse(encoded: &str) -> Result<Self, AppError> {
+        let decoded: Vec<Value> = URL_SAFE_NO_PAD
+            .decode(encoded)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .ok_or(InvalidSdJwtError("invalid disclosure"))?;
+
+        let (claim_name, claim_value) = match decoded.as_slice() {
+            [_salt, Value::String(claim_name), claim_value] => (Some(claim_name.clone()), claim_value.clone()),
+            [_salt, claim_value] => (None, claim_value.clone()),
+            _ => return Err(InvalidSdJwtError("invalid disclosure")),
+        };
+
+        Ok(Self {
+            encoded: encoded.to_string(),
+            claim_name,
+            claim_value,
+        })
+    }
+
+    /// Returns the digest with which the disclosure is referenced from within the Issuer-signed JWT. Only `sha-256` is
+    /// supported, which is the default `_sd_alg`.
+    pub fn digest(&self) -> String {
+        URL_SAFE_NO_PAD.encode(Sha256::digest(self.encoded.as_bytes()))
+    }
+}
+
+/// An SD-JWT (`vc+sd-jwt`) in its compact serialization: `<Issuer-signed JWT>~<Disclosure 1>~...~<optional KB-JWT>`.
+/// See: https://www.ietf.org/archive/id/draft-ietf-oauth-sd-jwt-vc-03.html
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SdJwt {
+    pub issuer_signed_jwt: String,
+    pub disclosures: Vec<Disclosure>,
+    pub key_binding_jwt: Option<String>,
+}
+
+impl SdJwt {
+    /// Returns `true` if the credential is a string in the compact SD-JWT serialization.
+    pub fn is_sd_jwt(credential: &Value) -> bool {
+        credential
+            .as_str()
+            .is_some_and(|credential| credential.contains(SD_JWT_SEPARATOR))
+    }
+
+    pub fn parse(credential: &Value) -> Result<Self, AppError> {
+        let mut components: Vec<&str> = credential
+            .as_str()
+            .ok_or(InvalidCredentialFormatError)?
+            .split(SD_JWT_SEPARATOR)
+            .collect();
+
+        if components.len() < 2 {
+            return Err(InvalidSdJwtError("missing separator"));
+        }
+
+        let issuer_signed_jwt = components.remove(0).to_string();
+        // The last component is either empty or contains the Key Binding JWT.
+        let key_binding_jwt = components
+            .pop()
+            .filter(|key_binding_jwt| !key_binding_jwt.is_empty())
+            .map(ToString::to_string);
+        let disclosures = components
+            .into_iter()
+            .map(Disclosure::parse)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self {
+            issuer_signed_jwt,
+            disclosures,
+            key_binding_jwt,
+        })
+    }
+
+    /// Returns the claims of the Issuer-signed JWT in which all digests are replaced by the disclosed claims.
+    pub fn disclosed_claims(&self) -> Result<Value, AppError> {
+        let mut claims = get_unverified_jwt_claims(&json!(self.issuer_signed_jwt))?;
+
+        resolve_digests(&mut claims, &self.disclosures_by_digest());
+
+        Ok(claims)
+    }
+
+    /// Returns a copy of the SD-JWT which only contains the disclosures on the requested claim paths (see
+    /// `requested_claim_paths`): the disclosures of the requested claims, of everything within them and of the claims
+    /// containing them. Any previous Key Binding JWT is removed.
+    pub fn select_disclosures(&self, requested_claim_paths: &HashSet<Vec<String>>) -> Result<Self, AppError> {
+        let claims = get_unverified_jwt_claims(&json!(self.issuer_signed_jwt))?;
+
+        let mut selected_disclosures = Vec::new();
+        collect_requested_disclosures(
+            &claims,
+            &[],
+            requested_claim_paths,
+            &self.disclosures_by_digest(),
+            &mut selected_disclosures,
+        );
+
+        Ok(Self {
+            issuer_signed_jwt: self.issuer_signed_jwt.clone(),
+            disclosures: selected_disclosures,
+            key_binding_jwt: None,
+        })
+    }
+
+    /// Returns the algorithm of the holder key the SD-JWT is bound to (`cnf`), which is the algorithm of its Key
+    /// Binding JWT. The key is either a JWK or a `did:jwk` or `did:key` DID URL.
+    /// See: https://www.ietf.org/archive/id/draft-ietf-oauth-sd-jwt-vc-03.html#name-key-binding-jwt
+    pub fn key_binding_algorithm(&self) -> Result<Algorithm, AppError> {
+        let claims = get_unverified_jwt_claims(&json!(self.issuer_signed_jwt))?;
+        let cnf = &claims["cnf"];
+
+        let jwk = match (&cnf["jwk"], cnf["kid"].as_str()) {
+            (jwk @ Value::Object(_), _) => Some(jwk.clone()),
+            (_, Some(kid)) if kid.starts_with("did:key:z6Mk") => return Ok(Algorithm::EdDSA),
+            (_, Some(kid)) if kid.starts_with("did:key:zDn") => return Ok(Algorithm::ES256),
+            (_, Some(kid)) => kid
+                .strip_prefix("did:jwk:")
+                .and_then(|did_jwk| did_jwk.split('#').next())
+                .and_then(|encoded| URL_SAFE_NO_PAD.decode(encoded).ok())
+                .and_then(|jwk| serde_json::from_slice(&jwk).ok()),
+            _ => None,
+        };
+
+        match jwk.as_ref().map(|jwk| (jwk["kty"].as_str(), jwk["crv"].as_str())) {
+            Some((Some("OKP"), Some("Ed25519"))) => Ok(Algorithm::EdDSA),
+            Some((Some("EC"), Some("P-256"))) => Ok(Algorithm::ES256),
+            _ => Err(InvalidSdJwtError("missing or unsupported `cnf` key")),
+        }
+    }
+
+    /// Signs a Key Binding JWT for the given `audience` and `nonce` with the holder's key the SD-JWT is bound to and
+    /// returns the serialized SD-JWT including the Key Binding JWT.
+    /// See: https://www.ietf.org/archive/id/draft-ietf-oauth-selective-disclosure-jwt-08.html#name-key-binding-jwt
+    pub async fn with_key_binding(
+        mut self,
+        subject: &dyn Sign,
+        subject_syntax_type: &str,
+        audience: &str,
+        nonce: &str,
+    ) -> Result<String, AppError> {
+        let algorithm = self.key_binding_algorithm()?;
+        let header = json!({
+            "alg": algorithm,
+            "typ": "kb+jwt",
+        });
+        let claims = json!({
+            "iat": chrono::Utc::now().timestamp(),
+            "aud": audience,
+            "nonce": nonce,
+            "sd_hash": URL_SAFE_NO_PAD.encode(Sha256::digest(self.serialize().as_bytes())),
+        });
+
+        let message = [header, claims]
+            .iter()
+            .map(|value| URL_SAFE_NO_PAD.encode(value.to_string()))
+            .collect::<Vec<_>>()
+            .join(".");
+
+        let signature = subject
+            .sign(&message, subject_syntax_type, algorithm)
+            .await
+            .map_err(KeyBindingJwtError)?;
+
+        self.key_binding_jwt = Some(format!("{message}.{}", URL_SAFE_NO_PAD.encode(signature)));
+
+        Ok(self.serialize())
+    }
+
+    /// Returns the compact serialization of the SD-JWT.
+    pub fn serialize(&self) -> String {
+        std::iter::once(self.issuer_signed_jwt.as_str())
+            .chain(self.disclosures.iter().map(|disclosure| disclosure.encoded.as_str()))
+            .chain(std::iter::once(self.key_binding_jwt.as_deref().unwrap_or_default()))
+            .collect::<Vec<_>>()
+            .join(&SD_JWT_SEPARATOR.to_string())
+    }
+
+    fn disclosures_by_digest(&self) -> HashMap<String, &Disclosure> {
+        self.disclosures
+            .iter()
+            .map(|disclosure| (disclosure.digest(), disclosure))
+            .collect()
+    }
+}
+
+// Replaces the `_sd` digests and the `{"...": <digest>}` array elements by their disclosed values.
+fn resolve_digests(value: &mut Value, disclosures: &HashMap<String, &Disclosure>) {
+    match value {
+        Value::Object(object) => {
+            if let Some(Value::Array(digests)) = object.remove("_sd") {
+                for disclosure in digests
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .filter_map(|digest| disclosures.get(digest))
+                {
+                    if let Some(claim_name) = &disclosure.claim_name {
+                        object.insert(claim_name.clone(), disclosure.claim_value.clone());
+                    }
+                }
+            }
+            object.remove("_sd_alg");
+
+            object
+                .values_mut()
+                .for_each(|value| resolve_digests(value, disclosures));
+        }
+        Value::Array(elements) => {
+            *elements = std::mem::take(elements)
+                .into_iter()
+                .filter_map(|element| match array_element_digest(&element) {
+                    // Undisclosed array elements are removed.
+                    Some(digest) => disclosures.get(digest).map(|disclosure| disclosure.claim_value.clone()),
+                    None => Some(element),
+                })
+                .collect();
+
+            elements
+                .iter_mut()
+                .for_each(|element| resolve_digests(element, disclosures));
+        }
+        _ => {}
+    }
+}
+
+// Collects the disclosures of the claims on the requested claim paths. The `path` is the path of the `value`, which
+// leaves out array indices like `requested_claim_paths` does.
+fn collect_requested_disclosures(
+    value: &Value,
+    path: &[String],
+    requested_claim_paths: &HashSet<Vec<String>>,
+    disclosures: &HashMap<String, &Disclosure>,
+    selected_disclosures: &mut Vec<Disclosure>,
+) {
+    match value {
+        Value::Object(object) => {
+            for (key, value) in object {
+                if key == "_sd" {
+                    for disclosure in value
+                        .as_array()
+                        .into_iter()
+                        .flatten()
+                        .filter_map(Value::as_str)
+                        .filter_map(|digest| disclosures.get(digest))
+                    {
+                        let Some(claim_name) = &disclosure.claim_name else {
+                            continue;
+                        };
+                        let claim_path = [path, &[claim_name.clone()]].concat();
+                        if is_on_requested_path(&claim_path, requested_claim_paths) {
+                            selected_disclosures.push((*disclosure).clone());
+                            collect_requested_disclosures(
+                                &disclosure.claim_value,
+                                &claim_path,
+                                requested_claim_paths,
+                                disclosures,
+                                selected_disclosures,
+                            );
+                        }
+                    }
+                } else {
+                    collect_requested_disclosures(
+                        value,
+                        &[path, &[key.clone()]].concat(),
+                        requested_claim_paths,
+                        disclosures,
+                        selected_disclosures,
+                    );
+                }
+            }
+        }
+        Value::Array(elements) => {
+            for element in elements {
+                match array_element_digest(element).and_then(|digest| disclosures.get(digest)) {
+                    Some(disclosure) if is_on_requested_path(path, requested_claim_paths) => {
+                        selected_disclosures.push((*disclosure).clone());
+                        collect_requested_disclosures(
+                            &disclosure.claim_value,
+                            path,
+                            requested_claim_paths,
+                            disclosures,
+                            selected_disclosures,
+                        );
+                    }
+                    Some(_) => {}
+                    None => collect_requested_disclosures(
+                        element,
+                        path,
+                        requested_claim_paths,
+                        disclosures,
+                        selected_disclosures,
+                    ),
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+// A claim is on a requested path if it is requested itself, is contained in a requested claim or contains a requested
+// claim.
+fn is_on_requested_path(path: &[String], requested_claim_paths: &HashSet<Vec<String>>) -> bool {
+    requested_claim_paths
+        .iter()
+        .any(|requested_claim_path| requested_claim_path.starts_with(path) || path.starts_with(requested_claim_path))
+}
+
+fn array_element_digest(element: &Value) -> Option<&str> {
+    element
+        .as_object()
+        .filter(|object| object.len() == 1)
+        .and_then(|object| object.get("..."))
+        .and_then(Value::as_str)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::core_utils::helpers::requested_claim_paths;
+
+    fn encode(value: Value) -> String {
+        URL_SAFE_NO_PAD.encode(value.to_string())
+    }
+
+    fn sd_jwt() -> SdJwt {
+        let given_name = Disclosure::parse(&encode(json!(["2GLC42sKQveCfGfryNRN9w", "given_name", "Ferris"]))).unwrap();
+        let family_name =
+            Disclosure::parse(&encode(json!(["eluV5Og3gSNII8EYnsxA_A", "family_name", "Crabman"]))).unwrap();
+        let nationality = Disclosure::parse(&encode(json!(["lklxF5jMYlGTPUovMNIvCA", "NL"]))).unwrap();
+
+        let issuer_signed_jwt = [
+            encode(json!({ "alg": "EdDSA", "typ": "vc+sd-jwt" })),
+            encode(json!({
+                "iss": "https://issuer.example.com",
+                "vct": "https://credentials.example.com/identity_credential",
+                "_sd_alg": "sha-256",
+                "_sd": [given_name.digest(), family_name.digest()],
+                "nationalities": [{ "...": nationality.digest() }, "DE"],
+            })),
+            "c2ln".to_string(),
+        ]
+        .join(".");
+
+        SdJwt {
+            issuer_signed_jwt,
+            disclosures: vec![given_name, family_name, nationality],
+            key_binding_jwt: None,
+        }
+    }
+
+    #[test]
+    fn sd_jwt_is_parsed_from_its_compact_serialization() {
+        let sd_jwt = sd_jwt();
+        let credential = json!(sd_jwt.serialize());
+
+        assert!(SdJwt::is_sd_jwt(&credential));
+        assert!(!SdJwt::is_sd_jwt(&json!("eyJhbGciOiJFZERTQSJ9.e30.c2ln")));
+        assert_eq!(SdJwt::parse(&credential).unwrap(), sd_jwt);
+    }
+
+    #[test]
+    fn disclosed_claims_replace_digests() {
+        assert_eq!(
+            sd_jwt().disclosed_claims().unwrap(),
+            json!({
+                "iss": "https://issuer.example.com",
+                "vct": "https://credentials.example.com/identity_credential",
+                "given_name": "Ferris",
+                "family_name": "Crabman",
+                "nationalities": ["NL", "DE"],
+            })
+        );
+    }
+
+    #[test]
+    fn only_requested_disclosures_are_selected() {
+        let presentation_definition = json!({
+            "id": "identity",
+            "input_descriptors": [{
+                "id": "identity_credential",
+                "constraints": {
+                    "fields": [
+                        { "path": ["$.vct"] },
+                        { "path": ["$.given_name"] },
+                    ]
+                }
+            }]
+        });
+
+        let presentation = sd_jwt()
+            .select_disclosures(&requested_claim_paths(&presentation_definition))
+            .unwrap();
+
+        assert_eq!(
+            presentation.disclosed_claims().unwrap(),
+            json!({
+                "iss": "https://issuer.example.com",
+                "vct": "https://credentials.example.com/identity_credential",
+                "given_name": "Ferris",
+                "nationalities": ["DE"],
+            })
+        );
+    }
+
+    #[test]
+    fn disclosures_are_selected_by_their_full_path() {
+        // The claim name `name` is used both at the top level and within `address`.
+        let name = Disclosure::parse(&encode(json!(["2GLC42sKQveCfGfryNRN9w", "name", "Ferris"]))).unwrap();
+        let address_name =
+            Disclosure::parse(&encode(json!(["eluV5Og3gSNII8EYnsxA_A", "name", "Crab Street 1"]))).unwrap();
+        let locality = Disclosure::parse(&encode(json!(["6Ij7tM-a5iVPGboS5tmvVA", "locality", "Rustville"]))).unwrap();
+        let address = Disclosure::parse(&encode(json!([
+            "eI8ZWm9QnKPpNPeNenHdhQ",
+            "address",
+            { "_sd": [address_name.digest(), locality.digest()] }
+        ])))
+        .unwrap();
+
+        let sd_jwt = SdJwt {
+            issuer_signed_jwt: [
+                encode(json!({ "alg": "EdDSA", "typ": "vc+sd-jwt" })),
+                encode(json!({
+                    "iss": "https://issuer.example.com",
+                    "_sd_alg": "sha-256",
+                    "_sd": [name.digest(), address.digest()],
+                })),
+                "c2ln".to_string(),
+            ]
+            .join("."),
+            disclosures: vec![name, address_name, locality, address],
+            key_binding_jwt: None,
+        };
+
+        let presentation_definition = json!({
+            "id": "address",
+            "input_descriptors": [{
+                "id": "address_name",
+                "constraints": { "fields": [{ "path": ["$.address.name"] }] }
+            }]
+        });
+
+        let presentation = sd_jwt
+            .select_disclosures(&requested_claim_paths(&presentation_definition))
+            .unwrap();
+
+        assert_eq!(
+            presentation.disclosed_claims().unwrap(),
+            json!({
+                "iss": "https://issuer.example.com",
+                "address": { "name": "Crab Street 1" },
+            })
+        );
+    }
+
+    #[test]
+    fn key_binding_algorithm_is_read_from_cnf() {
+        let sd_jwt_with_cnf = |cnf: Value| SdJwt {
+            issuer_signed_jwt: [
+                encode(json!({ "alg": "EdDSA", "typ": "vc+sd-jwt" })),
+                encode(json!({ "iss": "https://issuer.example.com", "cnf": cnf })),
+                "c2ln".to_string(),
+            ]
+            .join("."),
+            disclosures: vec![],
+            key_binding_jwt: None,
+        };
+
+        let es256_jwk = json!({
+            "kty": "EC",
+            "crv": "P-256",
+            "x": "TCAER19Zvu3OHF4j4W4vfSVoHIP1ILilDls7vCeGemc",
+            "y": "ZxjiWWbZMQGHVWKVQ4hbSIirsVfuecCE6t4jT9F2HZQ"
+        });
+        assert_eq!(
+            sd_jwt_with_cnf(json!({ "jwk": es256_jwk }))
+                .key_binding_algorithm()
+                .unwrap(),
+            Algorithm::ES256
+        );
+
+        let ed25519_did_jwk = format!(
+            "did:jwk:{}#0",
+            encode(json!({ "kty": "OKP", "crv": "Ed25519", "x": "11qYAYKxCrfVS_7TyWQHOg7hcvPapiMlrwIaaPcHURo" }))
+        );
+        assert_eq!(
+            sd_jwt_with_cnf(json!({ "kid": ed25519_did_jwk }))
+                .key_binding_algorithm()
+                .unwrap(),
+            Algorithm::EdDSA
+        );
+
+        assert!(sd_jwt_with_cnf(json!(null)).key_binding_algorithm().is_err());
+    }
+}
@@ -0,0 +1,550 @@
+use crate::{
+    error::AppError::{self, *},
+    state::{
+        core_utils::{helpers::http_client, DateUtils},
+        credentials::{verification::verify_credential, VerifiableCredentialRecord},
+        did::validate_domain_linkage::ValidationStatus,
+    },
+    stronghold::StrongholdManager,
+};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use chrono::{DateTime, Duration, Utc};
+use flate2::read::GzDecoder;
+use lazy_static::lazy_static;
+use log::{info, warn};
+use oid4vc::oid4vc_core::Verify;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{collections::HashMap, io::Read, sync::Mutex};
+use ts_rs::TS;
+
+/// The time during which a fetched status list is reused before it is fetched again.
+const STATUS_LIST_TTL_MINUTES: i64 = 5;
+
+/// The maximum size of a decompressed status list (16 MiB, i.e. over 134 million entries), which protects against
+/// compression bombs.
+const MAX_STATUS_LIST_BYTES: u64 = 16 * 1024 * 1024;
+
+lazy_static! {
+    // Status lists are shared by many credentials, so they are cached by their URL.
+    static ref STATUS_LIST_CACHE: Mutex<HashMap<String, (DateTime<Utc>, StatusList)>> = Mutex::new(HashMap::new());
+}
+
+/// The status lists by the URL of their status list credential, or `None` if it could not be retrieved.
+pub type StatusLists = HashMap<String, Option<StatusList>>;
+
+/// A verified status list credential.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StatusList {
+    /// The issuer of the status list credential, which needs to be the issuer of the credentials it lists.
+    issuer: Option<String>,
+    /// The purposes of the status list (e.g. `revocation`), which need to include the purpose of the status entries.
+    status_purposes: Vec<String>,
+    /// The decoded bitstring.
+    bits: Vec<u8>,
+}
+
+/// The status of a credential according to the status list(s) of its issuer.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq, TS, Default)]
+#[ts(export, export_to = "bindings/credentials/CredentialStatus.ts")]
+pub struct CredentialStatus {
+    pub status: RevocationStatus,
+    /// The moment the status was last checked (RFC 3339), if the credential has any status entries.
+    #[ts(optional)]
+    pub last_checked: Option<String>,
+}
+
+impl CredentialStatus {
+    /// Returns `true` if the credential should not be presented, because it is revoked or suspended.
+    pub fn is_revoked(&self) -> bool {
+        matches!(self.status, RevocationStatus::Revoked | RevocationStatus::Suspended)
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq, TS, Default)]
+#[ts(export, export_to = "bindings/credentials/RevocationStatus.ts")]
+#[serde(rename_all = "snake_case")]
+pub enum RevocationStatus {
+    Valid,
+    Revoked,
+    Suspended,
+    /// The credential has no (supported) status entries, or its status list could not be retrieved.
+    #[default]
+    Unknown,
+}
+
+/// An entry of the `credentialStatus` property of a credential.
+/// See: https://www.w3.org/TR/2023/WD-vc-status-list-20230427/#statuslist2021entry and
+/// https://www.w3.org/TR/vc-bitstring-status-list/#bitstringstatuslistentry
+#[derive(Debug, PartialEq)]
+struct StatusListEntry {
+    status_purpose: String,
+    status_list_index: usize,
+    status_list_credential: String,
+}
+
+/// Checks the status of the credential against the status lists referenced by its `credentialStatus` entries. The
+/// `status_lists` are the status lists that have already been retrieved during this scan, by URL. A status list is only
+/// used if it has been issued by the issuer of the credential for the purpose of the entry.
+pub async fn check_status(
+    verifiable_credential_record: &VerifiableCredentialRecord,
+    verifier: &dyn Verify,
+    status_lists: &mut StatusLists,
+) -> CredentialStatus {
+    let display_credential = &verifiable_credential_record.display_credential;
+    let issuer = verifiable_credential_record.issuer();
+    let entries = status_list_entries(&display_credential.data);
+
+    // Without status entries, there is nothing to check.
+    if entries.is_empty() {
+        return CredentialStatus::default();
+    }
+
+    let mut status = RevocationStatus::Valid;
+
+    for entry in entries {
+        let status_list = match status_lists.get(&entry.status_list_credential) {
+            Some(status_list) => status_list.clone(),
+            None => {
+                let status_list = fetch_status_list(&entry.status_list_credential, verifier)
+                    .await
+                    .map_err(|e| {
+                        warn!(
+                            "failed to retrieve status list `{}`: {:?}",
+                            entry.status_list_credential, e
+                        )
+                    })
+                    .ok();
+                status_lists.insert(entry.status_list_credential.clone(), status_list.clone());
+                status_list
+            }
+        };
+
+        let is_set = status_list
+            .ok_or(InvalidStatusListError("the status list could not be retrieved"))
+            .and_then(|status_list| {
+                if status_list.issuer.is_none() || status_list.issuer != issuer {
+                    return Err(InvalidStatusListError(
+                        "the status list is not issued by the issuer of the credential",
+                    ));
+                }
+                if !status_list.status_purposes.contains(&entry.status_purpose) {
+                    return Err(InvalidStatusListError(
+                        "the status list is not meant for the purpose of the status entry",
+                    ));
+                }
+                bit(&status_list.bits, entry.status_list_index)
+            });
+
+        match (is_set, entry.status_purpose.as_str()) {
+            (Ok(true), "revocation") => {
+                status = RevocationStatus::Revoked;
+                break;
+            }
+            (Ok(true), "suspension") => status = RevocationStatus::Suspended,
+            (Ok(_), _) => {}
+            (Err(e), _) => {
+                warn!(
+                    "failed to check the status of credential `{}`: {:?}",
+                    display_credential.id, e
+                );
+                if status == RevocationStatus::Valid {
+                    status = RevocationStatus::Unknown;
+                }
+            }
+        }
+    }
+
+    CredentialStatus {
+        status,
+        last_checked: Some(DateUtils::new_date_string()),
+    }
+}
+
+/// Checks the status of the credentials and stores the results in their metadata. Each status list is retrieved at
+/// most once and the Stronghold is committed once for all credentials.
+pub async fn update_statuses(
+    stronghold_manager: &StrongholdManager,
+    verifiable_credential_records: Vec<VerifiableCredentialRecord>,
+    verifier: &dyn Verify,
+) -> Result<Vec<VerifiableCredentialRecord>, AppError> {
+    let mut status_lists = StatusLists::new();
+    let mut updated_records = vec![];
+
+    for mut verifiable_credential_record in verifiable_credential_records {
+        let status = check_status(&verifiable_credential_record, verifier, &mut status_lists).await;
+        info!(
+            "status of credential `{}`: {:?}",
+            verifiable_credential_record.display_credential.id, status
+        );
+
+        verifiable_credential_record.display_credential.metadata.status = status;
+        updated_records.push(verifiable_credential_record);
+    }
+
+    stronghold_manager
+        .insert_all(
+            updated_records
+                .iter()
+                .map(|verifiable_credential_record| {
+                    Ok((
+                        verifiable_credential_record
+                            .display_credential
+                            .id
+                            .parse()
+                            .map_err(InvalidUuidError)?,
+                        json!(verifiable_credential_record).to_string().as_bytes().to_vec(),
+                    ))
+                })
+                .collect::<Result<_, AppError>>()?,
+        )
+        .map_err(StrongholdInsertionError)?;
+
+    Ok(updated_records)
+}
+
+// The `credentialStatus` property can contain either a single entry or a list of entries. Entries of unsupported types
+// are ignored.
+fn status_list_entries(credential: &Value) -> Vec<StatusListEntry> {
+    let entries = match &credential["credentialStatus"] {
+        Value::Array(entries) => entries.clone(),
+        Value::Object(_) => vec![credential["credentialStatus"].clone()],
+        _ => vec![],
+    };
+
+    entries
+        .iter()
+        .filter(|entry| {
+            matches!(
+                entry["type"].as_str(),
+                Some("StatusList2021Entry") | Some("BitstringStatusListEntry")
+            )
+        })
+        .filter_map(|entry| {
+            Some(StatusListEntry {
+                status_purpose: entry["statusPurpose"].as_str()?.to_string(),
+                // The index is a string according to the specifications, but some issuers use a number instead.
+                status_list_index: match &entry["statusListIndex"] {
+                    Value::String(index) => index.parse().ok()?,
+                    index => index.as_u64()? as usize,
+                },
+                status_list_credential: entry["statusListCredential"].as_str()?.to_string(),
+            })
+        })
+        .collect()
+}
+
+// Retrieves the status list credential, verifies its signature and validity period and returns the status list.
+async fn fetch_status_list(url: &str, verifier: &dyn Verify) -> Result<StatusList, AppError> {
+    if let Some((fetched_at, status_list)) = STATUS_LIST_CACHE.lock().unwrap().get(url) {
+        if Utc::now() - *fetched_at < Duration::minutes(STATUS_LIST_TTL_MINUTES) {
+            return Ok(status_list.clone());
+        }
+    }
+
+    info!("fetching status list: {}", url);
+
+    let response = http_client()
+        .get(url)
+        .send()
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .map_err(|e| GetStatusListError(e.into()))?
+        .text()
+        .await
+        .map_err(|e| GetStatusListError(e.into()))?;
+
+    // The status list credential is either a JSON-LD credential or a JWT.
+    let status_list_credential = match serde_json::from_str::<Value>(&response) {
+        Ok(credential @ Value::Object(_)) => credential,
+        _ => Value::String(response.trim().to_string()),
+    };
+
+    let status_list_record: VerifiableCredentialRecord = status_list_credential.try_into()?;
+
+    let verification = verify_credential(&status_list_record, verifier, &[]).await?;
+    // A status list that is not known to be signed by its issuer could revoke or reinstate any credential.
+    if verification.signature.status != ValidationStatus::Success {
+        return Err(InvalidStatusListError(
+            "the signature of the status list credential could not be verified",
+        ));
+    }
+    if verification.validity_period.status == ValidationStatus::Failure {
+        return Err(InvalidStatusListError(
+            "the status list credential is not within its validity period",
+        ));
+    }
+
+    let credential_subject = &status_list_record.display_credential.data["credentialSubject"];
+    let status_list = StatusList {
+        issuer: status_list_record.issuer(),
+        // The `statusPurpose` of a Bitstring Status List can also be a list of purposes.
+        status_purposes: match &credential_subject["statusPurpose"] {
+            Value::Array(status_purposes) => status_purposes
+                .iter()
+                .filter_map(Value::as_str)
+                .map(ToString::to_string)
+                .collect(),
+            status_purpose => status_purpose.as_str().map(ToString::to_string).into_iter().collect(),
+        },
+        bits: decode_status_list(
+            credential_subject["encodedList"]
+                .as_str()
+                .ok_or(InvalidStatusListError("missing `encodedList`"))?,
+        )?,
+    };
+
+    STATUS_LIST_CACHE
+        .lock()
+        .unwrap()
+        .insert(url.to_string(), (Utc::now(), status_list.clone()));
+
+    Ok(status_list)
+}
+
+// The `encodedList` is a GZIP-compressed bitstring, which is base64url-encoded (StatusList2021) or multibase-encoded
+// with the base64url prefix `u` (Bitstring Status List). Its decompressed size is limited to `MAX_STATUS_LIST_BYTES`.
+fn decode_status_list(encoded_list: &str) -> Result<Vec<u8>, AppError> {
+    let compressed = match encoded_list.strip_prefix('u') {
+        Some(encoded_list) => URL_SAFE_NO_PAD.decode(encoded_list),
+        None => URL_SAFE_NO_PAD.decode(encoded_list.trim_end_matches('=')),
+    }
+    .map_err(|_| InvalidStatusListError("`encodedList` is not base64url-encoded"))?;
+
+    let mut status_list = vec![];
+    GzDecoder::new(compressed.as_slice())
+        .take(MAX_STATUS_LIST_BYTES + 1)
+        .read_to_end(&mut status_list)
+        .map_err(|_| InvalidStatusListError("`encodedList` is not GZIP-compressed"))?;
+
+    if status_list.len() as u64 > MAX_STATUS_LIST_BYTES {
+        return Err(InvalidStatusListError("the status list is too large"));
+    }
+
+    Ok(status_list)
+}
+
+// The first index corresponds to the left-most (most significant) bit of the first byte.
+fn bit(status_list: &[u8], index: usize) -> Result<bool, AppError> {
+    status_list
+        .get(index / 8)
+        .map(|byte| byte & (0b1000_0000 >> (index % 8)) != 0)
+        .ok_or(InvalidStatusListError("the status list index is out of range"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::credentials::{CredentialFormat, DisplayCredential};
+    use flate2::{write::GzEncoder, Compression};
+    use std::io::Write;
+    use wiremock::{
+        matchers::{method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    struct NoVerifier;
+
+    #[async_trait::async_trait]
+    impl Verify for NoVerifier {
+        async fn public_key(&self, _did_url: &str) -> anyhow::Result<Vec<u8>> {
+            anyhow::bail!("no keys")
+        }
+    }
+
+    fn verifiable_credential_record(status_list_credential: &str) -> VerifiableCredentialRecord {
+        VerifiableCredentialRecord {
+            verifiable_credential: Value::Null,
+            display_credential: DisplayCredential {
+                id: "ddf8ab3f-5a89-4b3b-a5d2-cec6cba8b1f4".to_string(),
+                data: json!({
+                    "issuer": "did:example:issuer",
+                    "credentialStatus": {
+                        "type": "BitstringStatusListEntry",
+                        "statusPurpose": "revocation",
+                        "statusListIndex": "1",
+                        "statusListCredential": status_list_credential
+                    }
+                }),
+                format: CredentialFormat::LdpVc,
+                ..Default::default()
+            },
+            issuance: None,
+        }
+    }
+
+    #[test]
+    fn status_list_entries_are_parsed() {
+        let credential = json!({
+            "credentialStatus": [
+                {
+                    "id": "https://example.com/credentials/status/3#94567",
+                    "type": "StatusList2021Entry",
+                    "statusPurpose": "revocation",
+                    "statusListIndex": "94567",
+                    "statusListCredential": "https://example.com/credentials/status/3"
+                },
+                {
+                    "type": "BitstringStatusListEntry",
+                    "statusPurpose": "suspension",
+                    "statusListIndex": 7,
+                    "statusListCredential": "https://example.com/credentials/status/4"
+                },
+                {
+                    "type": "CredentialStatusList2017",
+                    "id": "https://example.com/credentials/status/5"
+                }
+            ]
+        });
+
+        assert_eq!(
+            status_list_entries(&credential),
+            vec![
+                StatusListEntry {
+                    status_purpose: "revocation".to_string(),
+                    status_list_index: 94567,
+                    status_list_credential: "https://example.com/credentials/status/3".to_string(),
+                },
+                StatusListEntry {
+                    status_purpose: "suspension".to_string(),
+                    status_list_index: 7,
+                    status_list_credential: "https://example.com/credentials/status/4".to_string(),
+                }
+            ]
+        );
+        assert!(status_list_entries(&json!({ "type": ["VerifiableCredential"] })).is_empty());
+    }
+
+    #[test]
+    fn status_list_bits_are_decoded() {
+        let mut encoder = GzEncoder::new(vec![], Compression::default());
+        encoder.write_all(&[0b0100_0000, 0b0000_0001]).unwrap();
+        let encoded_list = URL_SAFE_NO_PAD.encode(encoder.finish().unwrap());
+
+        for encoded_list in [encoded_list.clone(), format!("u{encoded_list}")] {
+            let status_list = decode_status_list(&encoded_list).unwrap();
+
+            assert!(!bit(&status_list, 0).unwrap());
+            assert!(bit(&status_list, 1).unwrap());
+            assert!(bit(&status_list, 15).unwrap());
+            assert!(bit(&status_list, 16).is_err());
+        }
+    }
+
+    #[test]
+    fn oversized_status_list_is_rejected() {
+        let mut encoder = GzEncoder::new(vec![], Compression::best());
+        encoder.write_all(&vec![0; MAX_STATUS_LIST_BYTES as usize + 1]).unwrap();
+        let encoded_list = URL_SAFE_NO_PAD.encode(encoder.finish().unwrap());
+
+        assert!(matches!(
+            decode_status_list(&encoded_list),
+            Err(InvalidStatusListError("the status list is too large"))
+        ));
+    }
+
+    #[tokio::test]
+    async fn status_list_needs_to_match_the_issuer_and_status_purpose() {
+        let status_list_credential = "https://example.com/credentials/status/3";
+        let verifiable_credential_record = verifiable_credential_record(status_list_credential);
+        let status_list = |issuer: &str, status_purpose: &str| StatusList {
+            issuer: Some(issuer.to_string()),
+            status_purposes: vec![status_purpose.to_string()],
+            bits: vec![0b0100_0000],
+        };
+
+        for (status_list, status) in [
+            (
+                status_list("did:example:issuer", "revocation"),
+                RevocationStatus::Revoked,
+            ),
+            (
+                status_list("did:example:other", "revocation"),
+                RevocationStatus::Unknown,
+            ),
+            (
+                status_list("did:example:issuer", "suspension"),
+                RevocationStatus::Unknown,
+            ),
+        ] {
+            let mut status_lists = StatusLists::from([(status_list_credential.to_string(), Some(status_list))]);
+
+            assert_eq!(
+                check_status(&verifiable_credential_record, &NoVerifier, &mut status_lists)
+                    .await
+                    .status,
+                status
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn unavailable_status_list_is_fetched_once_per_scan() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/status/unavailable"))
+            .respond_with(ResponseTemplate::new(503))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let verifiable_credential_record =
+            verifiable_credential_record(&format!("{}/status/unavailable", mock_server.uri()));
+        let mut status_lists = StatusLists::new();
+
+        for _ in 0..2 {
+            assert_eq!(
+                check_status(&verifiable_credential_record, &NoVerifier, &mut status_lists)
+                    .await
+                    .status,
+                RevocationStatus::Unknown
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn status_list_with_unverified_signature_is_not_used() {
+        let mock_server = MockServer::start().await;
+
+        // The status list revokes the credential, but its signature cannot be verified.
+        let mut encoder = GzEncoder::new(vec![], Compression::default());
+        encoder.write_all(&[0b0100_0000]).unwrap();
+        let status_list_credential = format!(
+            "{}.{}.{}",
+            URL_SAFE_NO_PAD.encode(json!({ "alg": "EdDSA", "typ": "JWT" }).to_string()),
+            URL_SAFE_NO_PAD.encode(
+                json!({
+                    "iss": "did:example:issuer",
+                    "vc": {
+                        "type": ["VerifiableCredential", "BitstringStatusListCredential"],
+                        "credentialSubject": {
+                            "type": "BitstringStatusList",
+                            "statusPurpose": "revocation",
+                            "encodedList": format!("u{}", URL_SAFE_NO_PAD.encode(encoder.finish().unwrap()))
+                        }
+                    }
+                })
+                .to_string()
+            ),
+            URL_SAFE_NO_PAD.encode([0; 64])
+        );
+
+        Mock::given(method("GET"))
+            .and(path("/status/unverified"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(status_list_credential))
+            .mount(&mock_server)
+            .await;
+
+        let verifiable_credential_record =
+            verifiable_credential_record(&format!("{}/status/unverified", mock_server.uri()));
+
+        assert_eq!(
+            check_status(&verifiable_credential_record, &NoVerifier, &mut StatusLists::new())
+                .await
+                .status,
+            RevocationStatus::Unknown
+        );
+    }
+}
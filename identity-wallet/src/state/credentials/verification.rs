@@ -0,0 +1,579 @@
+use crate::{
+    error::AppError::{self, *},
+    state::{
+        core_utils::helpers::{get_unverified_jwt_claims, http_client},
+        credentials::{
+            data_integrity::verify_proof, mdoc::Mdoc, sd_jwt::SdJwt, CredentialFormat, VerifiableCredentialRecord,
+        },
+        did::validate_domain_linkage::{ValidationResult, ValidationStatus},
+        SUPPORTED_DID_METHODS, SUPPORTED_SIGNING_ALGORITHMS,
+    },
+    subject::{public_key_bytes, Subject},
+};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use chrono::{DateTime, Utc};
+use identity_iota::verification::jwk::Jwk;
+use jsonwebtoken::{crypto::verify, Algorithm, DecodingKey};
+use oid4vc::oid4vc_core::{Subject as _, Verify};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use ts_rs::TS;
+use url::Url;
+
+/// The outcome of the verification of a credential on receipt.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq, TS, Default)]
+#[ts(export, export_to = "bindings/credentials/CredentialVerification.ts")]
+pub struct CredentialVerification {
+    /// Whether the credential is signed by (a key of) its issuer.
+    pub signature: ValidationResult,
+    /// Whether the credential was valid at the moment it was received, based on `nbf`/`exp` or their equivalents.
+    pub validity_period: ValidationResult,
+    /// Whether the credential was issued to one of the wallet's DIDs.
+    pub subject: ValidationResult,
+}
+
+impl CredentialVerification {
+    /// A credential whose signature is invalid or cannot be verified (e.g. because the key of its issuer cannot be
+    /// resolved) cannot be trusted at all and is therefore rejected. Other failures are only flagged in the
+    /// credential's metadata.
+    pub fn is_rejected(&self) -> bool {
+        self.signature.status != ValidationStatus::Success
+    }
+}
+
+/// Verifies the signature, validity period and subject of a received credential. Public keys are resolved from the
+/// issuer's DID document through the `verifier`, or from the metadata of an issuer that is identified by a URL.
+pub async fn verify_credential(
+    verifiable_credential_record: &VerifiableCredentialRecord,
+    verifier: &dyn Verify,
+    holder_dids: &[String],
+) -> Result<CredentialVerification, AppError> {
+    let credential = &verifiable_credential_record.verifiable_credential;
+    let data = &verifiable_credential_record.display_credential.data;
+
+    let verification = match verifiable_credential_record.display_credential.format {
+        CredentialFormat::JwtVcJson => {
+            let jwt = credential.as_str().ok_or(InvalidCredentialFormatError)?;
+            let claims = get_unverified_jwt_claims(credential)?;
+
+            CredentialVerification {
+                signature: verify_jwt_signature(jwt, &claims, verifier).await,
+                validity_period: verify_validity_period(timestamp(&claims["nbf"]), timestamp(&claims["exp"])),
+                subject: verify_subject(
+                    claims["sub"]
+                        .as_str()
+                        .or(claims["vc"]["credentialSubject"]["id"].as_str()),
+                    holder_dids,
+                ),
+            }
+        }
+        CredentialFormat::VcSdJwt => {
+            let issuer_signed_jwt = SdJwt::parse(credential)?.issuer_signed_jwt;
+            let claims = get_unverified_jwt_claims(&Value::String(issuer_signed_jwt.clone()))?;
+
+            CredentialVerification {
+                signature: verify_jwt_signature(&issuer_signed_jwt, &claims, verifier).await,
+                validity_period: verify_validity_period(timestamp(&claims["nbf"]), timestamp(&claims["exp"])),
+                subject: verify_subject(claims["sub"].as_str().or(claims["cnf"]["kid"].as_str()), holder_dids),
+            }
+        }
+        CredentialFormat::LdpVc => CredentialVerification {
+            signature: match verify_proof(credential, verifier).await {
+                Ok(()) => success(),
+                Err(e @ UnsupportedProofError(_)) => unknown(e.to_string()),
+                Err(e) => failure(e.to_string()),
+            },
+            validity_period: verify_validity_period(
+                date(&data["validFrom"]).or(date(&data["issuanceDate"])),
+                date(&data["validUntil"]).or(date(&data["expirationDate"])),
+            ),
+            subject: verify_subject(data["credentialSubject"]["id"].as_str(), holder_dids),
+        },
+        CredentialFormat::MsoMdoc => {
+            let mdoc = Mdoc::parse(credential)?;
+
+            CredentialVerification {
+                signature: mdoc.verify_issuer_signature(),
+                validity_period: verify_validity_period(
+                    mdoc.validity_info("validFrom").as_deref().and_then(parse_date),
+                    mdoc.validity_info("validUntil").as_deref().and_then(parse_date),
+                ),
+                subject: unknown("An mdoc is bound to a device key instead of a DID".to_string()),
+            }
+        }
+    };
+
+    Ok(verification)
+}
+
+/// Returns the DIDs of the holder: the DIDs in the state and the DIDs of the active (pairwise) keys of the `subject`.
+pub async fn holder_dids(dids: &HashMap<String, String>, subject: &Subject) -> Vec<String> {
+    let mut holder_dids: Vec<String> = dids.values().cloned().collect();
+
+    for did_method in SUPPORTED_DID_METHODS {
+        for algorithm in SUPPORTED_SIGNING_ALGORITHMS {
+            if let Ok(did) = subject.identifier(did_method, *algorithm).await {
+                if !holder_dids.contains(&did) {
+                    holder_dids.push(did);
+                }
+            }
+        }
+    }
+
+    holder_dids
+}
+
+// Verifies the JWS with the public key referenced by the `kid` in the header, which needs to belong to the issuer
+// (`iss`). The key of an issuer that is identified by a DID is resolved from its DID document, where a relative `kid`
+// (e.g. `#key-1`) refers to a key of the issuer's DID. The key of an issuer that is identified by an HTTPS URL is
+// resolved from its JWT VC Issuer Metadata.
+async fn verify_jwt_signature(jwt: &str, claims: &Value, verifier: &dyn Verify) -> ValidationResult {
+    // The header is decoded manually, since `jsonwebtoken` does not know the `ES256K` algorithm.
+    let header: Value = match jwt
+        .split('.')
+        .next()
+        .and_then(|header| URL_SAFE_NO_PAD.decode(header).ok())
+        .and_then(|header| serde_json::from_slice(&header).ok())
+    {
+        Some(header) => header,
+        None => return failure("The credential does not contain a valid JWS header".to_string()),
+    };
+
+    let Some(issuer) = claims["iss"].as_str() else {
+        return failure("The credential does not specify an issuer (`iss`)".to_string());
+    };
+    let Some(kid) = header["kid"].as_str() else {
+        return failure("The credential does not contain a key identifier (`kid`)".to_string());
+    };
+
+    let (message, signature) = match jwt.rsplit_once('.') {
+        Some(parts) => parts,
+        None => return failure("The credential is not a valid JWS".to_string()),
+    };
+
+    let algorithm = header["alg"].as_str().unwrap_or_default();
+    if !["EdDSA", "ES256", "ES384", "ES256K"].contains(&algorithm) {
+        return unknown(format!("Unsupported signature algorithm: {algorithm}"));
+    }
+
+    let public_key = if issuer.starts_with("did:") {
+        let kid = match kid.starts_with('#') {
+            true => format!("{issuer}{kid}"),
+            false => kid.to_string(),
+        };
+        if kid.split('#').next() != Some(issuer) {
+            return failure(format!("The key `{kid}` does not belong to the issuer `{issuer}`"));
+        }
+
+        verifier.public_key(&kid).await.map_err(|e| e.to_string())
+    } else {
+        match issuer.parse::<Url>() {
+            Ok(issuer_url) if issuer_url.scheme() == "https" => issuer_public_key(issuer_url, kid).await,
+            _ => return failure(format!("The issuer `{issuer}` is neither a DID nor an HTTPS URL")),
+        }
+    };
+    let public_key = match public_key {
+        Ok(public_key) => public_key,
+        Err(e) => return failure(e),
+    };
+
+    let verified = match algorithm.parse::<Algorithm>() {
+        Ok(Algorithm::EdDSA) => verify(
+            signature,
+            message.as_bytes(),
+            &DecodingKey::from_ed_der(&public_key),
+            Algorithm::EdDSA,
+        )
+        .map_err(|e| e.to_string()),
+        Ok(algorithm) => verify(
+            signature,
+            message.as_bytes(),
+            &DecodingKey::from_ec_der(&public_key),
+            algorithm,
+        )
+        .map_err(|e| e.to_string()),
+        Err(_) => verify_es256k(signature, message.as_bytes(), &public_key),
+    };
+
+    match verified {
+        Ok(true) => success(),
+        Ok(false) => failure("The signature of the credential is invalid".to_string()),
+        Err(e) => failure(e),
+    }
+}
+
+// Resolves the public key with the given `kid` from the `jwks` (or `jwks_uri`) of the JWT VC Issuer Metadata of the
+// issuer, which is located by inserting `/.well-known/jwt-vc-issuer` between the host and the path of its URL.
+// See: https://datatracker.ietf.org/doc/html/draft-ietf-oauth-sd-jwt-vc-05#section-5
+async fn issuer_public_key(issuer_url: Url, kid: &str) -> Result<Vec<u8>, String> {
+    let client = http_client();
+    let get_json = |url: Url| {
+        let request = client.get(url);
+        async move {
+            request
+                .send()
+                .await
+                .and_then(reqwest::Response::error_for_status)
+                .map_err(|e| e.to_string())?
+                .json::<Value>()
+                .await
+                .map_err(|e| e.to_string())
+        }
+    };
+
+    let mut metadata_url = issuer_url.clone();
+    metadata_url.set_path(&format!(
+        "/.well-known/jwt-vc-issuer{}",
+        issuer_url.path().trim_end_matches('/')
+    ));
+    let metadata = get_json(metadata_url).await?;
+
+    if metadata["issuer"].as_str().map(|issuer| issuer.trim_end_matches('/'))
+        != Some(issuer_url.as_str().trim_end_matches('/'))
+    {
+        return Err(format!(
+            "The metadata of the issuer `{issuer_url}` belongs to another issuer"
+        ));
+    }
+
+    let jwks = match (&metadata["jwks"], metadata["jwks_uri"].as_str()) {
+        (Value::Object(_), _) => metadata["jwks"].clone(),
+        (_, Some(jwks_uri)) => get_json(jwks_uri.parse::<Url>().map_err(|e| e.to_string())?).await?,
+        _ => {
+            return Err(format!(
+                "The metadata of the issuer `{issuer_url}` does not contain any keys"
+            ))
+        }
+    };
+
+    jwks["keys"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .find(|jwk| jwk["kid"].as_str() == Some(kid))
+        .and_then(|jwk| serde_json::from_value::<Jwk>(jwk.clone()).ok())
+        .as_ref()
+        .and_then(public_key_bytes)
+        .ok_or(format!("The key `{kid}` does not belong to the issuer `{issuer_url}`"))
+}
+
+// Verifies an `ES256K` signature (ECDSA using secp256k1 and SHA-256) with a SEC1-encoded public key.
+// See: https://www.rfc-editor.org/rfc/rfc8812#section-3.2
+fn verify_es256k(signature: &str, message: &[u8], public_key: &[u8]) -> Result<bool, String> {
+    use k256::ecdsa::{signature::Verifier as _, Signature, VerifyingKey};
+
+    let verifying_key = VerifyingKey::from_sec1_bytes(public_key).map_err(|e| e.to_string())?;
+    let signature = URL_SAFE_NO_PAD
+        .decode(signature)
+        .ok()
+        .and_then(|signature| Signature::from_slice(&signature).ok())
+        .ok_or("The signature is not a valid ES256K signature".to_string())?;
+    // Signatures with a high `s` value are just as valid, but rejected by `k256`.
+    let signature = signature.normalize_s().unwrap_or(signature);
+
+    Ok(verifying_key.verify(message, &signature).is_ok())
+}
+
+fn verify_validity_period(not_before: Option<DateTime<Utc>>, expires: Option<DateTime<Utc>>) -> ValidationResult {
+    let now = Utc::now();
+
+    match (not_before, expires) {
+        (_, Some(expires)) if expires < now => failure(format!("The credential expired on {}", expires.to_rfc3339())),
+        (Some(not_before), _) if not_before > now => failure(format!(
+            "The credential is not valid before {}",
+            not_before.to_rfc3339()
+        )),
+        (None, None) => unknown("The credential does not specify a validity period".to_string()),
+        _ => success(),
+    }
+}
+
+fn verify_subject(subject: Option<&str>, holder_dids: &[String]) -> ValidationResult {
+    let subject_did = match subject.and_then(|subject| subject.split('#').next()) {
+        Some(subject_did) => subject_did,
+        None => return unknown("The credential does not specify a subject".to_string()),
+    };
+
+    if holder_dids.iter().any(|did| did == subject_did) {
+        success()
+    } else {
+        failure(format!(
+            "The credential was issued to `{subject_did}`, which is not one of this wallet's DIDs"
+        ))
+    }
+}
+
+// Helpers
+
+fn timestamp(value: &Value) -> Option<DateTime<Utc>> {
+    value
+        .as_i64()
+        .and_then(|timestamp| DateTime::from_timestamp(timestamp, 0))
+}
+
+fn date(value: &Value) -> Option<DateTime<Utc>> {
+    value.as_str().and_then(parse_date)
+}
+
+fn parse_date(date: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(date)
+        .ok()
+        .map(|date| date.with_timezone(&Utc))
+}
+
+fn success() -> ValidationResult {
+    ValidationResult {
+        status: ValidationStatus::Success,
+        message: None,
+    }
+}
+
+fn failure(message: String) -> ValidationResult {
+    ValidationResult {
+        status: ValidationStatus::Failure,
+        message: Some(message),
+    }
+}
+
+fn unknown(message: String) -> ValidationResult {
+    ValidationResult {
+        status: ValidationStatus::Unknown,
+        message: Some(message),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ring::signature::{Ed25519KeyPair, KeyPair as _};
+    use serde_json::json;
+
+    const ISSUER: &str = "did:example:issuer";
+
+    // Resolves only the key `did:example:issuer#key-1`.
+    struct IssuerKey(Vec<u8>);
+
+    #[async_trait::async_trait]
+    impl Verify for IssuerKey {
+        async fn public_key(&self, did_url: &str) -> anyhow::Result<Vec<u8>> {
+            match did_url {
+                "did:example:issuer#key-1" => Ok(self.0.clone()),
+                did_url => anyhow::bail!("unknown key: {did_url}"),
+            }
+        }
+    }
+
+    fn encode(value: &Value) -> String {
+        URL_SAFE_NO_PAD.encode(value.to_string())
+    }
+
+    #[tokio::test]
+    async fn jwt_signature_is_verified_with_a_relative_kid() {
+        let key_pair = Ed25519KeyPair::from_seed_unchecked(&[7; 32]).unwrap();
+        let verifier = IssuerKey(key_pair.public_key().as_ref().to_vec());
+
+        let claims = json!({ "iss": ISSUER, "sub": "did:example:holder" });
+        let message = format!(
+            "{}.{}",
+            encode(&json!({ "alg": "EdDSA", "typ": "JWT", "kid": "#key-1" })),
+            encode(&claims)
+        );
+        let jwt = format!(
+            "{message}.{}",
+            URL_SAFE_NO_PAD.encode(key_pair.sign(message.as_bytes()))
+        );
+
+        assert_eq!(
+            verify_jwt_signature(&jwt, &claims, &verifier).await.status,
+            ValidationStatus::Success
+        );
+
+        // The signature does not match other claims.
+        let other_claims = json!({ "iss": ISSUER, "sub": "did:example:other" });
+        let (_, signature) = jwt.rsplit_once('.').unwrap();
+        let altered_jwt = format!(
+            "{}.{}.{signature}",
+            encode(&json!({ "alg": "EdDSA", "typ": "JWT", "kid": "#key-1" })),
+            encode(&other_claims)
+        );
+        assert_eq!(
+            verify_jwt_signature(&altered_jwt, &other_claims, &verifier)
+                .await
+                .status,
+            ValidationStatus::Failure
+        );
+
+        // The key needs to belong to the issuer.
+        let claims = json!({ "iss": "did:example:other", "sub": "did:example:holder" });
+        let message = format!(
+            "{}.{}",
+            encode(&json!({ "alg": "EdDSA", "typ": "JWT", "kid": "did:example:issuer#key-1" })),
+            encode(&claims)
+        );
+        let jwt = format!(
+            "{message}.{}",
+            URL_SAFE_NO_PAD.encode(key_pair.sign(message.as_bytes()))
+        );
+        assert_eq!(
+            verify_jwt_signature(&jwt, &claims, &verifier).await.status,
+            ValidationStatus::Failure
+        );
+    }
+
+    #[tokio::test]
+    async fn es256k_jwt_signature_is_verified() {
+        use k256::ecdsa::{signature::Signer as _, Signature, SigningKey};
+
+        let signing_key = SigningKey::from_slice(&[7; 32]).unwrap();
+        let verifier = IssuerKey(signing_key.verifying_key().to_encoded_point(false).as_bytes().to_vec());
+
+        let claims = json!({ "iss": ISSUER, "sub": "did:example:holder" });
+        let message = format!(
+            "{}.{}",
+            encode(&json!({ "alg": "ES256K", "typ": "JWT", "kid": "did:example:issuer#key-1" })),
+            encode(&claims)
+        );
+        let signature: Signature = signing_key.sign(message.as_bytes());
+        let jwt = format!("{message}.{}", URL_SAFE_NO_PAD.encode(signature.to_bytes()));
+
+        assert_eq!(
+            verify_jwt_signature(&jwt, &claims, &verifier).await.status,
+            ValidationStatus::Success
+        );
+
+        // A signature with another key is invalid.
+        let other_signature: Signature = SigningKey::from_slice(&[8; 32]).unwrap().sign(message.as_bytes());
+        let jwt = format!("{message}.{}", URL_SAFE_NO_PAD.encode(other_signature.to_bytes()));
+        assert_eq!(
+            verify_jwt_signature(&jwt, &claims, &verifier).await.status,
+            ValidationStatus::Failure
+        );
+    }
+
+    #[tokio::test]
+    async fn jwt_signature_needs_a_key_of_the_issuer() {
+        let key_pair = Ed25519KeyPair::from_seed_unchecked(&[7; 32]).unwrap();
+        let verifier = IssuerKey(key_pair.public_key().as_ref().to_vec());
+
+        let signed_jwt = |header: Value, claims: &Value| {
+            let message = format!("{}.{}", encode(&header), encode(claims));
+            format!(
+                "{message}.{}",
+                URL_SAFE_NO_PAD.encode(key_pair.sign(message.as_bytes()))
+            )
+        };
+
+        // Without a `kid`, the key of the issuer cannot be resolved.
+        let claims = json!({ "iss": ISSUER, "sub": "did:example:holder" });
+        let jwt = signed_jwt(json!({ "alg": "EdDSA", "typ": "JWT" }), &claims);
+        assert_eq!(
+            verify_jwt_signature(&jwt, &claims, &verifier).await.status,
+            ValidationStatus::Failure
+        );
+
+        // A key that cannot be resolved.
+        let jwt = signed_jwt(json!({ "alg": "EdDSA", "typ": "JWT", "kid": "#key-2" }), &claims);
+        assert_eq!(
+            verify_jwt_signature(&jwt, &claims, &verifier).await.status,
+            ValidationStatus::Failure
+        );
+
+        // The key of a DID cannot be used by an issuer that is identified by a URL.
+        let claims = json!({ "iss": "http://issuer.example.com", "sub": "did:example:holder" });
+        let jwt = signed_jwt(
+            json!({ "alg": "EdDSA", "typ": "JWT", "kid": "did:example:issuer#key-1" }),
+            &claims,
+        );
+        assert_eq!(
+            verify_jwt_signature(&jwt, &claims, &verifier).await.status,
+            ValidationStatus::Failure
+        );
+    }
+
+    #[tokio::test]
+    async fn issuer_public_key_is_resolved_from_the_issuer_metadata() {
+        use wiremock::{
+            matchers::{method, path},
+            Mock, MockServer, ResponseTemplate,
+        };
+
+        let key_pair = Ed25519KeyPair::from_seed_unchecked(&[7; 32]).unwrap();
+        let jwk = json!({
+            "kty": "OKP",
+            "crv": "Ed25519",
+            "kid": "key-1",
+            "x": URL_SAFE_NO_PAD.encode(key_pair.public_key().as_ref())
+        });
+
+        let mock_server = MockServer::start().await;
+        let issuer_url: Url = format!("{}/tenant", mock_server.uri()).parse().unwrap();
+
+        Mock::given(method("GET"))
+            .and(path("/.well-known/jwt-vc-issuer/tenant"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "issuer": issuer_url,
+                "jwks_uri": format!("{}/jwks", mock_server.uri())
+            })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/jwks"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "keys": [jwk] })))
+            .mount(&mock_server)
+            .await;
+
+        assert_eq!(
+            issuer_public_key(issuer_url.clone(), "key-1").await,
+            Ok(key_pair.public_key().as_ref().to_vec())
+        );
+        assert!(issuer_public_key(issuer_url, "key-2").await.is_err());
+
+        // The metadata needs to belong to the issuer.
+        let other_issuer_url: Url = format!("{}/other", mock_server.uri()).parse().unwrap();
+        Mock::given(method("GET"))
+            .and(path("/.well-known/jwt-vc-issuer/other"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "issuer": format!("{}/tenant", mock_server.uri()),
+                "jwks": { "keys": [jwk] }
+            })))
+            .mount(&mock_server)
+            .await;
+        assert!(issuer_public_key(other_issuer_url, "key-1").await.is_err());
+    }
+
+    #[test]
+    fn expired_credential_fails_validity_period() {
+        let yesterday = Utc::now() - chrono::Duration::days(1);
+        let tomorrow = Utc::now() + chrono::Duration::days(1);
+
+        assert_eq!(
+            verify_validity_period(Some(yesterday), Some(tomorrow)).status,
+            ValidationStatus::Success
+        );
+        assert_eq!(
+            verify_validity_period(None, Some(yesterday)).status,
+            ValidationStatus::Failure
+        );
+        assert_eq!(
+            verify_validity_period(Some(tomorrow), None).status,
+            ValidationStatus::Failure
+        );
+        assert_eq!(verify_validity_period(None, None).status, ValidationStatus::Unknown);
+    }
+
+    #[test]
+    fn subject_must_be_one_of_the_wallet_dids() {
+        let dids = vec!["did:key:z6Mkg1XXGUqfkhAKU1kVd1Pmw6UEj1vxiLj1xc91MBz5owNY".to_string()];
+        let kid = format!("{}#z6Mkg1XXGUqfkhAKU1kVd1Pmw6UEj1vxiLj1xc91MBz5owNY", dids[0]);
+
+        assert_eq!(verify_subject(Some(&kid), &dids).status, ValidationStatus::Success);
+        assert_eq!(
+            verify_subject(Some("did:example:123"), &dids).status,
+            ValidationStatus::Failure
+        );
+        assert_eq!(verify_subject(None, &dids).status, ValidationStatus::Unknown);
+    }
+}
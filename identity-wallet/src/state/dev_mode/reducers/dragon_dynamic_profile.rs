@@ -139,6 +139,7 @@ async fn accept_credential(state: AppState) -> Result<AppState, AppError> {
             "Higher Education Information Literacy Level 1".to_string(),
             "Business Innovation & Interdisciplinair Samenwerken".to_string(),
         ],
+        tx_code: None,
     };
 
     command::reduce(state, Arc::new(cr_selected)).await
@@ -223,6 +224,12 @@ async fn share_credentials(state: AppState) -> Result<AppState, AppError> {
         client_name: _,
         logo_uri: _,
         options,
+        revoked: _,
+        input_descriptors: _,
+        domain_validation: _,
+        client_id_validation: _,
+        trust: _,
+        client_metadata_error: _,
     }) = &state.current_user_prompt
     {
         let credential_uuids: Vec<Uuid> = options
@@ -230,7 +237,10 @@ async fn share_credentials(state: AppState) -> Result<AppState, AppError> {
             .map(|uuid_str| Uuid::parse_str(uuid_str).unwrap())
             .collect();
 
-        let cr_selected = CredentialsSelected { credential_uuids };
+        let cr_selected = CredentialsSelected {
+            credential_uuids,
+            input_descriptor_selection: None,
+        };
 
         command::reduce(state, Arc::new(cr_selected)).await
     } else {
@@ -263,6 +273,7 @@ async fn add_future_engineer(state: AppState) -> Result<AppState, AppError> {
 async fn accept_future_engineer(state: AppState) -> Result<AppState, AppError> {
     let cr_selected = CredentialOffersSelected {
         credential_configuration_ids: vec!["Future Engineer Certificate".to_string()],
+        tx_code: None,
     };
 
     command::reduce(state, Arc::new(cr_selected)).await
@@ -2,16 +2,17 @@ use crate::{
     error::AppError::{self, *},
     persistence::ASSETS_DIR,
     state::{
-        connections::{Connection, Connections},
+        connections::{trust_registry::TrustedRole, Connection, Connections},
         core_utils::{
             history_event::{EventType, HistoryCredential, HistoryEvent},
             IdentityManager,
         },
         credentials::VerifiableCredentialRecord,
         dev_mode::DevMode,
+        did::available_did_methods,
         profile_settings::{AppTheme, Profile},
         user_prompt::CurrentUserPrompt,
-        AppState, SUPPORTED_DID_METHODS, SUPPORTED_SIGNING_ALGORITHMS,
+        AppState, SUPPORTED_SIGNING_ALGORITHMS,
     },
     stronghold::StrongholdManager,
     subject::subject,
@@ -68,7 +69,7 @@ pub async fn load_ferris_profile() -> Result<AppState, AppError> {
 
     let stronghold_manager = Arc::new(StrongholdManager::create(&password).map_err(StrongholdCreationError)?);
 
-    let subject = subject(stronghold_manager.clone(), password).await;
+    let subject = subject(stronghold_manager.clone(), password, state.holder_keys.active_index).await;
 
     let profile = Profile {
         name: "Ferris".to_string(),
@@ -79,13 +80,13 @@ pub async fn load_ferris_profile() -> Result<AppState, AppError> {
 
     let provider_manager = ProviderManager::new(
         subject.clone(),
-        Vec::from(SUPPORTED_DID_METHODS),
+        available_did_methods(&state.profile_settings.preferred_did_methods, &state.dids),
         Vec::from(SUPPORTED_SIGNING_ALGORITHMS),
     )
     .map_err(OID4VCProviderManagerError)?;
     let wallet: Wallet = Wallet::new(
         subject.clone(),
-        Vec::from(SUPPORTED_DID_METHODS),
+        available_did_methods(&state.profile_settings.preferred_did_methods, &state.dids),
         Vec::from(SUPPORTED_SIGNING_ALGORITHMS),
     )
     .map_err(OID4VCWalletError)?;
@@ -213,6 +214,9 @@ pub async fn load_ferris_profile() -> Result<AppState, AppError> {
             verified: false,
             first_interacted: "2023-09-11T19:53:53.937981+00:00".to_string(),
             last_interacted: "2023-09-11T19:53:53.937981+00:00".to_string(),
+            domain_validation: Default::default(),
+            key_index: 0,
+            roles: vec![TrustedRole::Issuer],
         },
         Connection {
             id: "424313e61e35ca4eeca44aac85dc4764c32d7cf9def83ba15f428c308bf1d181".to_string(),
@@ -222,6 +226,9 @@ pub async fn load_ferris_profile() -> Result<AppState, AppError> {
             verified: true,
             first_interacted: "2024-01-09T07:36:41.382948+00:00".to_string(),
             last_interacted: "2024-01-09T07:36:41.382948+00:00".to_string(),
+            domain_validation: Default::default(),
+            key_index: 0,
+            roles: vec![TrustedRole::Verifier],
         },
         Connection {
             id: "e36236d8d7117ed6c6a5d4e99167a2ee1ccb455e75d5b71cee50b08adcf11ba1".to_string(),
@@ -231,6 +238,9 @@ pub async fn load_ferris_profile() -> Result<AppState, AppError> {
             verified: false,
             first_interacted: "2022-02-03T12:33:54.191824+00:00".to_string(),
             last_interacted: "2023-11-13T19:26:40.049239+00:00".to_string(),
+            domain_validation: Default::default(),
+            key_index: 0,
+            roles: vec![TrustedRole::Verifier],
         },
         Connection {
             id: "a81a51b8ad26bdd333abd791a112bf0e0823d559cadc580218a240238a86c292".to_string(),
@@ -240,6 +250,9 @@ pub async fn load_ferris_profile() -> Result<AppState, AppError> {
             verified: true,
             first_interacted: "2024-01-09T08:45:44.217Z".to_string(),
             last_interacted: "2024-01-09T08:45:44.217Z".to_string(),
+            domain_validation: Default::default(),
+            key_index: 0,
+            roles: vec![TrustedRole::Issuer],
         },
     ]);
 
@@ -1,2 +1,4 @@
+pub mod rotate_keys;
 pub mod set_preferred_keytype;
 pub mod set_preferred_method;
+pub mod set_up_did_web;
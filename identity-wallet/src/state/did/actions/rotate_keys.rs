@@ -0,0 +1,17 @@
+use crate::{
+    reducer,
+    state::{actions::ActionTrait, did::reducers::rotate_keys::rotate_keys, Reducer},
+};
+
+use serde::{Deserialize, Serialize};
+
+/// Action to replace the holder keys by new keys, which changes the DIDs of the holder.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RotateKeys;
+
+#[typetag::serde(name = "[Keys] Rotate")]
+impl ActionTrait for RotateKeys {
+    fn reducers<'a>(&self) -> Vec<Reducer<'a>> {
+        vec![reducer!(rotate_keys)]
+    }
+}
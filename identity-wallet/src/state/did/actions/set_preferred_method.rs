@@ -3,7 +3,6 @@ use crate::{
     state::{actions::ActionTrait, did::reducers::preferred_method::set_preferred_did_method, Reducer},
 };
 
-use did_manager::DidMethod;
 use serde::{Deserialize, Serialize};
 use ts_rs::TS;
 
@@ -11,8 +10,7 @@ use ts_rs::TS;
 #[derive(Serialize, Deserialize, Debug, Clone, TS)]
 #[ts(export, export_to = "bindings/actions/SetPreferredDidMethod.ts")]
 pub struct SetPreferredDidMethod {
-    #[ts(type = "string")]
-    pub method: DidMethod,
+    pub method: String,
 }
 
 #[typetag::serde(name = "[DID] Set preferred method")]
@@ -0,0 +1,21 @@
+use crate::{
+    reducer,
+    state::{actions::ActionTrait, did::reducers::set_up_did_web::set_up_did_web, Reducer},
+};
+
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+/// Set up a `did:web` DID for the given domain and produce its DID document, which needs to be hosted on that domain.
+#[derive(Serialize, Deserialize, Debug, Clone, TS)]
+#[ts(export, export_to = "bindings/actions/SetUpDidWeb.ts")]
+pub struct SetUpDidWeb {
+    pub domain: String,
+}
+
+#[typetag::serde(name = "[DID] Set up did:web")]
+impl ActionTrait for SetUpDidWeb {
+    fn reducers<'a>(&self) -> Vec<Reducer<'a>> {
+        vec![reducer!(set_up_did_web)]
+    }
+}
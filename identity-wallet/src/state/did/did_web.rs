@@ -0,0 +1,151 @@
+use crate::{
+    error::AppError::{self, *},
+    state::{did::holder_keys::HolderKeys, SUPPORTED_SIGNING_ALGORITHMS},
+    subject::Subject,
+};
+
+use chrono::Utc;
+use jsonwebtoken::Algorithm;
+use serde_json::json;
+
+pub const DID_WEB: &str = "did:web";
+
+/// Returns the `did:web` DID of the given domain, which may include a port and a path.
+/// For example, `example.com:8443/users/alice` results in `did:web:example.com%3A8443:users:alice`.
+/// See: https://w3c-ccg.github.io/did-method-web/#create-register
+pub fn did_web(domain: &str) -> Result<String, AppError> {
+    let domain = domain.trim();
+    let url = match domain.contains("://") {
+        true => url::Url::parse(domain),
+        false => url::Url::parse(&format!("https://{domain}")),
+    }
+    .map_err(|_| InvalidDidWebDomainError(domain.to_string()))?;
+
+    let host = url
+        .host_str()
+        .filter(|_| url.scheme() == "https")
+        .ok_or(InvalidDidWebDomainError(domain.to_string()))?;
+
+    let mut did = format!("{DID_WEB}:{host}");
+    if let Some(port) = url.port() {
+        did.push_str(&format!("%3A{port}"));
+    }
+
+    // The DID document itself is not part of the DID.
+    let path = url
+        .path()
+        .trim_end_matches("/did.json")
+        .trim_end_matches("/.well-known");
+    for segment in path.split('/').filter(|segment| !segment.is_empty()) {
+        did.push(':');
+        did.push_str(segment);
+    }
+
+    Ok(did)
+}
+
+/// Returns the URL at which the DID document of a `did:web` DID needs to be hosted.
+pub fn did_web_document_url(did: &str) -> Option<url::Url> {
+    let mut segments = did.strip_prefix(&format!("{DID_WEB}:"))?.split(':');
+    let host = segments.next()?.replace("%3A", ":");
+
+    let path: Vec<&str> = segments.collect();
+    let path = match path.is_empty() {
+        true => ".well-known".to_string(),
+        false => path.join("/"),
+    };
+
+    url::Url::parse(&format!("https://{host}/{path}/did.json")).ok()
+}
+
+/// Returns the fragment of the verification method of the key at the given index that is used for the given algorithm.
+pub fn key_fragment(algorithm: Algorithm, index: u32) -> Option<String> {
+    match algorithm {
+        Algorithm::EdDSA => Some(format!("ed25519-{index}")),
+        Algorithm::ES256 => Some(format!("es256-{index}")),
+        _ => None,
+    }
+}
+
+/// Produces the DID document of a `did:web` DID, containing a verification method for the key of each supported
+/// signing algorithm. The keys are the same Stronghold keys that are used for the other DID methods. Holder keys that
+/// have been replaced by a rotation remain in the DID document until their grace period ends, since credentials are
+/// bound to them.
+pub async fn produce_did_web_document(
+    subject: &Subject,
+    did: &str,
+    holder_keys: &HolderKeys,
+) -> Result<serde_json::Value, AppError> {
+    let mut key_indices = vec![subject.default_key_index];
+    key_indices.extend(holder_keys.usable_retired_holder_keys(Utc::now()));
+
+    let mut verification_methods = vec![];
+    for key_index in key_indices {
+        let keys = subject.with_keys(key_index).await.map_err(DidWebDocumentError)?;
+
+        for algorithm in SUPPORTED_SIGNING_ALGORITHMS {
+            let fragment = key_fragment(*algorithm, key_index).ok_or(DidWebDocumentError(anyhow::anyhow!(
+                "No key available for algorithm {algorithm:?}"
+            )))?;
+            let public_key_jwk = keys.public_key_jwk(*algorithm).await.map_err(DidWebDocumentError)?;
+
+            verification_methods.push(json!({
+                "id": format!("{did}#{fragment}"),
+                "type": "JsonWebKey2020",
+                "controller": did,
+                "publicKeyJwk": public_key_jwk,
+            }));
+        }
+    }
+
+    let verification_method_ids: Vec<_> = verification_methods
+        .iter()
+        .map(|verification_method| verification_method["id"].clone())
+        .collect();
+
+    Ok(json!({
+        "@context": [
+            "https://www.w3.org/ns/did/v1",
+            "https://w3id.org/security/suites/jws-2020/v1"
+        ],
+        "id": did,
+        "verificationMethod": verification_methods,
+        "authentication": verification_method_ids,
+        "assertionMethod": verification_method_ids,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn did_web_is_derived_from_domain() {
+        assert_eq!(did_web("example.com").unwrap(), "did:web:example.com");
+        assert_eq!(did_web("https://example.com/").unwrap(), "did:web:example.com");
+        assert_eq!(
+            did_web("example.com:8443/users/alice").unwrap(),
+            "did:web:example.com%3A8443:users:alice"
+        );
+        assert_eq!(
+            did_web("https://example.com/users/alice/did.json").unwrap(),
+            "did:web:example.com:users:alice"
+        );
+        assert!(did_web("http://example.com").is_err());
+    }
+
+    #[test]
+    fn did_web_document_url_is_derived_from_did() {
+        assert_eq!(
+            did_web_document_url("did:web:example.com").unwrap().as_str(),
+            "https://example.com/.well-known/did.json"
+        );
+        assert_eq!(
+            did_web_document_url("did:web:example.com%3A8443:users:alice")
+                .unwrap()
+                .as_str(),
+            "https://example.com:8443/users/alice/did.json"
+        );
+        assert!(did_web_document_url("did:key:z6Mk").is_none());
+    }
+}
@@ -0,0 +1,141 @@
+use crate::state::core_utils::DateUtils;
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+/// The holder keys in Stronghold that are used for the DIDs of the holder (`AppState.dids`), which can be replaced by
+/// a rotation. The pairwise keys of the connections are retired by a rotation as well, so connections get new pairwise
+/// keys the next time they are interacted with.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq, TS, Default)]
+#[ts(export, export_to = "bindings/did/HolderKeys.ts")]
+#[serde(default)]
+pub struct HolderKeys {
+    /// The index of the keys that are currently used for the DIDs of the holder.
+    pub active_index: u32,
+    /// The keys that have been replaced or retired by a rotation.
+    pub retired: Vec<RetiredKeys>,
+}
+
+/// Keys that have been replaced by a rotation. They remain usable during a grace period, since existing credentials
+/// are bound to them.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq, TS)]
+#[ts(export, export_to = "bindings/did/RetiredKeys.ts")]
+pub struct RetiredKeys {
+    pub index: u32,
+    pub rotated_at: String,
+    pub usable_until: String,
+    /// Whether these are the pairwise keys of a connection rather than the holder keys.
+    #[serde(default)]
+    pub pairwise: bool,
+}
+
+impl HolderKeys {
+    /// Replaces the active keys by the keys at the given index and retires the given pairwise keys of the connections.
+    /// The old keys remain usable during the grace period.
+    pub fn rotate(&mut self, index: u32, pairwise_indices: impl IntoIterator<Item = u32>, grace_period: Duration) {
+        let rotated_at = DateUtils::new_date_string();
+        let usable_until = (Utc::now() + grace_period).to_rfc3339();
+
+        let mut retired = vec![RetiredKeys {
+            index: self.active_index,
+            rotated_at: rotated_at.clone(),
+            usable_until: usable_until.clone(),
+            pairwise: false,
+        }];
+        for pairwise_index in pairwise_indices {
+            if pairwise_index == index
+                || self.is_retired(pairwise_index)
+                || retired.iter().any(|retired_keys| retired_keys.index == pairwise_index)
+            {
+                continue;
+            }
+            retired.push(RetiredKeys {
+                index: pairwise_index,
+                rotated_at: rotated_at.clone(),
+                usable_until: usable_until.clone(),
+                pairwise: true,
+            });
+        }
+
+        self.retired.append(&mut retired);
+        self.active_index = index;
+    }
+
+    /// Returns the indices of the holder keys (not the pairwise keys) that have been replaced by a rotation, but can
+    /// still be used at the given moment.
+    pub fn usable_retired_holder_keys(&self, moment: DateTime<Utc>) -> Vec<u32> {
+        self.retired
+            .iter()
+            .filter(|retired_keys| !retired_keys.pairwise && self.is_usable_at(retired_keys.index, moment))
+            .map(|retired_keys| retired_keys.index)
+            .collect()
+    }
+
+    /// Returns whether the keys at the given index have been replaced by a rotation.
+    pub fn is_retired(&self, index: u32) -> bool {
+        self.retired.iter().any(|retired_keys| retired_keys.index == index)
+    }
+
+    /// Returns whether the keys at the given index can be used at the given moment, which is the case unless their
+    /// grace period has ended. Retired keys whose grace period cannot be read are not usable.
+    pub fn is_usable_at(&self, index: u32, moment: DateTime<Utc>) -> bool {
+        self.retired
+            .iter()
+            .filter(|retired_keys| retired_keys.index == index)
+            .all(|retired_keys| {
+                DateTime::parse_from_rfc3339(&retired_keys.usable_until)
+                    .map(|usable_until| moment < usable_until)
+                    .unwrap_or(false)
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retired_keys_are_usable_during_the_grace_period() {
+        let mut holder_keys = HolderKeys::default();
+        holder_keys.rotate(3, [], Duration::days(30));
+
+        assert_eq!(holder_keys.active_index, 3);
+        assert!(holder_keys.is_retired(0));
+        assert!(!holder_keys.is_retired(3));
+
+        let now = Utc::now();
+        assert!(holder_keys.is_usable_at(0, now));
+        assert!(!holder_keys.is_usable_at(0, now + Duration::days(31)));
+        assert!(holder_keys.is_usable_at(3, now + Duration::days(31)));
+
+        // Retired keys whose grace period cannot be read are not usable.
+        holder_keys.retired[0].usable_until = "invalid".to_string();
+        assert!(!holder_keys.is_usable_at(0, now));
+    }
+
+    #[test]
+    fn rotation_retires_the_pairwise_keys_of_the_connections() {
+        let mut holder_keys = HolderKeys::default();
+        holder_keys.rotate(3, [1, 2, 2, 0], Duration::days(30));
+
+        assert_eq!(holder_keys.active_index, 3);
+        assert!(holder_keys.is_retired(0));
+        assert!(holder_keys.is_retired(1));
+        assert!(holder_keys.is_retired(2));
+        assert_eq!(holder_keys.retired.len(), 3);
+        assert_eq!(holder_keys.usable_retired_holder_keys(Utc::now()), vec![0]);
+
+        // Keys that have been retired before keep their grace period.
+        holder_keys.rotate(4, [1, 3], Duration::days(60));
+
+        assert_eq!(holder_keys.retired.len(), 4);
+        assert_eq!(holder_keys.usable_retired_holder_keys(Utc::now()), vec![0, 3]);
+        assert_eq!(
+            holder_keys.usable_retired_holder_keys(Utc::now() + Duration::days(31)),
+            vec![3]
+        );
+        assert!(holder_keys.is_usable_at(1, Utc::now() + Duration::days(29)));
+        assert!(!holder_keys.is_usable_at(1, Utc::now() + Duration::days(31)));
+    }
+}
@@ -1,3 +1,25 @@
 pub mod actions;
+pub mod did_web;
+pub mod holder_keys;
 pub mod reducers;
 pub mod validate_domain_linkage;
+
+use crate::state::SUPPORTED_DID_METHODS;
+
+use did_web::DID_WEB;
+use std::collections::HashMap;
+
+/// Returns the preferred DID methods that can be used as subject syntax types, in order of preference.
+/// A `did:web` DID can only be used once it has been set up, since its DID document needs to be hosted by the holder.
+pub fn available_did_methods(preferred_did_methods: &[String], dids: &HashMap<String, String>) -> Vec<&'static str> {
+    preferred_did_methods
+        .iter()
+        .filter_map(|method| {
+            SUPPORTED_DID_METHODS
+                .iter()
+                .find(|&&supported| method.as_str() == supported)
+        })
+        .filter(|&&method| method != DID_WEB || dids.contains_key(DID_WEB))
+        .copied()
+        .collect()
+}
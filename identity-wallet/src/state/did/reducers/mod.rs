@@ -1,2 +1,4 @@
 pub mod preferred_keytype;
 pub mod preferred_method;
+pub mod rotate_keys;
+pub mod set_up_did_web;
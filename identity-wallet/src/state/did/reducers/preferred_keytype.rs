@@ -4,69 +4,72 @@ use jsonwebtoken::Algorithm;
 use log::debug;
 
 use crate::{
-    error::AppError,
+    error::AppError::{self, *},
     state::{
         actions::{listen, Action},
         did::actions::set_preferred_keytype::SetPreferredKeyType,
-        profile_settings::ProfileSettings,
-        AppState,
+        profile_settings::{signing_key_type, ProfileSettings},
+        AppState, SUPPORTED_KEY_TYPES,
     },
 };
 
 pub async fn set_preferred_key_type(state: AppState, action: Action) -> Result<AppState, AppError> {
     if let Some(key_type) = listen::<SetPreferredKeyType>(action).map(|payload| payload.key_type) {
+        if !SUPPORTED_KEY_TYPES.contains(&key_type.as_str()) {
+            return Err(UnsupportedKeyTypeError(key_type));
+        }
+
         let mut managers = state.core_utils.managers.lock().await;
 
         let mut preferred_key_types = state.profile_settings.preferred_key_types;
 
         debug!("Order of preferred key types (current): {:?}", preferred_key_types);
 
-        let current_position = preferred_key_types
-            .iter()
-            .position(|k| k == &key_type.to_string())
-            .unwrap();
-
-        let element = preferred_key_types.remove(current_position);
-
-        preferred_key_types.insert(0, element);
+        // Profiles that were created before a key type was supported do not contain it yet, and profiles may still
+        // contain key types that are no longer offered (ES256K).
+        preferred_key_types.retain(|k| k != &key_type && SUPPORTED_KEY_TYPES.contains(&k.as_str()));
+        preferred_key_types.insert(0, key_type.clone());
 
         debug!("Order of preferred key types (updated): {:?}", preferred_key_types);
 
         let identity_manager = managers
             .identity_manager
             .as_mut()
-            .ok_or(AppError::MissingManagerError("identity"))?;
+            .ok_or(MissingManagerError("identity"))?;
 
-        let algorithm = match key_type.as_str() {
-            "EdDSA" => jsonwebtoken::Algorithm::EdDSA,
-            "ES256" => jsonwebtoken::Algorithm::ES256,
-            _ => return Err(AppError::Error("Unsupported key type".to_string())),
-        };
-
-        // Update the (deterministic) DIDs
-        let did_jwk = identity_manager.subject.identifier("did:jwk", algorithm).await.unwrap();
-        let did_key = identity_manager.subject.identifier("did:key", algorithm).await.unwrap();
+        // Update the (deterministic) DIDs. They need to be signed with, so they use the most preferred key type that
+        // can sign.
+        let signing_key_type = signing_key_type(&preferred_key_types);
+        let did_jwk = identity_manager
+            .subject
+            .identifier_for_key_type("did:jwk", signing_key_type)
+            .await
+            .map_err(|e| Error(e.to_string()))?;
+        let did_key = identity_manager
+            .subject
+            .identifier_for_key_type("did:key", signing_key_type)
+            .await
+            .map_err(|e| Error(e.to_string()))?;
 
         let mut dids = state.dids.clone();
         dids.insert("did:jwk".to_string(), did_jwk);
         dids.insert("did:key".to_string(), did_key);
 
-        // Update the Identity Manager
-        let supported_subject_syntax_types = preferred_key_types
+        // Update the Identity Manager.
+        let supported_signing_algorithms: Vec<Algorithm> = preferred_key_types
             .iter()
             .map(String::as_str)
-            .map(Algorithm::from_str)
-            .collect::<Result<Vec<_>, _>>()
-            .map_err(|e| AppError::Error(e.to_string()))?;
+            .filter_map(|key_type| Algorithm::from_str(key_type).ok())
+            .collect();
         identity_manager
             .provider_manager
             .provider
             .supported_signing_algorithms
-            .clone_from(&supported_subject_syntax_types);
+            .clone_from(&supported_signing_algorithms);
         identity_manager
             .wallet
             .proof_signing_alg_values_supported
-            .clone_from(&supported_subject_syntax_types);
+            .clone_from(&supported_signing_algorithms);
 
         drop(managers);
         return Ok(AppState {
@@ -81,3 +84,94 @@ pub async fn set_preferred_key_type(state: AppState, action: Action) -> Result<A
     }
     Ok(state)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        persistence::STRONGHOLD,
+        state::{
+            core_utils::{IdentityManager, Managers},
+            did::available_did_methods,
+            SUPPORTED_SIGNING_ALGORITHMS,
+        },
+        stronghold::StrongholdManager,
+        subject::subject,
+    };
+
+    use oid4vc::{oid4vc_manager::ProviderManager, oid4vci::Wallet};
+    use std::sync::Arc;
+    use tempfile::NamedTempFile;
+
+    #[tokio::test]
+    #[serial_test::serial]
+    async fn es256k_is_not_offered_as_preferred_key_type() {
+        let path = NamedTempFile::new().unwrap().into_temp_path();
+        *STRONGHOLD.lock().unwrap() = path.as_os_str().into();
+        let stronghold_manager = Arc::new(StrongholdManager::create("sup3rSecr3t").unwrap());
+
+        let mut state = AppState::default();
+        let subject = subject(stronghold_manager.clone(), "sup3rSecr3t".to_string(), 0).await;
+        let did_methods = available_did_methods(&state.profile_settings.preferred_did_methods, &state.dids);
+        state.core_utils.managers = Arc::new(tauri::async_runtime::Mutex::new(Managers {
+            stronghold_manager: Some(stronghold_manager),
+            identity_manager: Some(IdentityManager {
+                subject: subject.clone(),
+                provider_manager: ProviderManager::new(
+                    subject.clone(),
+                    did_methods.clone(),
+                    Vec::from(SUPPORTED_SIGNING_ALGORITHMS),
+                )
+                .unwrap(),
+                wallet: Wallet::new(subject.clone(), did_methods, Vec::from(SUPPORTED_SIGNING_ALGORITHMS)).unwrap(),
+            }),
+        }));
+
+        assert!(matches!(
+            set_preferred_key_type(
+                state.clone(),
+                Arc::new(SetPreferredKeyType {
+                    key_type: "ES256K".to_string(),
+                }),
+            )
+            .await,
+            Err(UnsupportedKeyTypeError(_))
+        ));
+
+        // A profile that still prefers ES256K loses it once another key type is preferred.
+        state.profile_settings.preferred_key_types =
+            vec!["ES256K".to_string(), "EdDSA".to_string(), "ES256".to_string()];
+        let state = set_preferred_key_type(
+            state,
+            Arc::new(SetPreferredKeyType {
+                key_type: "ES256".to_string(),
+            }),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            state.profile_settings.preferred_key_types,
+            vec!["ES256".to_string(), "EdDSA".to_string()]
+        );
+        assert_eq!(
+            state.dids["did:key"],
+            subject.identifier_for_key_type("did:key", "ES256").await.unwrap()
+        );
+        assert_eq!(
+            state.dids["did:jwk"],
+            subject.identifier_for_key_type("did:jwk", "ES256").await.unwrap()
+        );
+
+        let managers = state.core_utils.managers.lock().await;
+        assert_eq!(
+            managers
+                .identity_manager
+                .as_ref()
+                .unwrap()
+                .wallet
+                .proof_signing_alg_values_supported,
+            vec![Algorithm::ES256, Algorithm::EdDSA]
+        );
+    }
+}
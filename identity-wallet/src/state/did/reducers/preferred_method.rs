@@ -4,48 +4,46 @@ use log::debug;
 use oid4vc::oid4vc_core::SubjectSyntaxType;
 
 use crate::{
-    error::AppError,
+    error::AppError::{self, *},
     state::{
         actions::{listen, Action},
-        did::actions::set_preferred_method::SetPreferredDidMethod,
+        core_utils::IdentityManager,
+        did::{actions::set_preferred_method::SetPreferredDidMethod, available_did_methods, did_web::DID_WEB},
         profile_settings::ProfileSettings,
-        AppState,
+        AppState, SUPPORTED_DID_METHODS,
     },
 };
 
 pub async fn set_preferred_did_method(state: AppState, action: Action) -> Result<AppState, AppError> {
     if let Some(method) = listen::<SetPreferredDidMethod>(action).map(|payload| payload.method) {
+        if !SUPPORTED_DID_METHODS.contains(&method.as_str()) {
+            return Err(UnsupportedDidMethodError(method));
+        }
+        if method == DID_WEB && !state.dids.contains_key(DID_WEB) {
+            return Err(MissingDidWebError);
+        }
+
         let mut preferred_did_methods = state.profile_settings.preferred_did_methods;
 
         debug!("Order of preferred DID methods (current): {:?}", preferred_did_methods);
 
-        let current_position = preferred_did_methods
-            .iter()
-            .position(|m| m == &method.to_string())
-            .unwrap();
-
-        let element = preferred_did_methods.remove(current_position);
-
-        preferred_did_methods.insert(0, element);
+        // Profiles that were created before a DID method was supported do not contain it yet.
+        preferred_did_methods.retain(|m| m != &method);
+        preferred_did_methods.insert(0, method);
 
         debug!("Order of preferred DID methods (updated): {:?}", preferred_did_methods);
 
         let mut state_guard = state.core_utils.managers.lock().await;
 
-        let identity_manager = state_guard.identity_manager.as_mut().unwrap();
-
-        identity_manager
-            .provider_manager
-            .provider
-            .supported_subject_syntax_types = preferred_did_methods
-            .iter()
-            .map(|m| SubjectSyntaxType::from_str(m).unwrap())
-            .collect();
+        let identity_manager = state_guard
+            .identity_manager
+            .as_mut()
+            .ok_or(MissingManagerError("identity"))?;
 
-        identity_manager.wallet.supported_subject_syntax_types = preferred_did_methods
-            .iter()
-            .map(|m| SubjectSyntaxType::from_str(m).unwrap())
-            .collect();
+        update_supported_did_methods(
+            identity_manager,
+            available_did_methods(&preferred_did_methods, &state.dids),
+        )?;
 
         drop(state_guard);
         return Ok(AppState {
@@ -59,3 +57,23 @@ pub async fn set_preferred_did_method(state: AppState, action: Action) -> Result
     }
     Ok(state)
 }
+
+/// Updates the subject syntax types that are supported by the provider manager and the wallet, in order of preference.
+pub fn update_supported_did_methods(
+    identity_manager: &mut IdentityManager,
+    did_methods: Vec<&str>,
+) -> Result<(), AppError> {
+    let supported_subject_syntax_types = did_methods
+        .into_iter()
+        .map(|m| SubjectSyntaxType::from_str(m).map_err(|_| UnsupportedDidMethodError(m.to_string())))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    identity_manager
+        .provider_manager
+        .provider
+        .supported_subject_syntax_types
+        .clone_from(&supported_subject_syntax_types);
+    identity_manager.wallet.supported_subject_syntax_types = supported_subject_syntax_types;
+
+    Ok(())
+}
@@ -0,0 +1,272 @@
+use crate::{
+    error::AppError::{self, *},
+    persistence::save_did_web_document,
+    state::{
+        actions::{listen, Action},
+        core_utils::{
+            history_event::{EventType, HistoryCredential, HistoryEvent},
+            DateUtils, IdentityManager,
+        },
+        did::{
+            actions::rotate_keys::RotateKeys,
+            available_did_methods,
+            did_web::{did_web_document_url, produce_did_web_document, DID_WEB},
+        },
+        profile_settings::signing_key_type,
+        AppState,
+    },
+    subject::subject,
+};
+
+use log::info;
+use oid4vc::oid4vc_manager::ProviderManager;
+use oid4vc::oid4vci::Wallet;
+
+/// Generates new holder keys at the next index in Stronghold and replaces the `Subject` of the identity manager, so
+/// that the DIDs of the holder are derived from the new keys. The pairwise keys of the connections are retired as well.
+/// The old keys remain usable during the grace period, and the credentials bound to them are marked so they can be
+/// issued again.
+pub async fn rotate_keys(state: AppState, action: Action) -> Result<AppState, AppError> {
+    if listen::<RotateKeys>(action).is_some() {
+        let mut state_guard = state.core_utils.managers.lock().await;
+        let stronghold_manager = state_guard
+            .stronghold_manager
+            .clone()
+            .ok_or(MissingManagerError("stronghold"))?;
+        let identity_manager = state_guard
+            .identity_manager
+            .as_ref()
+            .ok_or(MissingManagerError("identity"))?;
+
+        let key_index = stronghold_manager.next_key_index().map_err(KeyRotationError)?;
+        stronghold_manager.generate_keys(key_index).map_err(KeyRotationError)?;
+        info!(
+            "rotating the holder keys from index {} to index {}",
+            state.holder_keys.active_index, key_index
+        );
+
+        let subject = subject(
+            stronghold_manager.clone(),
+            identity_manager.subject.password.to_string(),
+            key_index,
+        )
+        .await;
+        let did_web = identity_manager.subject.did_web.read().unwrap().clone();
+        let supported_signing_algorithms = identity_manager.wallet.proof_signing_alg_values_supported.clone();
+
+        let provider_manager = ProviderManager::new(
+            subject.clone(),
+            available_did_methods(&state.profile_settings.preferred_did_methods, &state.dids),
+            supported_signing_algorithms.clone(),
+        )
+        .map_err(OID4VCProviderManagerError)?;
+        let wallet: Wallet = Wallet::new(
+            subject.clone(),
+            available_did_methods(&state.profile_settings.preferred_did_methods, &state.dids),
+            supported_signing_algorithms,
+        )
+        .map_err(OID4VCWalletError)?;
+
+        // Update the (deterministic) DIDs
+        let key_type = signing_key_type(&state.profile_settings.preferred_key_types);
+        let mut dids = state.dids;
+        for method in ["did:jwk", "did:key"] {
+            let did = subject
+                .identifier_for_key_type(method, key_type)
+                .await
+                .map_err(|e| Error(e.to_string()))?;
+            dids.insert(method.to_string(), did);
+        }
+
+        // The pairwise keys of the connections and of their credentials are retired along with the holder keys, so the
+        // connections get new pairwise keys the next time they are interacted with.
+        let pairwise_indices: Vec<u32> = state
+            .connections
+            .0
+            .iter()
+            .map(|connection| connection.key_index)
+            .chain(state.credentials.iter().map(|credential| credential.metadata.key_index))
+            .collect();
+        let old_holder_keys = state.holder_keys;
+        let mut holder_keys = old_holder_keys.clone();
+        holder_keys.rotate(
+            key_index,
+            pairwise_indices,
+            state.profile_settings.key_rotation_grace_period(),
+        );
+
+        // The `did:web` DID stays the same, but its DID document needs to be hosted again with the new keys.
+        if let Some(did) = did_web {
+            let document = produce_did_web_document(&subject, &did, &holder_keys).await?;
+            let path = save_did_web_document(&document).await.map_err(DidWebDocumentError)?;
+            info!(
+                "DID document of {} saved to {}, it needs to be hosted again at {:?}",
+                did,
+                path.display(),
+                did_web_document_url(&did).map(|url| url.to_string())
+            );
+            dids.insert(DID_WEB.to_string(), did.clone());
+            *subject.did_web.write().unwrap() = Some(did);
+        }
+
+        let mut credentials = state.credentials;
+        credentials
+            .iter_mut()
+            .for_each(|credential| credential.metadata.update_key_state(&holder_keys));
+
+        // History
+        let mut history = state.history;
+        history.push(HistoryEvent {
+            connection_name: String::new(),
+            event_type: EventType::KeysRotated,
+            connection_id: String::new(),
+            date: DateUtils::new_date_string(),
+            credentials: credentials
+                .iter()
+                .filter(|credential| {
+                    let key_index = credential.metadata.key_index;
+                    !old_holder_keys.is_retired(key_index) && holder_keys.is_retired(key_index)
+                })
+                .map(|credential| HistoryCredential {
+                    title: credential.display_name.clone(),
+                    issuer_name: credential.issuer_name.clone(),
+                    id: credential.id.clone(),
+                })
+                .collect(),
+        });
+
+        state_guard.identity_manager.replace(IdentityManager {
+            subject,
+            provider_manager,
+            wallet,
+        });
+
+        drop(state_guard);
+        return Ok(AppState {
+            dids,
+            holder_keys,
+            credentials,
+            history,
+            current_user_prompt: None,
+            ..state
+        });
+    }
+
+    Ok(state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        persistence::{ASSETS_DIR, STRONGHOLD},
+        state::{
+            connections::{pairwise::activate_connection_keys, trust_registry::TrustedRole, Connections},
+            core_utils::Managers,
+            credentials::{CredentialMetadata, DisplayCredential},
+            SUPPORTED_SIGNING_ALGORITHMS,
+        },
+        stronghold::StrongholdManager,
+    };
+
+    use std::sync::Arc;
+    use tempfile::{NamedTempFile, TempDir};
+
+    fn credential(id: &str, key_index: u32) -> DisplayCredential {
+        DisplayCredential {
+            id: id.to_string(),
+            metadata: CredentialMetadata {
+                key_index,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    #[serial_test::serial]
+    async fn rotation_retires_the_holder_keys_and_the_pairwise_keys() {
+        let path = NamedTempFile::new().unwrap().into_temp_path();
+        *STRONGHOLD.lock().unwrap() = path.as_os_str().into();
+        let assets_dir = TempDir::new().unwrap();
+        *ASSETS_DIR.lock().unwrap() = assets_dir.path().to_path_buf();
+        let stronghold_manager = Arc::new(StrongholdManager::create("sup3rSecr3t").unwrap());
+        stronghold_manager.generate_keys(1).unwrap();
+
+        let mut state = AppState::default();
+        let subject = subject(stronghold_manager.clone(), "sup3rSecr3t".to_string(), 0).await;
+        *subject.did_web.write().unwrap() = Some("did:web:example.com".to_string());
+        state
+            .dids
+            .insert(DID_WEB.to_string(), "did:web:example.com".to_string());
+        let did_methods = available_did_methods(&state.profile_settings.preferred_did_methods, &state.dids);
+        state.core_utils.managers = Arc::new(tauri::async_runtime::Mutex::new(Managers {
+            stronghold_manager: Some(stronghold_manager),
+            identity_manager: Some(IdentityManager {
+                subject: subject.clone(),
+                provider_manager: ProviderManager::new(
+                    subject.clone(),
+                    did_methods.clone(),
+                    Vec::from(SUPPORTED_SIGNING_ALGORITHMS),
+                )
+                .unwrap(),
+                wallet: Wallet::new(subject.clone(), did_methods, Vec::from(SUPPORTED_SIGNING_ALGORITHMS)).unwrap(),
+            }),
+        }));
+
+        // A connection with pairwise keys and a credential bound to them, and a credential bound to the holder keys.
+        let mut connections = Connections::new();
+        connections
+            .update_or_insert("example.com", "Example", None, TrustedRole::Issuer)
+            .key_index = 1;
+        state.connections = connections;
+        state.credentials = vec![credential("holder", 0), credential("pairwise", 1)];
+
+        let state = rotate_keys(state, Arc::new(RotateKeys)).await.unwrap();
+
+        assert_eq!(state.holder_keys.active_index, 2);
+        assert!(state.holder_keys.is_retired(0));
+        assert!(state.holder_keys.is_retired(1));
+        assert!(state
+            .credentials
+            .iter()
+            .all(|credential| credential.metadata.key_retired));
+        assert_eq!(state.history.last().unwrap().credentials.len(), 2);
+
+        // The connection gets new pairwise keys the next time it is interacted with.
+        let managers = state.core_utils.managers.lock().await;
+        let identity_manager = managers.identity_manager.as_ref().unwrap();
+        let (key_index, _) = activate_connection_keys(
+            &state.connections,
+            "example.com",
+            "Example",
+            identity_manager,
+            &state.holder_keys,
+            available_did_methods(&state.profile_settings.preferred_did_methods, &state.dids),
+        )
+        .await
+        .unwrap();
+        assert_eq!(key_index, 3);
+        drop(managers);
+
+        // The DID document contains the new keys and, during the grace period, the replaced holder keys, but not the
+        // pairwise keys.
+        let document: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(assets_dir.path().join("did.json")).unwrap()).unwrap();
+        let verification_method_ids: Vec<&str> = document["verificationMethod"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|verification_method| verification_method["id"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            verification_method_ids,
+            vec![
+                "did:web:example.com#ed25519-2",
+                "did:web:example.com#es256-2",
+                "did:web:example.com#ed25519-0",
+                "did:web:example.com#es256-0",
+            ]
+        );
+    }
+}
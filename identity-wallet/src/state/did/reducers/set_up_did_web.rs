@@ -0,0 +1,66 @@
+use crate::{
+    error::AppError::{self, *},
+    persistence::save_did_web_document,
+    state::{
+        actions::{listen, Action},
+        did::{
+            actions::set_up_did_web::SetUpDidWeb,
+            available_did_methods,
+            did_web::{did_web, did_web_document_url, produce_did_web_document, DID_WEB},
+            reducers::preferred_method::update_supported_did_methods,
+        },
+        profile_settings::ProfileSettings,
+        AppState,
+    },
+};
+
+use log::info;
+
+/// Produces the `did:web` DID of the given domain and exports its DID document as `did.json`, so it can be hosted.
+pub async fn set_up_did_web(state: AppState, action: Action) -> Result<AppState, AppError> {
+    if let Some(domain) = listen::<SetUpDidWeb>(action).map(|payload| payload.domain) {
+        let did = did_web(&domain)?;
+
+        let mut state_guard = state.core_utils.managers.lock().await;
+        let identity_manager = state_guard
+            .identity_manager
+            .as_mut()
+            .ok_or(MissingManagerError("identity"))?;
+
+        let document = produce_did_web_document(&identity_manager.subject, &did, &state.holder_keys).await?;
+        let path = save_did_web_document(&document).await.map_err(DidWebDocumentError)?;
+
+        info!(
+            "DID document of {} saved to {}, it needs to be hosted at {:?}",
+            did,
+            path.display(),
+            did_web_document_url(&did).map(|url| url.to_string())
+        );
+
+        *identity_manager.subject.did_web.write().unwrap() = Some(did.clone());
+
+        let mut dids = state.dids;
+        dids.insert(DID_WEB.to_string(), did);
+
+        // Profiles that were created before `did:web` was supported do not contain it yet.
+        let mut preferred_did_methods = state.profile_settings.preferred_did_methods;
+        if !preferred_did_methods.iter().any(|method| method == DID_WEB) {
+            preferred_did_methods.push(DID_WEB.to_string());
+        }
+
+        update_supported_did_methods(identity_manager, available_did_methods(&preferred_did_methods, &dids))?;
+
+        drop(state_guard);
+        return Ok(AppState {
+            dids,
+            profile_settings: ProfileSettings {
+                preferred_did_methods,
+                ..state.profile_settings
+            },
+            current_user_prompt: None,
+            ..state
+        });
+    }
+
+    Ok(state)
+}
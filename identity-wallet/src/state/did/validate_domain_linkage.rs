@@ -1,6 +1,7 @@
 use std::str::FromStr;
 
 use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use chrono::{DateTime, Duration, Utc};
 use did_manager::Resolver;
 use identity_credential::domain_linkage::{DomainLinkageConfiguration, JwtDomainLinkageValidator};
 use identity_iota::{
@@ -16,14 +17,18 @@ use log::info;
 use serde::{Deserialize, Serialize};
 use ts_rs::TS;
 
-#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, TS, Default)]
+/// While the linkage cannot be validated again, the last known result of a domain validation is kept for at most this
+/// many times its time-to-live.
+const STALE_TTL_MULTIPLE: i32 = 7;
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq, TS, Default)]
 #[ts(export, export_to = "bindings/user_prompt/ValidationResult.ts")]
 pub struct ValidationResult {
     pub(crate) status: ValidationStatus,
     pub(crate) message: Option<String>,
 }
 
-#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, TS, Default)]
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq, TS, Default)]
 #[ts(export, export_to = "bindings/user_prompt/ValidationStatus.ts")]
 pub enum ValidationStatus {
     Success,
@@ -70,18 +75,39 @@ impl JwsVerifier for Verifier {
 
 /// https://wiki.iota.org/identity.rs/how-tos/domain-linkage/create-and-verify/#verifying-a-did-and-domain-linkage
 pub async fn validate_domain_linkage(url: url::Url, did: &str) -> ValidationResult {
+    validate_domain_linkage_with_configuration(url, did).await.0
+}
+
+// Validates the domain linkage and also returns the Domain Linkage Credential (JWT) of the DID, if the DID
+// configuration contains one.
+async fn validate_domain_linkage_with_configuration(url: url::Url, did: &str) -> (ValidationResult, Option<String>) {
     let did_configuration_result = fetch_configuration(url.clone()).await;
 
     let domain_linkage_configuration = match did_configuration_result {
         Ok(did_config) => did_config,
         Err(e) => {
-            return ValidationResult {
-                status: ValidationStatus::Unknown,
-                message: Some(e.to_string()),
-            };
+            return (
+                ValidationResult {
+                    status: ValidationStatus::Unknown,
+                    message: Some(e.to_string()),
+                },
+                None,
+            );
         }
     };
 
+    let did_configuration = domain_linkage_configuration
+        .linked_dids()
+        .iter()
+        .map(|jwt| jwt.as_str().to_string())
+        .find(|jwt| {
+            jwt.split('.')
+                .nth(1)
+                .and_then(|payload| URL_SAFE_NO_PAD.decode(payload).ok())
+                .and_then(|payload| serde_json::from_slice::<serde_json::Value>(&payload).ok())
+                .is_some_and(|claims| claims["iss"] == did)
+        });
+
     let validator = JwtDomainLinkageValidator::with_signature_verifier(Verifier);
 
     let resolver = Resolver::new().await;
@@ -89,10 +115,13 @@ pub async fn validate_domain_linkage(url: url::Url, did: &str) -> ValidationResu
     let document = match resolver.resolve(did).await {
         Ok(document) => document,
         Err(e) => {
-            return ValidationResult {
-                status: ValidationStatus::Unknown,
-                message: Some(e.to_string()),
-            };
+            return (
+                ValidationResult {
+                    status: ValidationStatus::Unknown,
+                    message: Some(e.to_string()),
+                },
+                did_configuration,
+            );
         }
     };
 
@@ -105,7 +134,7 @@ pub async fn validate_domain_linkage(url: url::Url, did: &str) -> ValidationResu
         &JwtCredentialValidationOptions::default(),
     );
 
-    if res.is_ok() {
+    let validation_result = if res.is_ok() {
         ValidationResult {
             status: ValidationStatus::Success,
             message: None,
@@ -115,6 +144,84 @@ pub async fn validate_domain_linkage(url: url::Url, did: &str) -> ValidationResu
             status: ValidationStatus::Failure,
             message: res.err().map(|e| e.to_string()),
         }
+    };
+
+    (validation_result, did_configuration)
+}
+
+/// The result of validating the linkage between a DID and the origin of a connection, which is stored on the connection
+/// so it can be reused for a while (and shown when the domain cannot be reached).
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq, TS, Default)]
+#[ts(export, export_to = "bindings/connections/DomainValidation.ts")]
+#[serde(default)]
+pub struct DomainValidation {
+    pub result: ValidationResult,
+    #[ts(optional)]
+    pub did: Option<String>,
+    #[ts(optional)]
+    pub origin: Option<String>,
+    /// The moment of the validation (RFC 3339).
+    #[ts(optional)]
+    pub validated_at: Option<String>,
+    /// The Domain Linkage Credential (JWT) of the `did` in the DID configuration of the `origin`.
+    #[ts(optional)]
+    pub did_configuration: Option<String>,
+}
+
+impl DomainValidation {
+    /// Validates the linkage between the DID and the origin of the URL.
+    pub async fn validate(url: url::Url, did: &str) -> Self {
+        let origin = url.origin().ascii_serialization();
+        let (result, did_configuration) = validate_domain_linkage_with_configuration(url, did).await;
+
+        Self {
+            result,
+            did: Some(did.to_string()),
+            origin: Some(origin),
+            validated_at: Some(Utc::now().to_rfc3339()),
+            did_configuration,
+        }
+    }
+
+    /// Reuses the `stored` validation when it concerns the same DID and origin and is younger than the `ttl`. Otherwise
+    /// the linkage is validated again. If the outcome is inconclusive (e.g. when the device is offline), the last known
+    /// validation is kept, but only until it is `STALE_TTL_MULTIPLE` times older than the `ttl`.
+    pub async fn validate_or_reuse(stored: Option<&DomainValidation>, url: url::Url, did: &str, ttl: Duration) -> Self {
+        let stored = stored.filter(|stored| stored.concerns(&url, did));
+
+        if let Some(stored) = stored.filter(|stored| stored.is_younger_than(ttl)) {
+            info!("reusing domain validation of {} from {:?}", did, stored.validated_at);
+            return stored.clone();
+        }
+
+        let domain_validation = Self::validate(url, did).await;
+
+        match stored {
+            Some(stored)
+                if domain_validation.result.status == ValidationStatus::Unknown
+                    && stored.result.status != ValidationStatus::Unknown
+                    && stored.is_younger_than(ttl * STALE_TTL_MULTIPLE) =>
+            {
+                info!(
+                    "domain validation of {} is inconclusive, keeping the last known result",
+                    did
+                );
+                stored.clone()
+            }
+            _ => domain_validation,
+        }
+    }
+
+    /// Returns `true` if this is a validation of the linkage between the DID and the origin of the URL.
+    pub fn concerns(&self, url: &url::Url, did: &str) -> bool {
+        self.did.as_deref() == Some(did) && self.origin.as_deref() == Some(url.origin().ascii_serialization().as_str())
+    }
+
+    fn is_younger_than(&self, ttl: Duration) -> bool {
+        self.validated_at
+            .as_deref()
+            .and_then(|validated_at| DateTime::parse_from_rfc3339(validated_at).ok())
+            .is_some_and(|validated_at| Utc::now() - validated_at.with_timezone(&Utc) < ttl)
     }
 }
 
@@ -403,4 +510,54 @@ mod tests {
 
         assert!(Verifier.verify(input, &jwk).is_ok());
     }
+
+    #[tokio::test]
+    async fn recent_domain_validation_is_reused() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path(".well-known/did-configuration.json"))
+            .respond_with(ResponseTemplate::new(404))
+            .expect(2)
+            .mount(&mock_server)
+            .await;
+
+        let url = url::Url::parse(&mock_server.uri()).unwrap();
+        let stored_at = |validated_at: DateTime<Utc>| DomainValidation {
+            result: ValidationResult {
+                status: ValidationStatus::Success,
+                message: None,
+            },
+            did: Some("did:foo:bar".to_string()),
+            origin: Some(url.origin().ascii_serialization()),
+            validated_at: Some(validated_at.to_rfc3339()),
+            did_configuration: Some(LINKED_DID_JWT.to_string()),
+        };
+        let stored = stored_at(Utc::now() - Duration::hours(2));
+
+        // The stored validation is recent enough, so the DID configuration is not fetched.
+        assert_eq!(
+            DomainValidation::validate_or_reuse(Some(&stored), url.clone(), "did:foo:bar", Duration::hours(3)).await,
+            stored
+        );
+
+        // The stored validation has expired, but the new one is inconclusive, so the last known result is kept.
+        assert_eq!(
+            DomainValidation::validate_or_reuse(Some(&stored), url.clone(), "did:foo:bar", Duration::hours(1)).await,
+            stored
+        );
+
+        // A last known result that has long expired is no longer kept.
+        let stale = stored_at(Utc::now() - Duration::hours(STALE_TTL_MULTIPLE.into()) - Duration::hours(1));
+        assert_eq!(
+            DomainValidation::validate_or_reuse(Some(&stale), url.clone(), "did:foo:bar", Duration::hours(1))
+                .await
+                .result
+                .status,
+            ValidationStatus::Unknown
+        );
+
+        // A validation of another DID is not reused.
+        assert!(!stored.concerns(&url, "did:foo:baz"));
+    }
 }
@@ -1,4 +1,5 @@
 pub mod actions;
+pub mod backup;
 pub mod common;
 pub mod connections;
 pub mod core_utils;
@@ -17,7 +18,8 @@ use self::{
     user_prompt::CurrentUserPrompt,
 };
 use crate::state::core_utils::history_event::HistoryEvent;
-use crate::state::credentials::DisplayCredential;
+use crate::state::credentials::{deferred_credential::PendingCredential, DisplayCredential};
+use crate::state::did::holder_keys::HolderKeys;
 use crate::{error::AppError, state::connections::Connections};
 
 use derivative::Derivative;
@@ -75,7 +77,11 @@ impl AppStateContainer {
 }
 
 pub const SUPPORTED_SIGNING_ALGORITHMS: &[Algorithm] = &[Algorithm::EdDSA, Algorithm::ES256];
-pub const SUPPORTED_DID_METHODS: &[&str] = &["did:jwk", "did:key"];
+/// The types of the holder keys that can be preferred. Stronghold also contains an ES256K key for every key index, but
+/// it is not offered, since `jsonwebtoken` has no `Algorithm` for it and it can therefore not sign credential requests,
+/// presentations or mdoc device responses.
+pub const SUPPORTED_KEY_TYPES: &[&str] = &["EdDSA", "ES256"];
+pub const SUPPORTED_DID_METHODS: &[&str] = &["did:jwk", "did:key", "did:web"];
 
 /// The inner state of the application managed by Tauri. When the state is serialized in order to be sent to the
 /// frontend, the `managers` and `active_connection_request` fields are skipped.
@@ -85,8 +91,12 @@ pub const SUPPORTED_DID_METHODS: &[&str] = &["did:jwk", "did:key"];
 #[serde(default)]
 pub struct AppState {
     pub dids: HashMap<String, String>,
+    /// The index of the holder keys in Stronghold that are used for the `dids`, and the keys they have replaced.
+    pub holder_keys: HolderKeys,
     pub connections: Connections,
     pub credentials: Vec<DisplayCredential>,
+    /// Credentials that have been requested, but which have not been issued yet by the credential issuer.
+    pub pending_credentials: Vec<PendingCredential>,
     pub search_results: SearchResults,
     /// This field contains utils needed for the backend to perform its tasks.
     #[serde(skip)]
@@ -3,9 +3,12 @@ pub mod reducers;
 
 use super::FeatTrait;
 
-use crate::state::{SUPPORTED_DID_METHODS, SUPPORTED_SIGNING_ALGORITHMS};
+use crate::state::{connections::trust_registry::TrustListSource, SUPPORTED_DID_METHODS, SUPPORTED_KEY_TYPES};
 
+use chrono::Duration;
+use jsonwebtoken::Algorithm;
 use serde::{Deserialize, Serialize};
+use std::str::FromStr;
 use strum::{EnumString, IntoStaticStr};
 use ts_rs::TS;
 
@@ -19,6 +22,12 @@ pub struct ProfileSettings {
     pub preferred_did_methods: Vec<String>,
     pub preferred_key_types: Vec<String>,
     pub sorting_preferences: SortingPreferences,
+    /// The sources (URLs or file paths) of the trust lists of trusted issuers and verifiers, and their operators.
+    pub trust_lists: Vec<TrustListSource>,
+    /// The number of hours during which the domain validation of a connection is reused.
+    pub domain_validation_ttl_hours: u32,
+    /// The number of days during which the holder keys remain usable after they have been replaced by a rotation.
+    pub key_rotation_grace_period_days: u32,
 }
 
 #[typetag::serde(name = "profile_settings")]
@@ -30,9 +39,9 @@ impl Default for ProfileSettings {
             locale: Locale::en_US,
             profile: None,
             preferred_did_methods: SUPPORTED_DID_METHODS.iter().map(|&method| method.to_string()).collect(),
-            preferred_key_types: SUPPORTED_SIGNING_ALGORITHMS
+            preferred_key_types: SUPPORTED_KEY_TYPES
                 .iter()
-                .map(|algorithm| serde_json::to_string(algorithm).unwrap().replace('"', ""))
+                .map(|&key_type| key_type.to_string())
                 .collect(),
             sorting_preferences: SortingPreferences {
                 credentials: Preferences {
@@ -44,10 +53,35 @@ impl Default for ProfileSettings {
                     reverse: false,
                 },
             },
+            trust_lists: vec![],
+            domain_validation_ttl_hours: 24,
+            key_rotation_grace_period_days: 90,
         }
     }
 }
 
+impl ProfileSettings {
+    /// Returns the time during which the domain validation of a connection is reused.
+    pub fn domain_validation_ttl(&self) -> Duration {
+        Duration::hours(self.domain_validation_ttl_hours.into())
+    }
+
+    /// Returns the time during which the holder keys remain usable after they have been replaced by a rotation.
+    pub fn key_rotation_grace_period(&self) -> Duration {
+        Duration::days(self.key_rotation_grace_period_days.into())
+    }
+}
+
+/// Returns the most preferred of the given key types that can be used for signing, which is the key type of the
+/// `did:jwk` and `did:key` DIDs. ES256K keys cannot sign, since `jsonwebtoken` has no `Algorithm` for them.
+pub fn signing_key_type(key_types: &[String]) -> &str {
+    key_types
+        .iter()
+        .map(String::as_str)
+        .find(|key_type| Algorithm::from_str(key_type).is_ok())
+        .unwrap_or("EdDSA")
+}
+
 /// A profile of the current user.
 #[derive(Clone, Serialize, Debug, Deserialize, TS, PartialEq, Default)]
 #[ts(export, export_to = "bindings/profile_settings/Profile.ts")]
@@ -104,6 +138,7 @@ pub enum CredentialSortMethod {
     NameAZ,
     IssueDateNewOld,
     AddedDateNewOld,
+    ExpiryDateSoonest,
 }
 
 #[derive(Clone, Serialize, Debug, Deserialize, TS, Default, PartialEq, EnumString)]
@@ -138,6 +173,15 @@ mod tests {
 
     use std::sync::Arc;
 
+    #[test]
+    fn signing_key_type_skips_key_types_that_cannot_sign() {
+        let key_types = |key_types: &[&str]| key_types.iter().map(ToString::to_string).collect::<Vec<_>>();
+
+        assert_eq!(signing_key_type(&key_types(&["ES256", "EdDSA", "ES256K"])), "ES256");
+        assert_eq!(signing_key_type(&key_types(&["ES256K", "EdDSA", "ES256"])), "EdDSA");
+        assert_eq!(signing_key_type(&key_types(&["ES256K"])), "EdDSA");
+    }
+
     #[tokio::test]
     async fn test_set_locale() {
         let mut app_state = AppState::default();
@@ -3,9 +3,10 @@ use crate::{
     state::{
         actions::{listen, Action},
         core_utils::IdentityManager,
+        did::available_did_methods,
         profile_settings::{actions::create_new::CreateNew, Profile, ProfileSettings},
         user_prompt::CurrentUserPrompt,
-        AppState, SUPPORTED_DID_METHODS, SUPPORTED_SIGNING_ALGORITHMS,
+        AppState, SUPPORTED_SIGNING_ALGORITHMS,
     },
     stronghold::StrongholdManager,
     subject::subject,
@@ -34,17 +35,17 @@ pub async fn create_identity(state: AppState, action: Action) -> Result<AppState
             .as_ref()
             .ok_or(MissingManagerError("stronghold"))?;
 
-        let subject = subject(stronghold_manager.clone(), password).await;
+        let subject = subject(stronghold_manager.clone(), password, state.holder_keys.active_index).await;
 
         let provider_manager = ProviderManager::new(
             subject.clone(),
-            Vec::from(SUPPORTED_DID_METHODS),
+            available_did_methods(&state.profile_settings.preferred_did_methods, &state.dids),
             Vec::from(SUPPORTED_SIGNING_ALGORITHMS),
         )
         .map_err(OID4VCProviderManagerError)?;
         let wallet: Wallet = Wallet::new(
             subject.clone(),
-            Vec::from(SUPPORTED_DID_METHODS),
+            available_did_methods(&state.profile_settings.preferred_did_methods, &state.dids),
             Vec::from(SUPPORTED_SIGNING_ALGORITHMS),
         )
         .map_err(OID4VCWalletError)?;
@@ -87,6 +87,11 @@ pub async fn sort_credentials(state: AppState, _action: Action) -> Result<AppSta
         CredentialSortMethod::AddedDateNewOld => credentials.sort_by(|a: &DisplayCredential, b: &DisplayCredential| {
             a.metadata.date_added.cmp(&b.metadata.date_added).reverse()
         }),
+        CredentialSortMethod::ExpiryDateSoonest => {
+            credentials.sort_by(|a: &DisplayCredential, b: &DisplayCredential| {
+                a.metadata.expiry_date().cmp(&b.metadata.expiry_date())
+            })
+        }
     };
 
     if preferences.reverse {
@@ -107,6 +112,17 @@ pub async fn sort_credentials(state: AppState, _action: Action) -> Result<AppSta
         credentials = credentials_with_date_issued
     };
 
+    // Credentials without an expiry date never expire, so they are sorted alphabetically and added to the bottom.
+    if preferences.sort_method == CredentialSortMethod::ExpiryDateSoonest {
+        let (mut credentials_with_date_expires, mut credentials_without_date_expires) = credentials
+            .into_iter()
+            .partition::<Vec<_>, _>(|credential| credential.metadata.expiry_date().is_some());
+
+        credentials_without_date_expires.sort_by(name_az);
+        credentials_with_date_expires.append(&mut credentials_without_date_expires);
+        credentials = credentials_with_date_expires
+    };
+
     // current_user_prompt is not set to None,
     // as this reducer is often used in combination with reducers that need to send a user_prompt to the frontend.
     Ok(AppState { credentials, ..state })
@@ -276,6 +292,30 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_credentials_sorting_expiry_date_soonest() {
+        let mut state = init_credential_names("C".to_string(), "A".to_string(), "B".to_string());
+        state.credentials[0].metadata.date_expires = Some("2030-01-01T00:00:00Z".to_string());
+        state.credentials[2].metadata.date_expires = Some("2025-01-01T00:00:00Z".to_string());
+        let action = Arc::new(UpdateSortingPreference {
+            credential_sorting: Some(CredentialSortMethod::ExpiryDateSoonest),
+            reverse: Some(false),
+            ..Default::default()
+        });
+
+        let result = update_sorting_preference(state, action.clone()).await.unwrap();
+        let result = sort_credentials(result, action).await.unwrap();
+
+        assert_eq!(
+            result
+                .credentials
+                .iter()
+                .map(|x| x.display_name.clone())
+                .collect::<Vec<String>>(),
+            vec!["B".to_string(), "C".to_string(), "A".to_string()]
+        );
+    }
+
     // sort_connections tests //
 
     #[tokio::test]
@@ -4,20 +4,30 @@ use crate::{
     state::{
         actions::{listen, Action},
         connections::reducers::handle_siopv2_authorization_request::get_siopv2_client_name_and_logo_uri,
-        core_utils::{ConnectionRequest, CoreUtils},
-        credentials::reducers::handle_oid4vp_authorization_request::{
-            get_oid4vp_client_name_and_logo_uri, OID4VPClientMetadata,
+        connections::trust_registry::{trust_status, TrustedRole},
+        core_utils::{
+            client_id_scheme::{request_object_by_value, validate_client_id_scheme},
+            client_metadata::resolve_client_metadata_resource,
+            ConnectionRequest, CoreUtils,
         },
-        did::validate_domain_linkage::validate_domain_linkage,
+        credentials::{
+            presentation_exchange::{input_descriptor_options, requested_input_descriptors},
+            reducers::handle_oid4vp_authorization_request::{
+                get_oid4vp_client_name_and_logo_uri, OID4VPClientMetadata,
+            },
+            status::update_statuses,
+        },
+        did::validate_domain_linkage::{DomainValidation, ValidationResult, ValidationStatus},
         qr_code::actions::qrcode_scanned::QrCodeScanned,
         user_prompt::CurrentUserPrompt,
         AppState,
     },
 };
 
+use chrono::Utc;
 use log::{debug, info};
 use oid4vc::oid4vc_core::authorization_request::{AuthorizationRequest, Object};
-use oid4vc::oid4vp::{evaluate_input, oid4vp::OID4VP};
+use oid4vc::oid4vp::oid4vp::OID4VP;
 use oid4vc::siopv2::siopv2::SIOPv2;
 
 // Reads the request url from the payload and validates it.
@@ -33,22 +43,31 @@ pub async fn read_authorization_request(state: AppState, action: Action) -> Resu
             .stronghold_manager
             .as_ref()
             .ok_or(MissingManagerError("stronghold"))?;
-        let provider_manager = &state_guard
+        let identity_manager = state_guard
             .identity_manager
             .as_ref()
-            .ok_or(MissingManagerError("identity"))?
-            .provider_manager;
+            .ok_or(MissingManagerError("identity"))?;
+        let provider_manager = &identity_manager.provider_manager;
+
+        // The request object is only fetched once, so the request that is parsed is the same one that is validated.
+        let qr_code_scanned = request_object_by_value(&qr_code_scanned)
+            .await
+            .map_err(|_| InvalidQRCodeError(qr_code_scanned.clone()))?;
 
         let generic_authorization_request = provider_manager
             .validate_request(qr_code_scanned.clone())
             .await
-            .map_err(|_| InvalidQRCodeError(qr_code_scanned))?;
+            .map_err(|_| InvalidQRCodeError(qr_code_scanned.clone()))?;
 
         if let Result::Ok(siopv2_authorization_request) =
             AuthorizationRequest::<Object<SIOPv2>>::from_generic(&generic_authorization_request)
         {
             let redirect_uri = siopv2_authorization_request.body.redirect_uri.to_string();
 
+            // The client metadata needs to be resolved before the client's name and logo can be determined.
+            let client_metadata_error =
+                resolve_client_metadata_resource(&siopv2_authorization_request.body.extension.client_metadata).await;
+
             let (client_name, logo_uri, connection_url, _) =
                 get_siopv2_client_name_and_logo_uri(&siopv2_authorization_request);
 
@@ -71,6 +90,20 @@ pub async fn read_authorization_request(state: AppState, action: Action) -> Resu
                 }
             }
 
+            // Requests of relying parties that fail to authenticate themselves are rejected.
+            let client_id_validation = validate_client_id_scheme(
+                &qr_code_scanned,
+                &siopv2_authorization_request.body.client_id,
+                &siopv2_authorization_request.body.redirect_uri,
+                identity_manager.subject.as_ref(),
+            )
+            .await;
+            if client_id_validation.status == ValidationStatus::Failure {
+                return Err(ClientIdSchemeValidationError(
+                    client_id_validation.message.unwrap_or_default(),
+                ));
+            }
+
             let previously_connected = state.connections.contains(&connection_url, &client_name);
 
             let url = url::Url::parse(&redirect_uri).map_err(|_| {
@@ -82,13 +115,21 @@ pub async fn read_authorization_request(state: AppState, action: Action) -> Resu
 
             let did = siopv2_authorization_request.body.client_id.as_str();
 
-            let domain_validation = validate_domain_linkage(url, did).await;
+            let domain_validation = DomainValidation::validate_or_reuse(
+                state.connections.domain_validation(did, &url),
+                url,
+                did,
+                state.profile_settings.domain_validation_ttl(),
+            )
+            .await;
 
             drop(state_guard);
 
             return Ok(AppState {
                 core_utils: CoreUtils {
                     active_connection_request: Some(ConnectionRequest::SIOPv2(siopv2_authorization_request.into())),
+                    active_domain_validation: Some(domain_validation.clone()),
+                    active_client_id_validation: Some(client_id_validation.clone()),
                     ..state.core_utils
                 },
                 current_user_prompt: Some(CurrentUserPrompt::AcceptConnection {
@@ -96,48 +137,90 @@ pub async fn read_authorization_request(state: AppState, action: Action) -> Resu
                     logo_uri,
                     redirect_uri,
                     previously_connected,
-                    domain_validation,
+                    domain_validation: domain_validation.result,
+                    client_id_validation,
+                    client_metadata_error,
                 }),
                 ..state
             });
         } else if let Result::Ok(oid4vp_authorization_request) =
             AuthorizationRequest::<Object<OID4VP>>::from_generic(&generic_authorization_request)
         {
-            let verifiable_credentials = stronghold_manager.values().map_err(StrongholdValuesError)?.unwrap();
+            // Expired credentials are not offered to the verifier.
+            let now = Utc::now();
+            let verifiable_credentials: Vec<_> = stronghold_manager
+                .values()
+                .map_err(StrongholdValuesError)?
+                .unwrap()
+                .into_iter()
+                .filter(|verifiable_credential_record| {
+                    !verifiable_credential_record
+                        .display_credential
+                        .metadata
+                        .is_expired_at(now)
+                })
+                .collect();
             info!("verifiable credentials: {:?}", verifiable_credentials);
 
-            let uuids: Vec<String> = oid4vp_authorization_request
-                .body
-                .extension
-                .presentation_definition
-                .input_descriptors()
-                .iter()
-                .map(|input_descriptor| {
-                    verifiable_credentials
-                        .iter()
-                        .find_map(|verifiable_credential_record| {
-                            evaluate_input(
-                                input_descriptor,
-                                &serde_json::json!({
-                                    "vc": verifiable_credential_record.display_credential.data
-                                }),
-                            )
-                            .then_some(verifiable_credential_record.display_credential.id.clone())
-                        })
-                        .ok_or(NoMatchingCredentialError)
-                })
-                .collect::<Result<Vec<String>, AppError>>()?;
+            // The client metadata needs to be resolved before the client's name and logo can be determined.
+            let client_metadata_error =
+                resolve_client_metadata_resource(&oid4vp_authorization_request.body.extension.client_metadata).await;
 
-            info!("uuids of VCs that can fulfill the request: {:?}", uuids);
+            // Requests of relying parties that fail to authenticate themselves are rejected.
+            let client_id_validation = validate_client_id_scheme(
+                &qr_code_scanned,
+                &oid4vp_authorization_request.body.client_id,
+                &oid4vp_authorization_request.body.redirect_uri,
+                identity_manager.subject.as_ref(),
+            )
+            .await;
+            if client_id_validation.status == ValidationStatus::Failure {
+                return Err(ClientIdSchemeValidationError(
+                    client_id_validation.message.unwrap_or_default(),
+                ));
+            }
 
             let OID4VPClientMetadata {
                 client_name,
                 logo_uri,
-                connection_url: _,
+                connection_url,
                 client_id: _,
                 algorithm: _,
             } = get_oid4vp_client_name_and_logo_uri(&oid4vp_authorization_request);
 
+            // The verifier is only looked up by its DID once it has authenticated itself with it.
+            let trust = trust_status(
+                Some(oid4vp_authorization_request.body.client_id.as_str())
+                    .filter(|_| client_id_validation.status == ValidationStatus::Success),
+                &connection_url,
+                Some(TrustedRole::Verifier),
+            );
+            info!("trust status of the verifier: {:?}", trust);
+
+            // The verifier's DID (if its `client_id` is one) needs to be linked to the domain of its `redirect_uri`.
+            let domain_validation = match oid4vp_authorization_request.body.client_id.starts_with("did:") {
+                true => {
+                    DomainValidation::validate_or_reuse(
+                        state.connections.domain_validation(
+                            &oid4vp_authorization_request.body.client_id,
+                            &oid4vp_authorization_request.body.redirect_uri,
+                        ),
+                        oid4vp_authorization_request.body.redirect_uri.clone(),
+                        &oid4vp_authorization_request.body.client_id,
+                        state.profile_settings.domain_validation_ttl(),
+                    )
+                    .await
+                }
+                false => DomainValidation {
+                    result: ValidationResult {
+                        status: ValidationStatus::Unknown,
+                        message: Some("The `client_id` of the verifier is not a DID".to_string()),
+                    },
+                    ..Default::default()
+                },
+            };
+            info!("domain validation of the verifier: {:?}", domain_validation);
+
             info!("client_name in credential_offer: {:?}", client_name);
             info!("logo_uri in read_authorization_request: {:?}", logo_uri);
 
@@ -154,22 +237,106 @@ pub async fn read_authorization_request(state: AppState, action: Action) -> Resu
                 }
             }
 
-            // TODO: communicate when no credentials are available.
-            if !uuids.is_empty() {
-                drop(state_guard);
-                return Ok(AppState {
-                    core_utils: CoreUtils {
-                        active_connection_request: Some(ConnectionRequest::OID4VP(oid4vp_authorization_request.into())),
-                        ..state.core_utils
-                    },
-                    current_user_prompt: Some(CurrentUserPrompt::ShareCredentials {
-                        client_name,
-                        logo_uri,
-                        options: uuids,
-                    }),
-                    ..state
-                });
+            let input_descriptors =
+                match input_descriptor_options(&oid4vp_authorization_request, &verifiable_credentials) {
+                    Ok(input_descriptors)
+                        if input_descriptors
+                            .iter()
+                            .any(|input_descriptor| !input_descriptor.options.is_empty()) =>
+                    {
+                        input_descriptors
+                    }
+                    // The user is told what the verifier asked for, so the request can be declined explicitly.
+                    Ok(_) | Err(NoMatchingCredentialError) => {
+                        info!("no credentials available that can fulfill the request");
+
+                        let requested = requested_input_descriptors(&oid4vp_authorization_request)?;
+
+                        drop(state_guard);
+                        return Ok(AppState {
+                            core_utils: CoreUtils {
+                                active_connection_request: Some(ConnectionRequest::OID4VP(
+                                    oid4vp_authorization_request.into(),
+                                )),
+                                ..state.core_utils
+                            },
+                            current_user_prompt: Some(CurrentUserPrompt::NoMatchingCredentials {
+                                client_name,
+                                logo_uri,
+                                requested,
+                                client_metadata_error,
+                            }),
+                            ..state
+                        });
+                    }
+                    Err(e) => return Err(e),
+                };
+
+            // All candidates, in the order in which they are first offered for an input descriptor.
+            let mut uuids: Vec<String> = vec![];
+            for uuid in input_descriptors
+                .iter()
+                .flat_map(|input_descriptor| input_descriptor.options.iter())
+            {
+                if !uuids.contains(uuid) {
+                    uuids.push(uuid.clone());
+                }
             }
+
+            info!("uuids of VCs that can fulfill the request: {:?}", uuids);
+
+            // Check the status of the candidate credentials, so the user can be warned about revoked credentials.
+            let mut credentials = state.credentials;
+            let mut revoked = vec![];
+            for display_credential in update_statuses(
+                stronghold_manager,
+                verifiable_credentials
+                    .into_iter()
+                    .filter(|verifiable_credential_record| {
+                        uuids.contains(&verifiable_credential_record.display_credential.id)
+                    })
+                    .collect(),
+                identity_manager.subject.as_ref(),
+            )
+            .await?
+            .into_iter()
+            .map(|verifiable_credential_record| verifiable_credential_record.display_credential)
+            {
+                if display_credential.metadata.status.is_revoked() {
+                    revoked.push(display_credential.id.clone());
+                }
+                if let Some(credential) = credentials
+                    .iter_mut()
+                    .find(|credential| credential.id == display_credential.id)
+                {
+                    credential.metadata.status = display_credential.metadata.status;
+                }
+            }
+
+            info!("uuids of revoked VCs: {:?}", revoked);
+
+            drop(state_guard);
+            return Ok(AppState {
+                core_utils: CoreUtils {
+                    active_connection_request: Some(ConnectionRequest::OID4VP(oid4vp_authorization_request.into())),
+                    active_domain_validation: Some(domain_validation.clone()),
+                    active_client_id_validation: Some(client_id_validation.clone()),
+                    ..state.core_utils
+                },
+                credentials,
+                current_user_prompt: Some(CurrentUserPrompt::ShareCredentials {
+                    client_name,
+                    logo_uri,
+                    options: uuids,
+                    revoked,
+                    input_descriptors,
+                    domain_validation: domain_validation.result,
+                    client_id_validation,
+                    trust,
+                    client_metadata_error,
+                }),
+                ..state
+            });
         } else {
             return Err(InvalidAuthorizationRequest(Box::new(generic_authorization_request)));
         };
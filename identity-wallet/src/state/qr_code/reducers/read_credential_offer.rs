@@ -5,18 +5,26 @@ use crate::{
     persistence::{download_asset, hash},
     state::{
         actions::{listen, Action},
-        core_utils::CoreUtils,
+        connections::trust_registry::{trust_status, TrustedRole},
+        core_utils::{client_id_scheme::verify_jwt_signature, CoreUtils},
+        did::validate_domain_linkage::{DomainValidation, ValidationResult, ValidationStatus},
         qr_code::actions::qrcode_scanned::QrCodeScanned,
-        user_prompt::CurrentUserPrompt,
+        user_prompt::{CurrentUserPrompt, TransactionCode},
         AppState,
     },
 };
 
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
 use log::{debug, info};
+use oid4vc::oid4vc_core::Verify;
 use oid4vc::oid4vci::{
-    credential_issuer::credential_configurations_supported::CredentialConfigurationsSupportedObject,
+    credential_issuer::{
+        credential_configurations_supported::CredentialConfigurationsSupportedObject,
+        credential_issuer_metadata::CredentialIssuerMetadata,
+    },
     credential_offer::{CredentialOffer, CredentialOfferParameters},
 };
+use serde_json::Value;
 
 pub async fn read_credential_offer(state: AppState, action: Action) -> Result<AppState, AppError> {
     info!("read_credential_offer");
@@ -28,11 +36,11 @@ pub async fn read_credential_offer(state: AppState, action: Action) -> Result<Ap
         listen::<QrCodeScanned>(action).and_then(|payload| payload.form_urlencoded.parse::<CredentialOffer>().ok())
     {
         let state_guard = state.core_utils.managers.lock().await;
-        let wallet = &state_guard
+        let identity_manager = state_guard
             .identity_manager
             .as_ref()
-            .ok_or(MissingManagerError("identity"))?
-            .wallet;
+            .ok_or(MissingManagerError("identity"))?;
+        let wallet = &identity_manager.wallet;
 
         let credential_offer: CredentialOfferParameters = match credential_offer_uri {
             CredentialOffer::CredentialOffer(credential_offer) => *credential_offer,
@@ -44,15 +52,28 @@ pub async fn read_credential_offer(state: AppState, action: Action) -> Result<Ap
 
         info!("credential offer: {:?}", credential_offer);
 
+        // Check whether the credential issuer requires a transaction code to be entered by the user.
+        let tx_code: Option<TransactionCode> = credential_offer
+            .grants
+            .as_ref()
+            .and_then(|grants| grants.pre_authorized_code.as_ref())
+            .and_then(|pre_authorized_code| pre_authorized_code.tx_code.as_ref())
+            .and_then(|tx_code| serde_json::from_value(serde_json::json!(tx_code)).ok());
+
+        info!("transaction code: {:?}", tx_code);
+
         // The credential offer contains a credential issuer url.
         let credential_issuer_url = credential_offer.credential_issuer.clone();
 
         info!("credential issuer url: {:?}", credential_issuer_url);
 
-        let credential_issuer_metadata = wallet
-            .get_credential_issuer_metadata(credential_issuer_url.clone())
-            .await
-            .ok();
+        // The metadata is only fetched once, as JSON, since the DID of the credential issuer is not part of the parsed
+        // metadata.
+        let credential_issuer_metadata_json = fetch_credential_issuer_metadata(&credential_issuer_url).await;
+        let credential_issuer_metadata = credential_issuer_metadata_json
+            .as_ref()
+            .ok()
+            .and_then(|metadata| serde_json::from_value::<CredentialIssuerMetadata>(metadata.clone()).ok());
 
         info!("credential issuer metadata: {:?}", credential_issuer_metadata);
 
@@ -107,6 +128,33 @@ pub async fn read_credential_offer(state: AppState, action: Action) -> Result<Ap
         info!("issuer_name in credential_offer: {:?}", issuer_name);
         info!("logo_uri in credential_offer: {:?}", logo_uri);
 
+        let trust = trust_status(None, credential_issuer_url.as_str(), Some(TrustedRole::Issuer));
+        info!("trust status of the credential issuer: {:?}", trust);
+
+        let credential_issuer_did = match &credential_issuer_metadata_json {
+            Ok(metadata) => credential_issuer_did(metadata, identity_manager.subject.as_ref()).await,
+            Err(message) => Err(ValidationResult {
+                status: ValidationStatus::Unknown,
+                message: Some(message.clone()),
+            }),
+        };
+        let domain_validation = match credential_issuer_did {
+            Ok(did) => {
+                DomainValidation::validate_or_reuse(
+                    state.connections.domain_validation(&did, &credential_issuer_url),
+                    credential_issuer_url.clone(),
+                    &did,
+                    state.profile_settings.domain_validation_ttl(),
+                )
+                .await
+            }
+            Err(result) => DomainValidation {
+                result,
+                ..Default::default()
+            },
+        };
+        info!("domain validation of the credential issuer: {:?}", domain_validation);
+
         download_credential_logos(&credential_configurations).await;
 
         if logo_uri.is_some() {
@@ -128,9 +176,13 @@ pub async fn read_credential_offer(state: AppState, action: Action) -> Result<Ap
                 issuer_name,
                 logo_uri,
                 credential_configurations,
+                tx_code,
+                trust,
+                domain_validation: domain_validation.result.clone(),
             }),
             core_utils: CoreUtils {
                 active_credential_offer: Some(credential_offer),
+                active_domain_validation: Some(domain_validation),
                 ..state.core_utils
             },
             ..state
@@ -169,3 +221,195 @@ async fn download_credential_logos(
         }
     }
 }
+
+// Fetches the credential issuer metadata as JSON. The well-known path is inserted before the path of the credential
+// issuer, or else appended to it, which is where the metadata used to be looked up.
+// See: https://openid.net/specs/openid-4-verifiable-credential-issuance-1_0.html#name-credential-issuer-metadata-
+async fn fetch_credential_issuer_metadata(credential_issuer_url: &url::Url) -> Result<Value, String> {
+    let path = credential_issuer_url.path().trim_end_matches('/');
+
+    let mut metadata_url = credential_issuer_url.clone();
+    metadata_url.set_path(&format!("/.well-known/openid-credential-issuer{path}"));
+    let mut metadata_urls = vec![metadata_url];
+    if !path.is_empty() {
+        let mut metadata_url = credential_issuer_url.clone();
+        metadata_url.set_path(&format!("{path}/.well-known/openid-credential-issuer"));
+        metadata_urls.push(metadata_url);
+    }
+
+    let mut error = String::new();
+    for metadata_url in metadata_urls {
+        match reqwest::get(metadata_url)
+            .await
+            .and_then(reqwest::Response::error_for_status)
+        {
+            Ok(response) => return response.json().await.map_err(|e| e.to_string()),
+            Err(e) => error = e.to_string(),
+        }
+    }
+
+    Err(error)
+}
+
+// Returns the DID of the credential issuer, which is either the issuer (`iss`) of its signed metadata, provided that
+// the signature is valid, or the `did` parameter of its metadata.
+async fn credential_issuer_did(metadata: &Value, verifier: &dyn Verify) -> Result<String, ValidationResult> {
+    let unknown = |message: String| ValidationResult {
+        status: ValidationStatus::Unknown,
+        message: Some(message),
+    };
+
+    if let Some(signed_metadata) = metadata["signed_metadata"].as_str() {
+        let signature_validation = verify_jwt_signature(signed_metadata, verifier).await;
+        if signature_validation.status != ValidationStatus::Success {
+            return Err(signature_validation);
+        }
+
+        let iss = signed_metadata
+            .split('.')
+            .nth(1)
+            .and_then(|payload| URL_SAFE_NO_PAD.decode(payload).ok())
+            .and_then(|payload| serde_json::from_slice::<serde_json::Value>(&payload).ok())
+            .and_then(|claims| claims["iss"].as_str().map(ToString::to_string));
+
+        if let Some(did) = iss.filter(|iss| iss.starts_with("did:")) {
+            // A DID key needs to belong to the issuer of the signed metadata.
+            let kid = jsonwebtoken::decode_header(signed_metadata)
+                .ok()
+                .and_then(|header| header.kid);
+            if kid.is_some_and(|kid| kid.starts_with("did:") && kid.split('#').next() != Some(did.as_str())) {
+                return Err(ValidationResult {
+                    status: ValidationStatus::Failure,
+                    message: Some("The signed metadata is not signed by its issuer".to_string()),
+                });
+            }
+
+            return Ok(did);
+        }
+    }
+
+    metadata["did"]
+        .as_str()
+        .filter(|did| did.starts_with("did:"))
+        .map(ToString::to_string)
+        .ok_or(unknown(
+            "The credential issuer metadata does not contain a DID".to_string(),
+        ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ring::signature::{Ed25519KeyPair, KeyPair as _};
+    use serde_json::json;
+    use wiremock::{
+        matchers::{method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    const ISSUER: &str = "did:example:issuer";
+
+    // Resolves only the keys `did:example:issuer#key-1` and `did:example:other#key-1`.
+    struct IssuerKey(Vec<u8>);
+
+    #[async_trait::async_trait]
+    impl Verify for IssuerKey {
+        async fn public_key(&self, did_url: &str) -> anyhow::Result<Vec<u8>> {
+            match did_url {
+                "did:example:issuer#key-1" | "did:example:other#key-1" => Ok(self.0.clone()),
+                did_url => anyhow::bail!("unknown key: {did_url}"),
+            }
+        }
+    }
+
+    fn signed_metadata(key_pair: &Ed25519KeyPair, kid: &str) -> String {
+        let encode = |value: &Value| URL_SAFE_NO_PAD.encode(value.to_string());
+        let message = format!(
+            "{}.{}",
+            encode(&json!({ "alg": "EdDSA", "kid": kid })),
+            encode(&json!({ "iss": ISSUER, "sub": "https://issuer.example.com" }))
+        );
+
+        format!(
+            "{message}.{}",
+            URL_SAFE_NO_PAD.encode(key_pair.sign(message.as_bytes()).as_ref())
+        )
+    }
+
+    #[tokio::test]
+    async fn credential_issuer_metadata_is_fetched_once() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/.well-known/openid-credential-issuer/issuer"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "did": ISSUER })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let credential_issuer_url: url::Url = format!("{}/issuer/", mock_server.uri()).parse().unwrap();
+        let metadata = fetch_credential_issuer_metadata(&credential_issuer_url).await.unwrap();
+
+        assert_eq!(metadata, json!({ "did": ISSUER }));
+    }
+
+    #[tokio::test]
+    async fn credential_issuer_metadata_falls_back_to_the_appended_well_known_path() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/issuer/.well-known/openid-credential-issuer"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "did": ISSUER })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let credential_issuer_url: url::Url = format!("{}/issuer", mock_server.uri()).parse().unwrap();
+        let metadata = fetch_credential_issuer_metadata(&credential_issuer_url).await.unwrap();
+
+        assert_eq!(metadata, json!({ "did": ISSUER }));
+        assert!(fetch_credential_issuer_metadata(&mock_server.uri().parse().unwrap())
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn credential_issuer_did_is_taken_from_the_metadata() {
+        let key_pair = Ed25519KeyPair::from_seed_unchecked(&[7; 32]).unwrap();
+        let verifier = IssuerKey(key_pair.public_key().as_ref().to_vec());
+
+        assert_eq!(
+            credential_issuer_did(&json!({ "did": ISSUER }), &verifier).await,
+            Ok(ISSUER.to_string())
+        );
+        assert_eq!(
+            credential_issuer_did(&json!({ "did": "https://issuer.example.com" }), &verifier)
+                .await
+                .unwrap_err()
+                .status,
+            ValidationStatus::Unknown
+        );
+
+        // The issuer of the signed metadata takes precedence over the `did` parameter.
+        let metadata = json!({
+            "did": "did:example:unsigned",
+            "signed_metadata": signed_metadata(&key_pair, "did:example:issuer#key-1")
+        });
+        assert_eq!(
+            credential_issuer_did(&metadata, &verifier).await,
+            Ok(ISSUER.to_string())
+        );
+
+        // The signed metadata needs to be signed by its issuer.
+        let metadata = json!({ "signed_metadata": signed_metadata(&key_pair, "did:example:other#key-1") });
+        assert_eq!(
+            credential_issuer_did(&metadata, &verifier).await.unwrap_err().status,
+            ValidationStatus::Failure
+        );
+
+        // A signature that cannot be verified is not accepted.
+        let metadata = json!({ "signed_metadata": signed_metadata(&key_pair, "did:example:unknown#key-1") });
+        assert_ne!(
+            credential_issuer_did(&metadata, &verifier).await.unwrap_err().status,
+            ValidationStatus::Success
+        );
+    }
+}
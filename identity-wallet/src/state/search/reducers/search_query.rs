@@ -117,6 +117,7 @@ mod tests {
                     },
                     connection_id: None,
                     display_name: "John".to_string(),
+                    format: Default::default(),
                 },
                 DisplayCredential {
                     id: "2".to_string(),
@@ -129,6 +130,7 @@ mod tests {
                     },
                     connection_id: None,
                     display_name: "Jane".to_string(),
+                    format: Default::default(),
                 },
                 DisplayCredential {
                     id: "3".to_string(),
@@ -141,6 +143,7 @@ mod tests {
                     },
                     connection_id: None,
                     display_name: "Jeff".to_string(),
+                    format: Default::default(),
                 },
             ],
             ..Default::default()
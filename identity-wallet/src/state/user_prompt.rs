@@ -29,6 +29,14 @@ pub enum CurrentUserPrompt {
         redirect_uri: String,
         previously_connected: bool,
         domain_validation: ValidationResult,
+        /// The result of authenticating the client according to the `client_id_scheme` of the request.
+        #[serde(default)]
+        client_id_validation: ValidationResult,
+        /// Why the client metadata referenced by the `client_metadata_uri` of the request could not be fetched, in
+        /// which case the client is shown by its hostname.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        #[ts(optional)]
+        client_metadata_error: Option<String>,
     },
     #[serde(rename = "credential-offer")]
     CredentialOffer {
@@ -37,6 +45,24 @@ pub enum CurrentUserPrompt {
         logo_uri: Option<String>,
         #[ts(type = "Record<string, any>")]
         credential_configurations: HashMap<String, CredentialConfigurationsSupportedObject>,
+        /// Present when the credential issuer requires a transaction code (e.g. a PIN sent by e-mail) to be entered.
+        #[ts(optional)]
+        tx_code: Option<TransactionCode>,
+        /// The result of validating the linkage between the DID of the credential issuer and its domain.
+        #[serde(default)]
+        domain_validation: ValidationResult,
+        /// Whether the credential issuer is listed in one of the trust lists.
+        #[serde(default)]
+        trust: ValidationResult,
+    },
+    /// The frontend is asked to open the `authorization_url` so the user can authenticate at the authorization server
+    /// of the credential issuer (OID4VCI Authorization Code Flow).
+    #[serde(rename = "authorize")]
+    Authorize {
+        issuer_name: String,
+        #[ts(optional)]
+        logo_uri: Option<String>,
+        authorization_url: String,
     },
     #[serde(rename = "share-credentials")]
     ShareCredentials {
@@ -44,7 +70,108 @@ pub enum CurrentUserPrompt {
         #[ts(optional)]
         logo_uri: Option<String>,
         options: Vec<String>,
+        /// The options that have been revoked or suspended by their issuer, about which the user should be warned.
+        #[serde(default)]
+        revoked: Vec<String>,
+        /// The options per input descriptor of the presentation definition, from which the user can choose.
+        #[serde(default)]
+        input_descriptors: Vec<InputDescriptorOptions>,
+        /// The result of validating the linkage between the DID of the verifier and the domain of its `redirect_uri`.
+        #[serde(default)]
+        domain_validation: ValidationResult,
+        /// The result of authenticating the client according to the `client_id_scheme` of the request.
+        #[serde(default)]
+        client_id_validation: ValidationResult,
+        /// Whether the verifier is listed in one of the trust lists.
+        #[serde(default)]
+        trust: ValidationResult,
+        /// See `AcceptConnection::client_metadata_error`.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        #[ts(optional)]
+        client_metadata_error: Option<String>,
     },
+    /// None of the stored credentials can fulfill the presentation request. The user is shown what the verifier asked
+    /// for and can decline the request, in which case the verifier is notified.
+    #[serde(rename = "no-matching-credentials")]
+    NoMatchingCredentials {
+        client_name: String,
+        #[ts(optional)]
+        logo_uri: Option<String>,
+        requested: Vec<RequestedInputDescriptor>,
+        /// See `AcceptConnection::client_metadata_error`.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        #[ts(optional)]
+        client_metadata_error: Option<String>,
+    },
+    /// The recovery phrase that protects a backup, which the user needs to write down. It is never persisted.
+    #[serde(rename = "recovery-phrase")]
+    RecoveryPhrase { words: Vec<String> },
+}
+
+/// The credentials that fulfill an input descriptor of a presentation definition.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, TS)]
+#[ts(export, export_to = "bindings/user_prompt/InputDescriptorOptions.ts")]
+pub struct InputDescriptorOptions {
+    pub id: String,
+    #[ts(optional)]
+    pub name: Option<String>,
+    #[ts(optional)]
+    pub purpose: Option<String>,
+    pub options: Vec<String>,
+    /// Whether the submission requirements of the presentation definition can only be satisfied with this input
+    /// descriptor. Input descriptors that are not required can be left out by the user.
+    pub required: bool,
+}
+
+/// Describes what an input descriptor of a presentation definition asks for.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, TS)]
+#[ts(export, export_to = "bindings/user_prompt/RequestedInputDescriptor.ts")]
+pub struct RequestedInputDescriptor {
+    pub id: String,
+    #[ts(optional)]
+    pub name: Option<String>,
+    #[ts(optional)]
+    pub purpose: Option<String>,
+    /// The paths of the fields that are not optional, e.g. `$.credentialSubject.email`.
+    pub fields: Vec<String>,
+    pub required: bool,
+}
+
+/// Describes the transaction code the user is expected to enter for a Pre-Authorized Code Flow.
+/// See: https://openid.net/specs/openid-4-verifiable-credential-issuance-1_0.html#name-credential-offer-parameters
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, TS, Default)]
+#[ts(export, export_to = "bindings/user_prompt/TransactionCode.ts")]
+#[serde(default)]
+pub struct TransactionCode {
+    pub input_mode: TransactionCodeInputMode,
+    #[ts(optional)]
+    pub length: Option<u32>,
+    #[ts(optional)]
+    pub description: Option<String>,
+}
+
+impl TransactionCode {
+    /// Checks whether the transaction code entered by the user matches the expected input mode and length.
+    pub fn is_valid(&self, tx_code: &str) -> bool {
+        let matches_input_mode = match self.input_mode {
+            TransactionCodeInputMode::Numeric => tx_code.chars().all(|c| c.is_ascii_digit()),
+            TransactionCodeInputMode::Text => true,
+        };
+        let matches_length = self
+            .length
+            .map_or(true, |length| tx_code.chars().count() as u32 == length);
+
+        !tx_code.is_empty() && matches_input_mode && matches_length
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, TS, Default)]
+#[ts(export, export_to = "bindings/user_prompt/TransactionCodeInputMode.ts")]
+#[serde(rename_all = "snake_case")]
+pub enum TransactionCodeInputMode {
+    #[default]
+    Numeric,
+    Text,
 }
 
 #[cfg(test)]
@@ -72,10 +199,40 @@ mod tests {
             redirect_uri: "https://example.com".to_string(),
             previously_connected: false,
             domain_validation: Default::default(),
+            client_id_validation: Default::default(),
+            client_metadata_error: None,
         };
         assert_eq!(
-            serde_json::to_string(&prompt).unwrap(),
-            r#"{"type":"accept-connection","client_name":"Test Client","logo_uri":null,"redirect_uri":"https://example.com","previously_connected":false,"domain_validation":{"status":"Unknown","message":null}}"#
+            serde_json::to_value(&prompt).unwrap(),
+            serde_json::json!({
+                "type": "accept-connection",
+                "client_name": "Test Client",
+                "logo_uri": null,
+                "redirect_uri": "https://example.com",
+                "previously_connected": false,
+                "domain_validation": { "status": "Unknown", "message": null },
+                "client_id_validation": { "status": "Unknown", "message": null }
+            })
         );
     }
+
+    #[test]
+    fn test_transaction_code_is_valid() {
+        let tx_code = TransactionCode {
+            input_mode: TransactionCodeInputMode::Numeric,
+            length: Some(4),
+            description: None,
+        };
+        assert!(tx_code.is_valid("1234"));
+        assert!(!tx_code.is_valid("12345"));
+        assert!(!tx_code.is_valid("12a4"));
+        assert!(!tx_code.is_valid(""));
+
+        let tx_code = TransactionCode {
+            input_mode: TransactionCodeInputMode::Text,
+            length: None,
+            description: Some("Please enter the code sent to your e-mail".to_string()),
+        };
+        assert!(tx_code.is_valid("a1b2c3"));
+    }
 }
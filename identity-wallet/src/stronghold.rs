@@ -1,10 +1,14 @@
-use crate::{persistence::STRONGHOLD, state::credentials::VerifiableCredentialRecord};
+use crate::{
+    persistence::STRONGHOLD,
+    state::credentials::{deferred_credential::DeferredCredentialRecord, VerifiableCredentialRecord},
+};
 
 use iota_stronghold::{
     procedures::{GenerateKey, KeyType, StrongholdProcedure},
     Client, KeyProvider, Location, SnapshotPath, Stronghold,
 };
-use log::info;
+use log::{info, warn};
+use std::path::{Path, PathBuf};
 use stronghold_ext::{
     execute_procedure_ext,
     procs::{self, es256::Es256Procs},
@@ -16,77 +20,33 @@ use uuid::Uuid;
 static STRONGHOLD_VAULT_PATH: &str = "iota_identity_vault";
 static STRONGHOLD_CLIENT_PATH: &[u8] = b"iota_identity_client";
 
+// Records that are not credentials are stored under a prefixed key, so they can be distinguished from the credentials,
+// which are stored under their plain UUID.
+static DEFERRED_CREDENTIAL_KEY_PREFIX: &str = "deferred_credential:";
+
 /// This struct is the main point of communication between our appstate and the stronghold library.
 #[derive(Debug)]
 pub struct StrongholdManager {
     stronghold: Stronghold,
     client: Client,
     key_provider: KeyProvider,
-    snapshot_path: SnapshotPath,
 }
 
 impl StrongholdManager {
     pub fn create(password: &str) -> anyhow::Result<Self> {
         let stronghold = Stronghold::default();
-        let client_path = STRONGHOLD
-            .lock()
-            .unwrap()
-            .to_str()
-            .ok_or(anyhow::anyhow!("failed to get stronghold path"))?
-            .to_owned();
-
-        let snapshot_path = SnapshotPath::from_path(client_path.clone());
-        let key_provider =
-            KeyProvider::with_passphrase_hashed_blake2b(password.as_bytes().to_vec()).expect("failed to load key");
+        let key_provider = key_provider(password)?;
 
         let client: Client = stronghold
             .create_client(STRONGHOLD_CLIENT_PATH)
-            .expect("cannot create client");
-
-        // Generate ed25519 key
-        {
-            let ed25519_output_location = Location::generic(
-                STRONGHOLD_VAULT_PATH.as_bytes().to_vec(),
-                "ed25519-0".to_string().as_bytes().to_vec(),
-            );
-
-            info!("ed25519_output_location: {:?}", ed25519_output_location);
-
-            client
-                .execute_procedure(StrongholdProcedure::GenerateKey(GenerateKey {
-                    ty: KeyType::Ed25519,
-                    output: ed25519_output_location,
-                }))
-                .expect("failed to generate new private key");
-
-            info!("Successfully generated new private key with type Ed25519");
-        }
-
-        // Generate ES256 Key
-        {
-            let es256_output_location = Location::generic(
-                STRONGHOLD_VAULT_PATH.as_bytes().to_vec(),
-                "es256-0".to_string().as_bytes().to_vec(),
-            );
-
-            info!("es256_output_location: {:?}", es256_output_location);
+            .map_err(|e| anyhow::anyhow!("cannot create client: {e:?}"))?;
 
-            execute_procedure_ext(
-                &client,
-                Es256Procs::GenerateKey(procs::es256::GenerateKey {
-                    output: es256_output_location,
-                }),
-            )
-            .expect("failed to generate new private key");
-
-            info!("Successfully generated new private key with type Es256");
-        }
+        generate_keys(&client, 0)?;
 
         let stronghold_manager = Self {
             stronghold,
             client,
             key_provider,
-            snapshot_path: snapshot_path.clone(),
         };
 
         stronghold_manager.commit()?;
@@ -101,9 +61,8 @@ impl StrongholdManager {
             .to_str()
             .ok_or(anyhow::anyhow!("failed to get stronghold path"))?
             .to_owned();
-        let snapshot_path = SnapshotPath::from_path(client_path.clone());
-        let key_provider =
-            KeyProvider::with_passphrase_hashed_blake2b(password.as_bytes().to_vec()).expect("failed to load key");
+        let snapshot_path = SnapshotPath::from_path(client_path);
+        let key_provider = key_provider(password)?;
 
         info!("Loading snapshot");
 
@@ -113,26 +72,114 @@ impl StrongholdManager {
             stronghold,
             client,
             key_provider,
-            snapshot_path,
         })
     }
 
+    /// Writes the client state to the snapshot. The snapshot is written to a temporary file first, which replaces the
+    /// snapshot once it is known to load, see `replace_snapshot`.
     pub fn commit(&self) -> anyhow::Result<()> {
         // Set the work factor to 10 to speed up the commit.
         // TODO: security: weaker encryption?
         engine::snapshot::try_set_encrypt_work_factor(10)?;
 
+        let temporary_file = temporary_snapshot_file();
         self.stronghold
             .write_client(STRONGHOLD_CLIENT_PATH)
-            .expect("store client state into snapshot state failed");
+            .map_err(|e| anyhow::anyhow!("failed to store client state into snapshot state: {e:?}"))?;
+        self.stronghold
+            .commit_with_keyprovider(&SnapshotPath::from_path(&temporary_file), &self.key_provider)
+            .map_err(|e| anyhow::anyhow!("failed to write the snapshot: {e:?}"))?;
+
+        replace_snapshot(&temporary_file, &self.key_provider)
+    }
+
+    /// Re-encrypts the snapshot with a key derived from the new password and returns a `StrongholdManager` that uses
+    /// it. The current snapshot is only replaced once the re-encrypted one is known to load with the new password, see
+    /// `replace_snapshot`. If the new `StrongholdManager` cannot be loaded after all, the snapshot is encrypted with
+    /// the old password again, so it keeps matching this `StrongholdManager`.
+    pub fn change_password(&self, new_password: &str) -> anyhow::Result<Self> {
+        let temporary_file = temporary_snapshot_file();
+        let key_provider = key_provider(new_password)?;
+
+        engine::snapshot::try_set_encrypt_work_factor(10)?;
 
         self.stronghold
-            .commit_with_keyprovider(&self.snapshot_path, &self.key_provider)
-            .expect("stronghold could not commit");
+            .write_client(STRONGHOLD_CLIENT_PATH)
+            .map_err(|e| anyhow::anyhow!("failed to store client state into snapshot state: {e:?}"))
+            .and_then(|_| {
+                self.stronghold
+                    .commit_with_keyprovider(&SnapshotPath::from_path(&temporary_file), &key_provider)
+                    .map_err(|e| anyhow::anyhow!("failed to write the re-encrypted snapshot: {e:?}"))
+            })
+            .and_then(|_| replace_snapshot(&temporary_file, &key_provider))?;
+        info!("snapshot re-encrypted with the new password");
+
+        Self::load(new_password).or_else(|e| {
+            warn!("failed to load the re-encrypted snapshot, restoring the old password: {e:?}");
+
+            let temporary_file = temporary_snapshot_file();
+            self.stronghold
+                .commit_with_keyprovider(&SnapshotPath::from_path(&temporary_file), &self.key_provider)
+                .map_err(|e| anyhow::anyhow!("failed to write the snapshot with the old password: {e:?}"))
+                .and_then(|_| replace_snapshot(&temporary_file, &self.key_provider))?;
+
+            Err(e)
+        })
+    }
+
+    /// Replaces the snapshot by the given snapshot (e.g. from a backup), which must load with the given password. See
+    /// `replace_snapshot`.
+    pub fn restore_snapshot(snapshot: &[u8], password: &str) -> anyhow::Result<()> {
+        let temporary_file = temporary_snapshot_file();
+
+        std::fs::write(&temporary_file, snapshot)
+            .map_err(anyhow::Error::from)
+            .and_then(|_| replace_snapshot(&temporary_file, &key_provider(password)?))?;
+        info!("snapshot restored");
+
+        Ok(())
+    }
+
+    /// Puts back the snapshot that was replaced by `restore_snapshot`, e.g. when the wallet cannot be unlocked after
+    /// all. Without a previous snapshot, the restored snapshot is removed. Unlike `restore_snapshot`, the previous
+    /// snapshot is not verified, since its password is not known.
+    pub fn revert_snapshot(previous_snapshot: Option<&[u8]>) -> anyhow::Result<()> {
+        let snapshot_file = STRONGHOLD.lock().unwrap().clone();
+
+        match previous_snapshot {
+            Some(previous_snapshot) => {
+                let temporary_file = temporary_snapshot_file();
+                std::fs::write(&temporary_file, previous_snapshot)?;
+                std::fs::rename(&temporary_file, &snapshot_file)?;
+            }
+            None if snapshot_file.exists() => std::fs::remove_file(&snapshot_file)?,
+            None => {}
+        }
+        info!("snapshot reverted");
+
+        Ok(())
+    }
+
+    /// Generates the keys at the given index (`ed25519-N`, `es256-N` and `es256k-N`), unless they already exist.
+    pub fn generate_keys(&self, index: u32) -> anyhow::Result<()> {
+        if generate_keys(&self.client, index)? {
+            self.commit()?;
+        }
 
         Ok(())
     }
 
+    /// Returns the lowest index at which no keys have been generated yet. Index 0 is reserved for the keys that are
+    /// generated when the Stronghold is created.
+    pub fn next_key_index(&self) -> anyhow::Result<u32> {
+        let mut index = 1;
+        while self.client.record_exists(&key_location(&format!("ed25519-{index}")))? {
+            index += 1;
+        }
+
+        Ok(index)
+    }
+
     pub fn get(&self, key: Uuid) -> anyhow::Result<Option<Vec<u8>>> {
         let key = key.to_string().as_bytes().to_vec();
         let value = self.client.store().get(&key)?;
@@ -148,11 +195,29 @@ impl StrongholdManager {
         self.commit()
     }
 
+    /// Inserts all values and commits once.
+    pub fn insert_all(&self, values: Vec<(Uuid, Vec<u8>)>) -> anyhow::Result<()> {
+        for (key, value) in values {
+            self.client
+                .store()
+                .insert(key.to_string().as_bytes().to_vec(), value, None)?;
+        }
+
+        self.commit()
+    }
+
     // TODO: fix this function's return type.
     pub fn values(&self) -> anyhow::Result<Option<Vec<VerifiableCredentialRecord>>> {
         let client = self.client.clone();
 
         let mut keys = self.client.store().keys()?;
+        // Only credentials are stored under a plain UUID key.
+        keys.retain(|key| {
+            std::str::from_utf8(key)
+                .ok()
+                .and_then(|key| Uuid::parse_str(key).ok())
+                .is_some()
+        });
         keys.sort();
         keys.iter()
             .map(|key| {
@@ -172,6 +237,137 @@ impl StrongholdManager {
 
         Ok(value)
     }
+
+    pub fn insert_deferred(&self, key: Uuid, value: Vec<u8>) -> anyhow::Result<()> {
+        self.client.store().insert(
+            format!("{DEFERRED_CREDENTIAL_KEY_PREFIX}{key}").into_bytes(),
+            value,
+            None,
+        )?;
+
+        self.commit()
+    }
+
+    pub fn deferred_values(&self) -> anyhow::Result<Vec<DeferredCredentialRecord>> {
+        let mut keys = self.client.store().keys()?;
+        keys.retain(|key| key.starts_with(DEFERRED_CREDENTIAL_KEY_PREFIX.as_bytes()));
+        keys.sort();
+
+        keys.iter()
+            .filter_map(|key| self.client.store().get(key).transpose())
+            .map(|value| Ok(serde_json::from_slice(&value?)?))
+            .collect()
+    }
+
+    pub fn remove_deferred(&self, key: Uuid) -> anyhow::Result<Option<Vec<u8>>> {
+        let value = self
+            .client
+            .store()
+            .delete(format!("{DEFERRED_CREDENTIAL_KEY_PREFIX}{key}").as_bytes())?;
+        self.commit()?;
+
+        Ok(value)
+    }
+}
+
+fn key_provider(password: &str) -> anyhow::Result<KeyProvider> {
+    KeyProvider::with_passphrase_hashed_blake2b(password.as_bytes().to_vec())
+        .map_err(|e| anyhow::anyhow!("failed to load key: {e:?}"))
+}
+
+fn temporary_snapshot_file() -> PathBuf {
+    let mut temporary_file = STRONGHOLD.lock().unwrap().clone().into_os_string();
+    temporary_file.push(".tmp");
+    PathBuf::from(temporary_file)
+}
+
+// Replaces the snapshot by the snapshot in the temporary file once it is known to load with the given key provider.
+// Since the replacement is an atomic rename, the snapshot on disk is always a complete snapshot, even if the app
+// crashes halfway. The temporary file is removed if it cannot be used.
+fn replace_snapshot(temporary_file: &Path, key_provider: &KeyProvider) -> anyhow::Result<()> {
+    let snapshot_file = STRONGHOLD.lock().unwrap().clone();
+
+    let verified = Stronghold::default()
+        .load_client_from_snapshot(
+            STRONGHOLD_CLIENT_PATH,
+            key_provider,
+            &SnapshotPath::from_path(temporary_file),
+        )
+        .map_err(|e| anyhow::anyhow!("failed to load the new snapshot: {e:?}"))
+        .and_then(|_| Ok(std::fs::File::open(temporary_file)?.sync_all()?));
+    if let Err(e) = verified {
+        std::fs::remove_file(temporary_file).ok();
+        return Err(e);
+    }
+
+    std::fs::rename(temporary_file, &snapshot_file)?;
+    // Persist the rename itself.
+    #[cfg(unix)]
+    if let Some(parent) = snapshot_file.parent() {
+        std::fs::File::open(parent)?.sync_all()?;
+    }
+
+    Ok(())
+}
+
+fn key_location(key_id: &str) -> Location {
+    Location::generic(STRONGHOLD_VAULT_PATH.as_bytes().to_vec(), key_id.as_bytes().to_vec())
+}
+
+// Generates the Ed25519, ES256 and ES256K keys at the given index. Returns whether any keys were generated.
+fn generate_keys(client: &Client, index: u32) -> anyhow::Result<bool> {
+    let mut generated = false;
+
+    // Generate ed25519 key
+    let ed25519_output_location = key_location(&format!("ed25519-{index}"));
+    if !client.record_exists(&ed25519_output_location)? {
+        info!("ed25519_output_location: {:?}", ed25519_output_location);
+
+        client
+            .execute_procedure(StrongholdProcedure::GenerateKey(GenerateKey {
+                ty: KeyType::Ed25519,
+                output: ed25519_output_location,
+            }))
+            .map_err(|e| anyhow::anyhow!("failed to generate new private key: {e:?}"))?;
+
+        info!("Successfully generated new private key with type Ed25519");
+        generated = true;
+    }
+
+    // Generate ES256 Key
+    let es256_output_location = key_location(&format!("es256-{index}"));
+    if !client.record_exists(&es256_output_location)? {
+        info!("es256_output_location: {:?}", es256_output_location);
+
+        execute_procedure_ext(
+            client,
+            Es256Procs::GenerateKey(procs::es256::GenerateKey {
+                output: es256_output_location,
+            }),
+        )
+        .map_err(|e| anyhow::anyhow!("failed to generate new private key: {e:?}"))?;
+
+        info!("Successfully generated new private key with type Es256");
+        generated = true;
+    }
+
+    // Generate ES256K Key
+    let es256k_output_location = key_location(&format!("es256k-{index}"));
+    if !client.record_exists(&es256k_output_location)? {
+        info!("es256k_output_location: {:?}", es256k_output_location);
+
+        client
+            .execute_procedure(StrongholdProcedure::GenerateKey(GenerateKey {
+                ty: KeyType::Secp256k1Ecdsa,
+                output: es256k_output_location,
+            }))
+            .map_err(|e| anyhow::anyhow!("failed to generate new private key: {e:?}"))?;
+
+        info!("Successfully generated new private key with type Es256k");
+        generated = true;
+    }
+
+    Ok(generated)
 }
 
 #[cfg(test)]
@@ -191,6 +387,8 @@ mod tests {
         let value = "test".as_bytes().to_vec();
 
         stronghold_manager.insert(key, value).unwrap();
+        // The snapshot is replaced by the committed snapshot.
+        assert!(!temporary_snapshot_file().exists());
 
         let value = stronghold_manager.get(key).unwrap().unwrap();
         assert_eq!(value, "test".as_bytes().to_vec());
@@ -200,4 +398,81 @@ mod tests {
         let value = stronghold_manager.get(key).unwrap();
         assert!(value.is_none());
     }
+
+    #[test]
+    #[serial_test::serial]
+    fn test_generate_keys() {
+        let path = NamedTempFile::new().unwrap().into_temp_path();
+        *STRONGHOLD.lock().unwrap() = path.as_os_str().into();
+
+        let stronghold_manager = StrongholdManager::create("sup3rSecr3t").unwrap();
+        assert_eq!(stronghold_manager.next_key_index().unwrap(), 1);
+
+        stronghold_manager.generate_keys(1).unwrap();
+        assert_eq!(stronghold_manager.next_key_index().unwrap(), 2);
+
+        // The keys persist across loads of the Stronghold.
+        let stronghold_manager = StrongholdManager::load("sup3rSecr3t").unwrap();
+        assert_eq!(stronghold_manager.next_key_index().unwrap(), 2);
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn test_change_password() {
+        let path = NamedTempFile::new().unwrap().into_temp_path();
+        *STRONGHOLD.lock().unwrap() = path.as_os_str().into();
+
+        let stronghold_manager = StrongholdManager::create("sup3rSecr3t").unwrap();
+
+        let key = Uuid::new_v4();
+        stronghold_manager.insert(key, "test".as_bytes().to_vec()).unwrap();
+
+        let stronghold_manager = stronghold_manager.change_password("n3wSecr3t").unwrap();
+        assert_eq!(
+            stronghold_manager.get(key).unwrap().unwrap(),
+            "test".as_bytes().to_vec()
+        );
+
+        // Only the new password can be used to load the snapshot.
+        assert!(StrongholdManager::load("sup3rSecr3t").is_err());
+        let stronghold_manager = StrongholdManager::load("n3wSecr3t").unwrap();
+        assert_eq!(
+            stronghold_manager.get(key).unwrap().unwrap(),
+            "test".as_bytes().to_vec()
+        );
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn test_restore_snapshot() {
+        let path = NamedTempFile::new().unwrap().into_temp_path();
+        *STRONGHOLD.lock().unwrap() = path.as_os_str().into();
+
+        let stronghold_manager = StrongholdManager::create("sup3rSecr3t").unwrap();
+        let key = Uuid::new_v4();
+        stronghold_manager.insert(key, "test".as_bytes().to_vec()).unwrap();
+        let snapshot = std::fs::read(&path).unwrap();
+
+        let other_path = NamedTempFile::new().unwrap().into_temp_path();
+        *STRONGHOLD.lock().unwrap() = other_path.as_os_str().into();
+        StrongholdManager::create("0therSecr3t").unwrap();
+        let previous_snapshot = std::fs::read(&other_path).unwrap();
+
+        // A snapshot that does not load with the password does not replace the current snapshot.
+        assert!(StrongholdManager::restore_snapshot(&snapshot, "wr0ngSecr3t").is_err());
+        assert!(StrongholdManager::load("0therSecr3t").is_ok());
+        assert!(!temporary_snapshot_file().exists());
+
+        StrongholdManager::restore_snapshot(&snapshot, "sup3rSecr3t").unwrap();
+        let stronghold_manager = StrongholdManager::load("sup3rSecr3t").unwrap();
+        assert_eq!(
+            stronghold_manager.get(key).unwrap().unwrap(),
+            "test".as_bytes().to_vec()
+        );
+
+        // The previous snapshot can be put back.
+        StrongholdManager::revert_snapshot(Some(&previous_snapshot)).unwrap();
+        assert!(StrongholdManager::load("0therSecr3t").is_ok());
+        assert!(StrongholdManager::load("sup3rSecr3t").is_err());
+    }
 }
@@ -1,3 +1,4 @@
+use crate::state::did::did_web::{key_fragment, DID_WEB};
 use crate::stronghold::StrongholdManager;
 
 use async_trait::async_trait;
@@ -6,26 +7,159 @@ use did_manager::{DidMethod, Resolver, SecretManager};
 use identity_iota::{
     did::DID,
     document::DIDUrlQuery,
-    verification::{jwk::JwkParams, jws::JwsAlgorithm},
+    verification::{
+        jwk::{Jwk, JwkParams},
+        jws::JwsAlgorithm,
+    },
 };
 use jsonwebtoken::Algorithm;
 use oid4vc::oid4vc_core::{authentication::sign::ExternalSign, Sign, Verify};
-use std::sync::Arc;
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+use zeroize::Zeroizing;
 
 /// A `Subject` implements functions required for signatures and verification.
 /// In UniMe, it serves as the "binding link" between the protocol libraries (OID4VC) and the secret management (DID Manager).
-#[derive(Debug)]
 pub struct Subject {
     pub stronghold_manager: Arc<StrongholdManager>,
-    pub secret_manager: SecretManager,
+    /// The secret manager of the holder keys at `default_key_index`, which are used for the DIDs of the holder.
+    pub secret_manager: Arc<SecretManager>,
+    /// The index of the holder keys, which changes when the keys are rotated (see `HolderKeys`).
+    pub default_key_index: u32,
+    /// The holder's `did:web` DID, if one has been set up. Its DID document is hosted by the holder.
+    pub did_web: RwLock<Option<String>>,
+    /// The password of the Stronghold, which is needed to load the secret managers of the pairwise keys.
+    pub password: Zeroizing<String>,
+    /// The secret managers of the pairwise keys (`ed25519-N`, `es256-N`, `es256k-N`) by their index, loaded on demand.
+    pub secret_managers: Arc<tokio::sync::RwLock<HashMap<u32, Arc<SecretManager>>>>,
+    /// The index of the keys this subject signs with, which is the `default_key_index` unless this subject is returned
+    /// by `Subject::with_keys`.
+    pub key_index: u32,
+    /// The secret manager of the keys at `key_index`, unless these are the holder keys.
+    pub pairwise_secret_manager: Option<Arc<SecretManager>>,
+}
+
+// The password is left out on purpose.
+impl std::fmt::Debug for Subject {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Subject")
+            .field("stronghold_manager", &self.stronghold_manager)
+            .field("secret_manager", &self.secret_manager)
+            .field("default_key_index", &self.default_key_index)
+            .field("did_web", &self.did_web)
+            .field("key_index", &self.key_index)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Subject {
+    /// Signs arbitrary binary data, such as CBOR structures, which cannot be passed to `Sign::sign` as a string.
+    pub async fn sign_bytes(&self, message: &[u8], algorithm: Algorithm) -> anyhow::Result<Vec<u8>> {
+        Ok(self.keys().sign(message, algorithm.into_jws_algorithm()).await?)
+    }
+
+    /// Returns a subject that signs with the keys at the given index instead, such as the pairwise keys of a
+    /// connection. The keys are generated in Stronghold if they do not exist yet. The keys of this subject itself are
+    /// unaffected, so the returned subject is passed to the `ProviderManager` and `Wallet` of a single connection.
+    pub async fn with_keys(&self, index: u32) -> anyhow::Result<Arc<Subject>> {
+        let pairwise_secret_manager = match index == self.default_key_index {
+            true => None,
+            false => Some(self.pairwise_keys(index).await?),
+        };
+        let did_web = self
+            .did_web
+            .read()
+            .map_err(|_| anyhow::anyhow!("Failed to read the `did:web` DID"))?
+            .clone();
+
+        Ok(Arc::new(Subject {
+            stronghold_manager: self.stronghold_manager.clone(),
+            secret_manager: self.secret_manager.clone(),
+            default_key_index: self.default_key_index,
+            did_web: RwLock::new(did_web),
+            password: self.password.clone(),
+            secret_managers: self.secret_managers.clone(),
+            key_index: index,
+            pairwise_secret_manager,
+        }))
+    }
+
+    // Returns the secret manager of the pairwise keys at the given index, which are generated and loaded if needed.
+    async fn pairwise_keys(&self, index: u32) -> anyhow::Result<Arc<SecretManager>> {
+        if let Some(secret_manager) = self.secret_managers.read().await.get(&index) {
+            return Ok(secret_manager.clone());
+        }
+
+        self.stronghold_manager.generate_keys(index)?;
+
+        let secret_manager = Arc::new(
+            SecretManager::load(
+                stronghold_path()?,
+                self.password.to_string(),
+                Some(format!("ed25519-{index}")),
+                Some(format!("es256-{index}")),
+                Some(format!("es256k-{index}")),
+                None,
+                None,
+            )
+            .await?,
+        );
+        self.secret_managers.write().await.insert(index, secret_manager.clone());
+
+        Ok(secret_manager)
+    }
+
+    // Returns the secret manager of the keys at `key_index`.
+    fn keys(&self) -> &SecretManager {
+        self.pairwise_secret_manager.as_ref().unwrap_or(&self.secret_manager)
+    }
+
+    /// Returns the DID of the given method for the key of the given key type (see `SUPPORTED_KEY_TYPES`). Unlike
+    /// `Subject::identifier`, this also covers key types for which `jsonwebtoken` has no `Algorithm`, such as ES256K.
+    pub async fn identifier_for_key_type(&self, subject_syntax_type: &str, key_type: &str) -> anyhow::Result<String> {
+        let algorithm = key_type_algorithm(key_type).ok_or(anyhow::anyhow!("Unsupported key type: {key_type}"))?;
+        let method: DidMethod = serde_json::from_str(&format!("{subject_syntax_type:?}"))?;
+
+        Ok(self
+            .keys()
+            .produce_document(method, None, algorithm)
+            .await
+            .map(|document| document.id().to_string())?)
+    }
+
+    /// Returns the public key used for the given algorithm as a JWK, as it appears in its `did:jwk` document.
+    pub async fn public_key_jwk(&self, algorithm: Algorithm) -> anyhow::Result<serde_json::Value> {
+        let method: DidMethod = serde_json::from_str("\"did:jwk\"")?;
+
+        let document = self
+            .keys()
+            .produce_document(method, None, algorithm.into_jws_algorithm())
+            .await?;
+
+        let public_key_jwk = document
+            .verification_method()
+            .first()
+            .and_then(|verification_method| verification_method.data().public_key_jwk())
+            .ok_or(anyhow::anyhow!("No public key found for algorithm {algorithm:?}"))?;
+
+        Ok(serde_json::to_value(public_key_jwk)?)
+    }
 }
 
 #[async_trait]
 impl Sign for Subject {
     async fn key_id(&self, subject_syntax_type: &str, algorithm: Algorithm) -> Option<String> {
+        // The DID document of a `did:web` DID is produced by UniMe itself, see `produce_did_web_document`.
+        if subject_syntax_type == DID_WEB {
+            let did = self.did_web.read().ok()?.clone()?;
+            return key_fragment(algorithm, self.default_key_index).map(|fragment| format!("{did}#{fragment}"));
+        }
+
         let method: DidMethod = serde_json::from_str(&format!("{subject_syntax_type:?}")).ok()?;
 
-        self.secret_manager
+        self.keys()
             .produce_document(method, None, algorithm.into_jws_algorithm())
             .await
             .ok()
@@ -33,11 +167,16 @@ impl Sign for Subject {
             .map(|first| first.id().to_string())
     }
 
-    async fn sign(&self, message: &str, _subject_syntax_type: &str, algorithm: Algorithm) -> anyhow::Result<Vec<u8>> {
-        Ok(self
-            .secret_manager
-            .sign(message.as_bytes(), algorithm.into_jws_algorithm())
-            .await?)
+    async fn sign(&self, message: &str, subject_syntax_type: &str, algorithm: Algorithm) -> anyhow::Result<Vec<u8>> {
+        // The DID document of the `did:web` DID only contains the holder keys.
+        if subject_syntax_type == DID_WEB {
+            return Ok(self
+                .secret_manager
+                .sign(message.as_bytes(), algorithm.into_jws_algorithm())
+                .await?);
+        }
+
+        self.sign_bytes(message.as_bytes(), algorithm).await
     }
 
     fn external_signer(&self) -> Option<Arc<dyn ExternalSign>> {
@@ -48,10 +187,19 @@ impl Sign for Subject {
 #[async_trait]
 impl oid4vc::oid4vc_core::Subject for Subject {
     async fn identifier(&self, subject_syntax_type: &str, algorithm: Algorithm) -> anyhow::Result<String> {
+        if subject_syntax_type == DID_WEB {
+            return self
+                .did_web
+                .read()
+                .map_err(|_| anyhow::anyhow!("Failed to read the `did:web` DID"))?
+                .clone()
+                .ok_or(anyhow::anyhow!("No `did:web` DID has been set up"));
+        }
+
         let method: DidMethod = serde_json::from_str(&format!("{subject_syntax_type:?}"))?;
 
         Ok(self
-            .secret_manager
+            .keys()
             .produce_document(method, None, algorithm.into_jws_algorithm())
             .await
             .map(|document| document.id().to_string())?)
@@ -61,73 +209,114 @@ impl oid4vc::oid4vc_core::Subject for Subject {
 #[async_trait]
 impl Verify for Subject {
     async fn public_key(&self, did_url: &str) -> anyhow::Result<Vec<u8>> {
-        let did_url = identity_iota::did::DIDUrl::parse(did_url).unwrap();
+        let did_url = identity_iota::did::DIDUrl::parse(did_url)?;
 
         let resolver = Resolver::new().await;
 
-        let document = resolver.resolve(did_url.did().as_str()).await.unwrap();
+        let document = resolver.resolve(did_url.did().as_str()).await?;
 
         let verification_method = document
             .resolve_method(
                 DIDUrlQuery::from(&did_url),
                 Some(identity_iota::verification::MethodScope::VerificationMethod),
             )
-            .unwrap();
+            .ok_or(anyhow::anyhow!("Failed to resolve verification method: {}", did_url))?;
 
         // Try decode from `MethodData` directly, else use public JWK params.
         verification_method.data().try_decode().or_else(|_| {
             verification_method
                 .data()
                 .public_key_jwk()
-                .and_then(|public_key_jwk| match public_key_jwk.params() {
-                    JwkParams::Okp(okp_params) => URL_SAFE_NO_PAD.decode(&okp_params.x).ok(),
-                    JwkParams::Ec(ec_params) => {
-                        let x_bytes = URL_SAFE_NO_PAD.decode(&ec_params.x).ok()?;
-                        let y_bytes = URL_SAFE_NO_PAD.decode(&ec_params.y).ok()?;
-
-                        let encoded_point = p256::EncodedPoint::from_affine_coordinates(
-                            p256::FieldBytes::from_slice(&x_bytes),
-                            p256::FieldBytes::from_slice(&y_bytes),
-                            false, // false for uncompressed point
-                        );
-
-                        let verifying_key = p256::ecdsa::VerifyingKey::from_encoded_point(&encoded_point)
-                            .expect("Failed to create verifying key from encoded point");
-
-                        Some(verifying_key.to_encoded_point(false).as_bytes().to_vec())
-                    }
-                    _ => None,
-                })
+                .and_then(public_key_bytes)
                 .ok_or(anyhow::anyhow!("Failed to decode public key for DID URL: {}", did_url))
         })
     }
 }
 
-// Helper function: load a `Subject`
-pub async fn subject(stronghold_manager: Arc<StrongholdManager>, password: String) -> Arc<Subject> {
-    let client_path = crate::persistence::STRONGHOLD
+// Returns the raw public key of the given JWK: the `x` parameter of an OKP key, or the uncompressed SEC1 encoding
+// (`0x04 || x || y`) of an EC key.
+pub(crate) fn public_key_bytes(public_key_jwk: &Jwk) -> Option<Vec<u8>> {
+    match public_key_jwk.params() {
+        JwkParams::Okp(okp_params) => URL_SAFE_NO_PAD.decode(&okp_params.x).ok(),
+        JwkParams::Ec(ec_params) => {
+            let x_bytes = URL_SAFE_NO_PAD.decode(&ec_params.x).ok()?;
+            let y_bytes = URL_SAFE_NO_PAD.decode(&ec_params.y).ok()?;
+
+            let coordinate_length = match ec_params.crv.as_str() {
+                "P-256" | "secp256k1" => 32,
+                "P-384" => 48,
+                _ => return None,
+            };
+            if x_bytes.len() != coordinate_length || y_bytes.len() != coordinate_length {
+                return None;
+            }
+
+            match ec_params.crv.as_str() {
+                "P-256" => {
+                    let encoded_point = p256::EncodedPoint::from_affine_coordinates(
+                        p256::FieldBytes::from_slice(&x_bytes),
+                        p256::FieldBytes::from_slice(&y_bytes),
+                        false, // false for uncompressed point
+                    );
+
+                    let verifying_key = p256::ecdsa::VerifyingKey::from_encoded_point(&encoded_point).ok()?;
+
+                    Some(verifying_key.to_encoded_point(false).as_bytes().to_vec())
+                }
+                _ => Some([vec![0x04], x_bytes, y_bytes].concat()),
+            }
+        }
+        _ => None,
+    }
+}
+
+fn stronghold_path() -> anyhow::Result<String> {
+    Ok(crate::persistence::STRONGHOLD
         .lock()
         .unwrap()
         .to_str()
-        .expect("failed to get stronghold path")
-        .to_owned();
+        .ok_or(anyhow::anyhow!("failed to get stronghold path"))?
+        .to_owned())
+}
+
+// Helper function: load a `Subject` with the holder keys at the given index
+pub async fn subject(stronghold_manager: Arc<StrongholdManager>, password: String, key_index: u32) -> Arc<Subject> {
+    let client_path = stronghold_path().expect("failed to get stronghold path");
 
     Arc::new(Subject {
         stronghold_manager: stronghold_manager.clone(),
-        secret_manager: SecretManager::load(
-            client_path,
-            password,
-            Some("ed25519-0".to_owned()),
-            Some("es256-0".to_owned()),
-            Some("es256k-0".to_owned()),
-            None,
-            None,
-        )
-        .await
-        .unwrap(),
+        secret_manager: Arc::new(
+            SecretManager::load(
+                client_path,
+                password.clone(),
+                Some(format!("ed25519-{key_index}")),
+                Some(format!("es256-{key_index}")),
+                Some(format!("es256k-{key_index}")),
+                None,
+                None,
+            )
+            .await
+            .unwrap(),
+        ),
+        default_key_index: key_index,
+        did_web: RwLock::new(None),
+        password: Zeroizing::new(password),
+        secret_managers: Default::default(),
+        key_index,
+        pairwise_secret_manager: None,
     })
 }
 
+/// Returns the JWS algorithm of the given key type (see `SUPPORTED_KEY_TYPES`).
+pub fn key_type_algorithm(key_type: &str) -> Option<JwsAlgorithm> {
+    match key_type {
+        "EdDSA" => Some(JwsAlgorithm::EdDSA),
+        "ES256" => Some(JwsAlgorithm::ES256),
+        "ES256K" => Some(JwsAlgorithm::ES256K),
+        _ => None,
+    }
+}
+
 trait IntoJwsAlgorithm {
     fn into_jws_algorithm(self) -> JwsAlgorithm;
 }
@@ -150,3 +339,50 @@ impl IntoJwsAlgorithm for Algorithm {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    // The base points of P-256 and secp256k1.
+    const P256_X: &str = "axfR8uEsQkf4vOblY6RA8ncDfYEt6zOg9KE5RdiYwpY";
+    const P256_Y: &str = "T-NC4v4af5uO5-tKfA-eFivOM1drMV7Oy7ZAaDe_UfU";
+    const SECP256K1_X: &str = "eb5mfvncu6xVoGKVzocLBwKb_NstzijZWfKBWxb4F5g";
+    const SECP256K1_Y: &str = "SDradyajxGVdpPv8DhEIqP0XtEimhVQZnEfQj_sQ1Lg";
+
+    fn ec_jwk(crv: &str, x: &str, y: &str) -> Jwk {
+        serde_json::from_value(json!({ "kty": "EC", "crv": crv, "x": x, "y": y })).unwrap()
+    }
+
+    fn uncompressed_point(x: &str, y: &str) -> Vec<u8> {
+        [
+            vec![0x04],
+            URL_SAFE_NO_PAD.decode(x).unwrap(),
+            URL_SAFE_NO_PAD.decode(y).unwrap(),
+        ]
+        .concat()
+    }
+
+    #[test]
+    fn ec_public_keys_are_decoded_as_uncompressed_points() {
+        assert_eq!(
+            public_key_bytes(&ec_jwk("P-256", P256_X, P256_Y)),
+            Some(uncompressed_point(P256_X, P256_Y))
+        );
+        assert_eq!(
+            public_key_bytes(&ec_jwk("secp256k1", SECP256K1_X, SECP256K1_Y)),
+            Some(uncompressed_point(SECP256K1_X, SECP256K1_Y))
+        );
+    }
+
+    #[test]
+    fn ec_public_keys_with_coordinates_of_the_wrong_length_are_rejected() {
+        // The last byte of the x coordinate is missing.
+        let short_x = "axfR8uEsQkf4vOblY6RA8ncDfYEt6zOg9KE5RdiYwg";
+
+        assert_eq!(public_key_bytes(&ec_jwk("P-256", short_x, P256_Y)), None);
+        assert_eq!(public_key_bytes(&ec_jwk("secp256k1", short_x, SECP256K1_Y)), None);
+        assert_eq!(public_key_bytes(&ec_jwk("P-384", P256_X, P256_Y)), None);
+    }
+}
@@ -54,17 +54,25 @@ pub async fn test_managers(
 
     let subject: Arc<Subject> = Arc::new(Subject {
         stronghold_manager: stronghold_manager.clone(),
-        secret_manager: SecretManager::load(
-            stronghold_snapshot_path,
-            TEST_PASSWORD.to_string(),
-            Some(KEY_ID.to_string()),
-            None,
-            None,
-            None,
-            None,
-        )
-        .await
-        .unwrap(),
+        secret_manager: Arc::new(
+            SecretManager::load(
+                stronghold_snapshot_path,
+                TEST_PASSWORD.to_string(),
+                Some(KEY_ID.to_string()),
+                None,
+                None,
+                None,
+                None,
+            )
+            .await
+            .unwrap(),
+        ),
+        default_key_index: 0,
+        did_web: Default::default(),
+        password: TEST_PASSWORD.to_string().into(),
+        secret_managers: Default::default(),
+        key_index: 0,
+        pairwise_secret_manager: None,
     });
 
     let provider_manager = ProviderManager::new(
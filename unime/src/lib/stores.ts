@@ -12,6 +12,7 @@ const empty_state: AppState = {
   dids: {},
   connections: [],
   credentials: [],
+  pending_credentials: [],
   search_results: {
     current: [],
     recent_credentials: [],